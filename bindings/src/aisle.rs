@@ -1,8 +1,11 @@
 use std::collections::HashMap;
+use std::sync::Arc;
 
-use cooklang::aisle::Category as OriginalAisleCategory;
+use cooklang::aisle::{
+    Category as OriginalAisleCategory, Ingredient as OriginalAisleIngredient, PANTRY_CATEGORY,
+};
 
-#[derive(uniffi::Record, Debug, Clone)]
+#[derive(uniffi::Record, Debug, Clone, PartialEq)]
 pub struct AisleIngredient {
     pub name: String,
     pub aliases: Vec<String>,
@@ -10,22 +13,192 @@ pub struct AisleIngredient {
 
 pub type AisleReverseCategory = HashMap<String, String>;
 
-#[derive(uniffi::Record, Debug, Clone)]
+#[derive(uniffi::Record, Debug, Clone, PartialEq)]
 pub struct AisleCategory {
     pub name: String,
     pub ingredients: Vec<AisleIngredient>,
+    pub icon: Option<String>,
+    pub order: Option<i32>,
 }
 
 #[derive(uniffi::Object, Debug, Clone)]
 pub struct AisleConf {
     pub categories: Vec<AisleCategory>, // cache for quick category search
     pub cache: AisleReverseCategory,
+    /// [`Self::cache`] with the names in singular
+    singular: AisleReverseCategory,
 }
 
 #[uniffi::export]
 impl AisleConf {
+    /// Creates a config from its categories, like the ones edited from
+    /// [`AisleConf::categories`]
+    #[uniffi::constructor]
+    pub fn from_categories(categories: Vec<AisleCategory>) -> Arc<Self> {
+        let mut cache = AisleReverseCategory::default();
+        for category in &categories {
+            for ingredient in &category.ingredients {
+                // an ingredient in more than one category stays in the first
+                for name in std::iter::once(&ingredient.name).chain(&ingredient.aliases) {
+                    cache
+                        .entry(name.clone())
+                        .or_insert_with(|| category.name.clone());
+                }
+            }
+        }
+        let singular = normalized_cache(&cache, english_singular);
+        Arc::new(Self {
+            categories,
+            cache,
+            singular,
+        })
+    }
+
+    /// The categories with their ingredients, in the order of the file
+    ///
+    /// The first name of each ingredient is the [`AisleIngredient::name`] and
+    /// the rest are the aliases. See [`render_aisle_config`](crate::render_aisle_config)
+    /// to write them back.
+    pub fn categories(&self) -> Vec<AisleCategory> {
+        self.categories.clone()
+    }
+
+    /// Category of an ingredient
+    ///
+    /// If there is no exact match, the names are compared in singular with a
+    /// simple English heuristic, so `tomatoes` finds `tomato`. Use an
+    /// [`AisleIndex`] for other languages.
+    pub fn category_for(&self, ingredient_name: String) -> Option<String> {
+        lookup(
+            &self.cache,
+            &self.singular,
+            &ingredient_name,
+            english_singular,
+        )
+    }
+
+    /// If an ingredient is in the `pantry` category
+    ///
+    /// The name is matched like in [`AisleConf::category_for`], so `eggs`
+    /// is a staple if `egg` is in the pantry.
+    pub fn is_pantry_staple(&self, ingredient_name: String) -> bool {
+        is_pantry(self.category_for(ingredient_name).as_deref())
+    }
+
+    /// Names of the ingredients in the `pantry` category, including aliases
+    ///
+    /// These are the staples usually at home, like salt or water. The result
+    /// is sorted by name.
+    pub fn pantry_staples(&self) -> Vec<String> {
+        let mut staples = self
+            .cache
+            .iter()
+            .filter(|(_, category)| is_pantry(Some(category)))
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>();
+        staples.sort();
+        staples
+    }
+}
+
+/// An [`AisleConf`] that matches names with a custom [`NameNormalizer`]
+///
+/// The names in the config are normalized once when the index is built, so
+/// a lookup only normalizes the name looked up.
+#[derive(uniffi::Object)]
+pub struct AisleIndex {
+    cache: AisleReverseCategory,
+    normalized: AisleReverseCategory,
+    normalizer: Arc<dyn NameNormalizer>,
+}
+
+#[uniffi::export]
+impl AisleIndex {
+    #[uniffi::constructor]
+    pub fn new(conf: Arc<AisleConf>, normalizer: Arc<dyn NameNormalizer>) -> Arc<Self> {
+        let normalized =
+            normalized_cache(&conf.cache, |name| normalizer.normalize(name.to_string()));
+        Arc::new(Self {
+            cache: conf.cache.clone(),
+            normalized,
+            normalizer,
+        })
+    }
+
+    /// Category of an ingredient
+    ///
+    /// If there is no exact match, the names are compared after passing them
+    /// through the normalizer.
     pub fn category_for(&self, ingredient_name: String) -> Option<String> {
-        self.cache.get(&ingredient_name).cloned()
+        lookup(&self.cache, &self.normalized, &ingredient_name, |name| {
+            self.normalizer.normalize(name.to_string())
+        })
+    }
+
+    /// If an ingredient is in the `pantry` category, matched like in
+    /// [`AisleIndex::category_for`]
+    pub fn is_pantry_staple(&self, ingredient_name: String) -> bool {
+        is_pantry(self.category_for(ingredient_name).as_deref())
+    }
+}
+
+fn lookup(
+    cache: &AisleReverseCategory,
+    normalized: &AisleReverseCategory,
+    name: &str,
+    normalize: impl Fn(&str) -> String,
+) -> Option<String> {
+    cache
+        .get(name)
+        .or_else(|| normalized.get(&normalize(name)))
+        .cloned()
+}
+
+/// The cache with the names passed through `normalize`
+fn normalized_cache(
+    cache: &AisleReverseCategory,
+    normalize: impl Fn(&str) -> String,
+) -> AisleReverseCategory {
+    // sorted so the result doesn't depend on the map order when more than
+    // one name normalizes to the same
+    let mut names = cache.iter().collect::<Vec<_>>();
+    names.sort();
+    let mut normalized = AisleReverseCategory::default();
+    for (name, category) in names {
+        normalized
+            .entry(normalize(name))
+            .or_insert_with(|| category.clone());
+    }
+    normalized
+}
+
+fn is_pantry(category: Option<&str>) -> bool {
+    category.is_some_and(|category| category.eq_ignore_ascii_case(PANTRY_CATEGORY))
+}
+
+/// Normalizes ingredient names to match them with an [`AisleConf`]
+///
+/// Usually this turns plurals into singular, which is language dependent.
+#[uniffi::export(with_foreign)]
+pub trait NameNormalizer: Send + Sync {
+    fn normalize(&self, name: String) -> String;
+}
+
+/// Naive English singular: `berries` to `berry`, `tomatoes` to `tomato` and
+/// `apples` to `apple`. Also lowercases the name.
+pub(crate) fn english_singular(name: &str) -> String {
+    let name = name.trim().to_lowercase();
+    if let Some(stem) = name.strip_suffix("ies") {
+        format!("{stem}y")
+    } else if ["oes", "ches", "shes", "sses", "xes"]
+        .iter()
+        .any(|suffix| name.ends_with(suffix))
+    {
+        name[..name.len() - 2].to_string()
+    } else if name.ends_with('s') && !name.ends_with("ss") {
+        name[..name.len() - 1].to_string()
+    } else {
+        name
     }
 }
 
@@ -44,5 +217,27 @@ pub fn into_category(original: &OriginalAisleCategory) -> AisleCategory {
     AisleCategory {
         name: original.name.to_string(),
         ingredients,
+        icon: original.icon.map(|i| i.to_string()),
+        order: original.order,
+    }
+}
+
+pub fn into_original_category(category: &AisleCategory) -> OriginalAisleCategory<'_> {
+    let ingredients = category
+        .ingredients
+        .iter()
+        .map(|i| OriginalAisleIngredient {
+            names: std::iter::once(&i.name)
+                .chain(&i.aliases)
+                .map(String::as_str)
+                .collect(),
+        })
+        .collect();
+
+    OriginalAisleCategory {
+        name: &category.name,
+        ingredients,
+        icon: category.icon.as_deref(),
+        order: category.order,
     }
 }
@@ -0,0 +1,289 @@
+//! Build recipes from code and write them as Cooklang
+//!
+//! [`RecipeBuilder`] creates a [`CooklangRecipe`] without parsing any text,
+//! and [`render_cooklang`] writes any recipe back to the `.cook` format.
+
+use std::sync::{Arc, Mutex};
+
+use crate::model::{
+    add_to_ingredient_list, into_group_quantity, into_original_quantity, Amount, CooklangMetadata,
+    CooklangRecipe, IngredientList, Item, Section, Step, Value,
+};
+
+/// Builds a [`CooklangRecipe`] step by step
+///
+/// Steps are added to the last section. Every method returns the builder so
+/// calls can be chained. The step actions are not detected, they are always
+/// empty.
+#[derive(uniffi::Object, Debug, Default)]
+pub struct RecipeBuilder {
+    state: Mutex<BuilderState>,
+}
+
+#[derive(Debug, Default)]
+struct BuilderState {
+    metadata: CooklangMetadata,
+    sections: Vec<Section>,
+}
+
+#[uniffi::export]
+impl RecipeBuilder {
+    #[uniffi::constructor]
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    /// Sets a metadata entry, replacing the value if the key exists
+    pub fn metadata(self: Arc<Self>, key: String, value: String) -> Arc<Self> {
+        self.state.lock().unwrap().metadata.insert(key, value);
+        self
+    }
+
+    /// Starts a new section
+    pub fn section(self: Arc<Self>, name: Option<String>) -> Arc<Self> {
+        self.state.lock().unwrap().sections.push(Section {
+            name,
+            steps: Vec::new(),
+            yields: None,
+        });
+        self
+    }
+
+    /// Adds a step to the last section
+    pub fn step(self: Arc<Self>, items: Vec<Item>) -> Arc<Self> {
+        self.step_with_note(items, None)
+    }
+
+    /// Adds a step with a note to the last section
+    pub fn step_with_note(self: Arc<Self>, items: Vec<Item>, note: Option<String>) -> Arc<Self> {
+        let mut state = self.state.lock().unwrap();
+        if state.sections.is_empty() {
+            state.sections.push(Section {
+                name: None,
+                steps: Vec::new(),
+                yields: None,
+            });
+        }
+        state.sections.last_mut().unwrap().steps.push(Step {
+            items,
+            actions: Vec::new(),
+            note,
+            has_temperature: false,
+        });
+        drop(state);
+        self
+    }
+
+    /// Creates the recipe
+    ///
+    /// Like when parsing, `sections` is left empty if there is only one
+    /// section without a name.
+    pub fn build(&self) -> CooklangRecipe {
+        let state = self.state.lock().unwrap();
+        let mut ingredients = IngredientList::default();
+        let mut ingredient_order = Vec::new();
+        let mut cookware = Vec::new();
+        let steps = state
+            .sections
+            .iter()
+            .flat_map(|s| s.steps.iter().cloned())
+            .collect::<Vec<_>>();
+
+        for item in steps.iter().flat_map(|s| &s.items) {
+            match item {
+                Item::Ingredient {
+                    name,
+                    amount,
+                    hidden: false,
+                    ..
+                } => {
+                    if !ingredients.contains_key(name) {
+                        ingredient_order.push(name.clone());
+                    }
+                    add_to_ingredient_list(&mut ingredients, name, &into_group_quantity(amount));
+                }
+                Item::Cookware { .. } => cookware.push(item.clone()),
+                _ => {}
+            }
+        }
+
+        let sections = if state.sections.len() == 1 && state.sections[0].name.is_none() {
+            Vec::new()
+        } else {
+            state.sections.clone()
+        };
+
+        CooklangRecipe {
+            metadata: state.metadata.clone(),
+            steps,
+            ingredients,
+            ingredient_order,
+            cookware,
+            sections,
+        }
+    }
+}
+
+/// Writes a recipe in the Cooklang format
+///
+/// Metadata is written first, sorted by key. Components always use braces,
+/// like `@salt{}`, and amounts use the [`Amount::raw`] text when available.
+/// Text that would be read as Cooklang syntax is escaped.
+#[uniffi::export]
+pub fn render_cooklang(recipe: CooklangRecipe) -> String {
+    let mut out = String::new();
+
+    let mut metadata = recipe.metadata.iter().collect::<Vec<_>>();
+    metadata.sort();
+    for (key, value) in &metadata {
+        out += &format!(">> {key}: {value}\n");
+    }
+    if !metadata.is_empty() {
+        out += "\n";
+    }
+
+    let sections = if recipe.sections.is_empty() {
+        vec![(None, None, recipe.steps.as_slice())]
+    } else {
+        recipe
+            .sections
+            .iter()
+            .map(|s| (s.name.as_deref(), s.yields.as_ref(), s.steps.as_slice()))
+            .collect()
+    };
+    for (index, (name, yields, steps)) in sections.into_iter().enumerate() {
+        let yields = yields
+            .map(|amount| format!(" {{{}}}", render_amount(&Some(amount.clone()))))
+            .unwrap_or_default();
+        match name {
+            Some(name) => out += &format!("== {}{yields} ==\n\n", name.replace('=', "\\=")),
+            None if index > 0 || !yields.is_empty() => out += &format!("=={yields} ==\n\n"),
+            None => {}
+        }
+        for step in steps {
+            out += &render_step(step);
+            out += "\n\n";
+        }
+    }
+
+    let len = out.trim_end().len();
+    out.truncate(len);
+    out.push('\n');
+    out
+}
+
+fn render_step(step: &Step) -> String {
+    let mut out = String::new();
+    for item in &step.items {
+        match item {
+            Item::Text { value } | Item::Link { text: value, .. } => out += &escape_text(value),
+            Item::Ingredient { .. } => out += &format_ingredient(item.clone()),
+            Item::Cookware { name, amount } => {
+                out += &format!("#{name}{{{}}}", render_amount(amount));
+            }
+            Item::Timer { name, amount } => {
+                let name = name.as_deref().unwrap_or_default();
+                out += &format!("~{name}{{{}}}", render_amount(amount));
+            }
+        }
+    }
+    if let Some(note) = &step.note {
+        out += " > ";
+        out += &escape_text(note);
+    }
+    let mut out = out.split_whitespace().collect::<Vec<_>>().join(" ");
+    // a step that starts like a section
+    if out.starts_with('=') {
+        out.insert(0, '\\');
+    }
+    out
+}
+
+/// Writes an ingredient in the Cooklang format
+///
+/// The result is `@name|alias{quantity%unit}(note)[#order]`, with the
+/// modifiers before the name, like `@&-salt{}`, and only the parts the
+/// ingredient has. The
+/// braces are always written, like in [`render_cooklang`]. Any other item
+/// returns an empty string.
+#[uniffi::export]
+pub fn format_ingredient(item: Item) -> String {
+    let Item::Ingredient {
+        name,
+        amount,
+        prep_order,
+        hidden,
+        reference,
+        optional,
+        recipe,
+        new,
+        alias,
+        note,
+    } = item
+    else {
+        return String::new();
+    };
+    let mut out = String::from("@");
+    for (set, modifier) in [
+        (recipe, '@'),
+        (reference, '&'),
+        (optional, '?'),
+        (hidden, '-'),
+        (new, '+'),
+    ] {
+        if set {
+            out.push(modifier);
+        }
+    }
+    out += &name;
+    if let Some(alias) = alias {
+        out += &format!("|{alias}");
+    }
+    out += &format!("{{{}}}", render_amount(&amount));
+    if let Some(note) = note {
+        out += &format!("({note})");
+    }
+    if let Some(order) = prep_order {
+        out += &format!("[#{order}]");
+    }
+    out
+}
+
+fn render_amount(amount: &Option<Amount>) -> String {
+    let Some(amount) = amount else {
+        return String::new();
+    };
+    let value = match (&amount.raw, &amount.quantity) {
+        (Some(raw), _) => raw.clone(),
+        (None, Value::Empty) => String::new(),
+        (None, _) => into_original_quantity(amount)
+            .map(|q| q.value.to_string())
+            .unwrap_or_default(),
+    };
+    let value = if amount.approximate {
+        format!("~{value}")
+    } else {
+        value
+    };
+    match &amount.units {
+        Some(units) if amount.unit_locked => format!("{value}%{units}!"),
+        Some(units) => format!("{value}%{units}"),
+        None => value,
+    }
+}
+
+/// Escapes the characters that would start a component, a note or a comment
+fn escape_text(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let next = chars.peek().copied();
+        if matches!(c, '@' | '#' | '~' | '>' | '\\')
+            || (matches!(c, '-' | '[') && next == Some('-'))
+        {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
@@ -0,0 +1,176 @@
+//! Canonical form of a recipe, to find duplicates in a collection
+//!
+//! Two recipes that only differ in how they are written, like the case of
+//! the ingredient names, the unit names or the spacing, canonicalize to equal
+//! recipes.
+
+use std::collections::HashSet;
+
+use cooklang::{text::collapse_whitespace, Converter};
+
+use crate::model::{
+    add_to_ingredient_list, Amount, CooklangRecipe, GroupedQuantity, GroupedQuantityKey,
+    IngredientList, Item, Section, Step, Value,
+};
+
+/// Returns the recipe in a canonical form
+///
+/// This is what is normalized:
+/// - Ingredient names are lowercased, in `ingredients`, `ingredient_order`
+///   and the steps. Ingredients that end up with the same name are merged,
+///   and so are their quantities with the same unit.
+/// - Units are replaced by their symbol in the bundled units, like `g` for
+///   `grams`. Unknown units are lowercased.
+/// - Whitespace is collapsed to a single space, or a single line break if it
+///   has one, in all the text: steps, notes, names, text quantities and
+///   metadata. It is also trimmed, except between the items of a step.
+/// - The raw quantities, [`Amount::raw`], are removed.
+///
+/// The metadata, like every map in the recipe, has no order, so the order
+/// the keys were written in never matters. Cookware and timer names keep
+/// their case.
+#[uniffi::export]
+pub fn canonicalize(recipe: CooklangRecipe) -> CooklangRecipe {
+    let converter = Converter::bundled();
+
+    let metadata = recipe
+        .metadata
+        .iter()
+        .map(|(key, value)| (text(key), text(value)))
+        .collect();
+
+    let mut ingredients = IngredientList::new();
+    for (name, quantity) in &recipe.ingredients {
+        let name = ingredient_name(name);
+        if quantity.is_empty() {
+            add_to_ingredient_list(&mut ingredients, &name, quantity);
+        }
+        for (key, value) in quantity {
+            let key = GroupedQuantityKey {
+                name: unit(&key.name, &converter),
+                unit_type: key.unit_type.clone(),
+            };
+            let mut value = value.clone();
+            canonical_value(&mut value);
+            add_to_ingredient_list(
+                &mut ingredients,
+                &name,
+                &GroupedQuantity::from([(key, value)]),
+            );
+        }
+    }
+
+    let mut seen = HashSet::new();
+    let ingredient_order = recipe
+        .ingredient_order
+        .iter()
+        .map(|name| ingredient_name(name))
+        .filter(|name| seen.insert(name.clone()))
+        .collect();
+
+    let mut steps = recipe.steps;
+    steps.iter_mut().for_each(|s| canonical_step(s, &converter));
+    let mut cookware = recipe.cookware;
+    cookware
+        .iter_mut()
+        .for_each(|i| canonical_item(i, &converter));
+    let sections = recipe
+        .sections
+        .into_iter()
+        .map(|mut section| {
+            section
+                .steps
+                .iter_mut()
+                .for_each(|s| canonical_step(s, &converter));
+            Section {
+                name: section.name.as_deref().map(text),
+                yields: section.yields.map(|a| canonical_amount(a, &converter)),
+                steps: section.steps,
+            }
+        })
+        .collect();
+
+    CooklangRecipe {
+        metadata,
+        steps,
+        ingredients,
+        ingredient_order,
+        cookware,
+        sections,
+    }
+}
+
+fn canonical_step(step: &mut Step, converter: &Converter) {
+    step.items
+        .iter_mut()
+        .for_each(|i| canonical_item(i, converter));
+    if let Some(Item::Text { value }) = step.items.first_mut() {
+        *value = value.trim_start().to_string();
+    }
+    if let Some(Item::Text { value }) = step.items.last_mut() {
+        *value = value.trim_end().to_string();
+    }
+    step.items
+        .retain(|i| !matches!(i, Item::Text { value } if value.is_empty()));
+    step.note = step.note.as_deref().map(text);
+}
+
+fn canonical_item(item: &mut Item, converter: &Converter) {
+    match item {
+        // spaces around the components are kept
+        Item::Text { value } => *value = collapse_whitespace(value),
+        Item::Link { .. } => {}
+        Item::Ingredient {
+            name,
+            amount,
+            alias,
+            note,
+            ..
+        } => {
+            *name = ingredient_name(name);
+            *amount = amount.take().map(|a| canonical_amount(a, converter));
+            *alias = alias.as_deref().map(text);
+            *note = note.as_deref().map(text);
+        }
+        Item::Cookware { name, amount } => {
+            *name = text(name);
+            *amount = amount.take().map(|a| canonical_amount(a, converter));
+        }
+        Item::Timer { name, amount } => {
+            *name = name.as_deref().map(text);
+            *amount = amount.take().map(|a| canonical_amount(a, converter));
+        }
+    }
+}
+
+fn canonical_amount(mut amount: Amount, converter: &Converter) -> Amount {
+    canonical_value(&mut amount.quantity);
+    amount.units = amount
+        .units
+        .map(|u| unit(&u, converter))
+        .filter(|u| !u.is_empty());
+    amount.raw = None;
+    amount
+}
+
+fn canonical_value(value: &mut Value) {
+    if let Value::Text { value } = value {
+        *value = text(value);
+    }
+}
+
+fn ingredient_name(name: &str) -> String {
+    text(name).to_lowercase()
+}
+
+fn unit(unit: &str, converter: &Converter) -> String {
+    let unit = text(unit);
+    match converter.find_unit(&unit) {
+        Some(known) => known.symbol().to_string(),
+        None => unit.to_lowercase(),
+    }
+}
+
+fn text(s: &str) -> String {
+    collapse_whitespace(s).trim().to_string()
+}
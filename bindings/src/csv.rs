@@ -0,0 +1,54 @@
+//! Export an ingredient list as CSV
+//!
+//! The first row is the header, `name,quantity,unit`, and then there is a
+//! row for every amount of each ingredient, sorted by name and unit. Ranges
+//! are written like `2-3`, text amounts as the text and empty amounts as an
+//! empty field.
+
+use crate::mealmaster::type_order;
+use crate::model::{IngredientList, Value};
+
+/// Converts an ingredient list to CSV
+#[uniffi::export]
+pub fn ingredients_to_csv(list: IngredientList) -> String {
+    let mut out = String::from("name,quantity,unit\n");
+
+    let mut names = list.keys().collect::<Vec<_>>();
+    names.sort();
+    for name in names {
+        let mut quantities = list[name].iter().collect::<Vec<_>>();
+        quantities.sort_by(|(a, _), (b, _)| {
+            (a.name.as_str(), type_order(&a.unit_type))
+                .cmp(&(b.name.as_str(), type_order(&b.unit_type)))
+        });
+        for (key, value) in quantities {
+            let quantity = match value {
+                Value::Number { value } => number(*value),
+                Value::Range { start, end } => format!("{}-{}", number(*start), number(*end)),
+                Value::Text { value } => value.clone(),
+                Value::Empty => String::new(),
+            };
+            out += &format!(
+                "{},{},{}\n",
+                field(name),
+                field(&quantity),
+                field(&key.name)
+            );
+        }
+    }
+    out
+}
+
+fn number(value: f64) -> String {
+    let rounded = (value * 1000.0).round() / 1000.0;
+    format!("{rounded}")
+}
+
+/// Quotes the field if it has a comma, a quote or a line break
+fn field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
@@ -0,0 +1,57 @@
+//! Errors of the fallible functions
+
+use cooklang::aisle::AisleConfError;
+use cooklang::convert::ConvertError;
+use cooklang::error::SourceReport;
+
+/// Error returned by the functions that can fail, so hosts can handle each
+/// kind without reading the message
+#[derive(uniffi::Error, thiserror::Error, Debug, Clone, PartialEq)]
+pub enum CooklangError {
+    /// The recipe has errors and can't be parsed
+    #[error("Error parsing the recipe: {message}")]
+    Parse { message: String },
+    /// The recipe has no servings to scale it from
+    #[error("The recipe doesn't have the servings it makes")]
+    NoServings,
+    /// The aisle config can't be parsed
+    #[error("Error parsing the aisle config: {message}")]
+    Aisle { message: String },
+    /// An amount can't be converted, like to an unknown unit or from a text
+    /// value
+    #[error("Error converting: {message}")]
+    Conversion { message: String },
+}
+
+impl From<SourceReport> for CooklangError {
+    fn from(report: SourceReport) -> Self {
+        CooklangError::Parse {
+            message: report_message(&report),
+        }
+    }
+}
+
+/// The errors of a report, one per line
+pub(crate) fn report_message(report: &SourceReport) -> String {
+    report
+        .errors()
+        .map(|e| e.to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+impl From<AisleConfError> for CooklangError {
+    fn from(err: AisleConfError) -> Self {
+        CooklangError::Aisle {
+            message: err.to_string(),
+        }
+    }
+}
+
+impl From<ConvertError> for CooklangError {
+    fn from(err: ConvertError) -> Self {
+        CooklangError::Conversion {
+            message: err.to_string(),
+        }
+    }
+}
@@ -1,47 +1,609 @@
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
-use cooklang::aisle::parse as parse_aisle_config_original;
+use cooklang::actions::ActionVerbs;
+use cooklang::aisle::parse_lenient as parse_aisle_config_original;
 use cooklang::analysis::parse_events;
+use cooklang::heat::HeatKeywords;
+use cooklang::ingredient_list::{
+    combine_ingredients, shared_ingredients as shared_ingredients_original,
+    shopping_list_for_plan as shopping_list_for_plan_original,
+};
+use cooklang::language::guess_language;
 use cooklang::parser::PullParser;
-use cooklang::{Converter, Extensions};
+use cooklang::quantity::{to_ascii, FormatOptions, NumberLocale, ScalableValue};
+use cooklang::timers::{sentence_around, ItemText};
+use cooklang::{Converter, CooklangParser, Extensions};
 
 pub mod aisle;
+pub mod builder;
+pub mod canonical;
+pub mod csv;
+pub mod error;
+pub mod mealmaster;
 pub mod model;
+pub mod units;
 
 use aisle::*;
+pub use builder::{format_ingredient, render_cooklang, RecipeBuilder};
+pub use canonical::canonicalize;
+pub use csv::ingredients_to_csv;
+use error::report_message;
+pub use error::CooklangError;
+pub use mealmaster::to_mealmaster;
 use model::*;
+use units::UnitConverter;
 
+/// Parses a recipe
+///
+/// Fails with [`CooklangError::Parse`] when the recipe has errors that leave
+/// nothing to return. Other errors, like a reference to an ingredient that
+/// doesn't exist, still return the recipe.
 #[uniffi::export]
-pub fn parse_recipe(input: String) -> CooklangRecipe {
-    let extensions = Extensions::empty();
+pub fn parse_recipe(input: String) -> Result<CooklangRecipe, CooklangError> {
+    parse_recipe_with_options(input, RecipeOptions::default())
+}
+
+/// Same as [`parse_recipe`] but with extra [`RecipeOptions`]
+#[uniffi::export]
+pub fn parse_recipe_with_options(
+    input: String,
+    options: RecipeOptions,
+) -> Result<CooklangRecipe, CooklangError> {
+    let extensions = extensions();
     let converter = Converter::empty();
 
-    let mut parser = PullParser::new(&input, extensions);
-    let parsed = parse_events(
-        &mut parser,
-        &input,
-        extensions,
-        &converter,
-        Default::default(),
-    )
-    .unwrap_output();
+    let mut raw = RawQuantities::default();
+    let events = PullParser::new(&input, extensions).inspect(|event| {
+        if options.keep_raw_quantities {
+            raw.record(event, &input);
+        }
+    });
+    let parse_options = cooklang::ParseOptions {
+        detect_links: options.detect_links,
+        ..Default::default()
+    };
+    // errors that still produce a recipe are not fatal
+    let (parsed, report) =
+        parse_events(events, &input, extensions, &converter, parse_options).into_tuple();
+    let parsed = parsed.ok_or(report)?;
+
+    Ok(simplify_recipe_data(&parsed, &options, &raw))
+}
+
+/// Extensions the recipes are parsed with
+///
+/// Adding one changes how some recipes are read, so it has to be listed as
+/// breaking in the change log.
+fn extensions() -> Extensions {
+    Extensions::SECTIONS
+        | Extensions::COMPONENT_NOTE
+        | Extensions::COMPONENT_ALIAS
+        | Extensions::PREP_ORDER
+        | Extensions::COMPONENT_MODIFIERS
+        | Extensions::RELATIVE_QUANTITIES
+        | Extensions::SECTION_YIELDS
+        | Extensions::APPROXIMATE_QUANTITIES
+        | Extensions::LOCKED_UNITS
+}
+
+/// Parses a recipe, scales it to `servings` and returns its ingredient list,
+/// like for a shopping list for that many people
+///
+/// The list is the same as [`CooklangRecipe::ingredients`] after scaling.
+/// Like in the recipe format, only the quantities marked with `*`, like
+/// `@flour{200*%g}`, scale. If the recipe has a value for each number of
+/// servings, like `@flour{200|400%g}` with `servings: 2|4`, the one for
+/// `servings` is used. Fails with
+/// [`CooklangError::NoServings`] if the recipe doesn't say how many servings
+/// it makes.
+#[uniffi::export]
+pub fn ingredient_list_for_servings(
+    input: String,
+    servings: u32,
+) -> Result<IngredientList, CooklangError> {
+    let extensions = extensions();
+    let converter = Converter::empty();
+    let events = PullParser::new(&input, extensions);
+    let (parsed, report) =
+        parse_events(events, &input, extensions, &converter, Default::default()).into_tuple();
+    let parsed = parsed.ok_or(report)?;
+    if parsed.metadata.servings().is_none() && parsed.metadata.servings_range().is_none() {
+        return Err(CooklangError::NoServings);
+    }
+    let scaled = parsed.scale(servings, &converter);
+    Ok(scaled_ingredient_list(&scaled))
+}
+
+/// Parses a recipe, scales it by its `default scale` metadata and returns
+/// its ingredient list
+///
+/// The factor is used as is, like `1.5`, it is not rounded to a whole number
+/// of servings. Like in [`ingredient_list_for_servings`], only the quantities
+/// marked with `*` scale. Without the key, the recipe is not scaled. See
+/// [`metadata_default_scale`] to get the factor.
+#[uniffi::export]
+pub fn ingredient_list_default_scaled(input: String) -> Result<IngredientList, CooklangError> {
+    let extensions = extensions();
+    let converter = Converter::empty();
+    let events = PullParser::new(&input, extensions);
+    let (parsed, report) =
+        parse_events(events, &input, extensions, &converter, Default::default()).into_tuple();
+    let scaled = parsed.ok_or(report)?.apply_default_scale(&converter);
+    Ok(scaled_ingredient_list(&scaled))
+}
+
+/// Builds the shopping list for a meal plan
+///
+/// Every recipe is parsed, scaled to its servings and its ingredients added
+/// to one list. Quantities of the same ingredient are added, converting
+/// between units of the same physical quantity, and fit to the best unit with
+/// `converter`. Then they are split in categories with `aisle`.
+///
+/// Like in the recipe format, only the quantities marked with `*` scale, and
+/// a recipe without `servings` metadata is scaled as if it was written for 1.
+/// A recipe with errors is left out and listed in
+/// [`PlanShoppingList::failed`], so one broken recipe doesn't break the whole
+/// plan.
+#[uniffi::export]
+pub fn shopping_list_for_plan(
+    items: Vec<PlanRecipe>,
+    converter: Arc<UnitConverter>,
+    aisle: Arc<AisleConf>,
+) -> PlanShoppingList {
+    let parser = CooklangParser::new(extensions(), converter.converter.clone());
+    let plan = items
+        .iter()
+        .map(|item| (item.input.as_str(), item.servings))
+        .collect::<Vec<_>>();
+    let mut original_aisle = cooklang::aisle::AisleConf::default();
+    original_aisle.categories = aisle
+        .categories
+        .iter()
+        .map(into_original_category)
+        .collect();
+
+    let shopping = shopping_list_for_plan_original(&parser, &plan, &original_aisle);
+    let categories = shopping
+        .list
+        .categories
+        .iter()
+        .map(|(name, list)| (name.clone(), into_ingredient_list(list)))
+        .collect();
+    let failed = shopping
+        .failed
+        .into_iter()
+        .map(|(index, report)| PlanFailure {
+            index: index as u32,
+            message: report_message(&report),
+        })
+        .collect();
+    PlanShoppingList {
+        categories,
+        other: into_ingredient_list(&shopping.list.other),
+        failed,
+    }
+}
+
+/// Returns the same recipe with every ingredient, cookware and timer amount
+/// removed
+#[uniffi::export]
+pub fn strip_quantities(mut recipe: CooklangRecipe) -> CooklangRecipe {
+    recipe
+        .steps
+        .iter_mut()
+        .flat_map(|step| step.items.iter_mut())
+        .chain(recipe.cookware.iter_mut())
+        .for_each(Item::strip_amount);
+
+    recipe
+        .ingredients
+        .values_mut()
+        .for_each(|quantity| *quantity = into_group_quantity(&None));
+
+    recipe
+}
+
+/// Detects the language of a recipe as an ISO 639-1 code like `en`
+///
+/// Uses the `language` or `lang` metadata key if present, otherwise makes a
+/// conservative guess from the text of the steps.
+#[uniffi::export]
+pub fn detect_language(recipe: CooklangRecipe) -> Option<String> {
+    let mut metadata = cooklang::Metadata::default();
+    metadata.map.extend(recipe.metadata.clone());
+    if let Some(lang) = metadata.language() {
+        return Some(lang.to_string());
+    }
+
+    let text = recipe
+        .steps
+        .iter()
+        .flat_map(|step| &step.items)
+        .filter_map(|item| match item {
+            Item::Text { value } => Some(value.as_str()),
+            _ => None,
+        });
+    guess_language(text).map(String::from)
+}
+
+/// Names of the ingredients used in both recipes, like to suggest what else
+/// can be cooked with the same ingredients
+///
+/// Names are compared ignoring case and extra whitespace, and returned
+/// lowercase in the order of `a`.
+#[uniffi::export]
+pub fn shared_ingredients(a: CooklangRecipe, b: CooklangRecipe) -> Vec<String> {
+    shared_ingredients_original(&a.ingredient_order, &b.ingredient_order)
+}
+
+/// Names of the cookware that involves heat, like an oven or a pan, without
+/// duplicates
+///
+/// This is a heuristic based on common English words. More words can be added
+/// with `extra_keywords`.
+#[uniffi::export]
+pub fn heat_cookware(recipe: CooklangRecipe, extra_keywords: Vec<String>) -> Vec<String> {
+    let keywords = HeatKeywords::default().with_keywords(extra_keywords);
+    let mut names: Vec<String> = Vec::new();
+    for item in recipe.cookware {
+        if let Item::Cookware { name, .. } = item {
+            if keywords.matches(&name) && !names.contains(&name) {
+                names.push(name);
+            }
+        }
+    }
+    names
+}
+
+/// All the timers with the sentence of the step around them, for example
+/// `Simmer for 20 min`
+#[uniffi::export]
+pub fn timers_with_context(recipe: CooklangRecipe) -> Vec<TimerContext> {
+    let item_text = |item: &Item| match item {
+        Item::Text { value } | Item::Link { text: value, .. } => value.clone(),
+        Item::Ingredient { name, .. } | Item::Cookware { name, .. } => name.clone(),
+        Item::Timer { name, amount } => amount
+            .as_ref()
+            .and_then(into_original_quantity)
+            .map(|q| q.to_string())
+            .or_else(|| name.clone())
+            .unwrap_or_default(),
+    };
+
+    let mut timers = Vec::new();
+    for (step_index, step) in recipe.steps.iter().enumerate() {
+        let texts = step.items.iter().map(item_text).collect::<Vec<_>>();
+        let items = step
+            .items
+            .iter()
+            .zip(&texts)
+            .map(|(item, text)| match item {
+                Item::Text { .. } => ItemText::Text(text),
+                _ => ItemText::Component(text),
+            })
+            .collect::<Vec<_>>();
+        for (pos, item) in step.items.iter().enumerate() {
+            let Item::Timer { name, amount } = item else {
+                continue;
+            };
+            timers.push(TimerContext {
+                name: name.clone(),
+                amount: amount.clone(),
+                step_number: step_index as u32 + 1,
+                text: sentence_around(&items, pos),
+            });
+        }
+    }
+    timers
+}
+
+/// Counts of the ingredients, cookware and timers used in each step, like
+/// "Step 2: 3 ingredients, 1 timer"
+#[uniffi::export]
+pub fn step_summaries(recipe: CooklangRecipe) -> Vec<StepSummary> {
+    recipe
+        .steps
+        .iter()
+        .enumerate()
+        .map(|(step_index, step)| {
+            let mut ingredients = HashSet::new();
+            let mut cookware = HashSet::new();
+            let mut timers = 0;
+            for item in &step.items {
+                match item {
+                    Item::Ingredient { name, .. } => {
+                        ingredients.insert(name);
+                    }
+                    Item::Cookware { name, .. } => {
+                        cookware.insert(name);
+                    }
+                    Item::Timer { .. } => timers += 1,
+                    Item::Text { .. } | Item::Link { .. } => {}
+                }
+            }
+            StepSummary {
+                step_number: step_index as u32 + 1,
+                ingredients: ingredients.len() as u32,
+                cookware: cookware.len() as u32,
+                timers,
+            }
+        })
+        .collect()
+}
+
+/// The text of each step with the components written as phrases, for reading
+/// out loud
+///
+/// Ingredients are written with their amount, like `1 cup of flour` or
+/// `2 eggs`, or as `the flour` without one. Cookware is written as `the pan`
+/// and timers as their amount. No article is added when the text already has
+/// one before the component, like in `a #pan{}`.
+#[uniffi::export]
+pub fn readable_steps(recipe: CooklangRecipe) -> Vec<String> {
+    const ARTICLES: [&str; 5] = ["a", "an", "the", "some", "your"];
+
+    recipe
+        .steps
+        .iter()
+        .map(|step| {
+            let mut text = String::new();
+            for item in &step.items {
+                let has_article = || {
+                    text.split_whitespace()
+                        .last()
+                        .is_some_and(|w| ARTICLES.contains(&w.to_lowercase().as_str()))
+                };
+                let the = |name: &str| {
+                    if has_article() {
+                        name.to_string()
+                    } else {
+                        format!("the {name}")
+                    }
+                };
+                let phrase = match item {
+                    Item::Text { value } | Item::Link { text: value, .. } => value.clone(),
+                    Item::Ingredient { name, amount, .. } => match amount {
+                        Some(Amount {
+                            quantity: Value::Text { value },
+                            ..
+                        }) => format!("{} ({value})", the(name)),
+                        Some(amount) => match into_original_quantity(amount) {
+                            Some(q) if q.unit().is_some() => format!("{q} of {name}"),
+                            Some(q) => format!("{q} {name}"),
+                            None => the(name),
+                        },
+                        None => the(name),
+                    },
+                    Item::Cookware { name, amount } => {
+                        match amount.as_ref().and_then(into_original_quantity) {
+                            Some(q) => format!("{q} {name}"),
+                            None => the(name),
+                        }
+                    }
+                    Item::Timer { name, amount } => amount
+                        .as_ref()
+                        .and_then(into_original_quantity)
+                        .map(|q| q.to_string())
+                        .or_else(|| name.clone())
+                        .unwrap_or_default(),
+                };
+                text += &phrase;
+            }
+            text.split_whitespace().collect::<Vec<_>>().join(" ")
+        })
+        .collect()
+}
+
+/// The whole method as plain text, for showing it as one block
+///
+/// Each step is a line and sections are separated by a blank line. Section
+/// names and step notes are not included. With `include_components`,
+/// ingredients and cookware are written by name and timers with their amount,
+/// otherwise they are left out.
+#[uniffi::export(default(include_components = true))]
+pub fn method_prose(recipe: CooklangRecipe, include_components: bool) -> String {
+    let sections = if recipe.sections.is_empty() {
+        vec![recipe.steps]
+    } else {
+        recipe.sections.into_iter().map(|s| s.steps).collect()
+    };
+
+    let mut blocks = Vec::new();
+    for steps in sections {
+        let mut lines = Vec::new();
+        for step in steps {
+            let mut text = String::new();
+            for item in step.items {
+                match item {
+                    Item::Text { value } | Item::Link { text: value, .. } => text += &value,
+                    _ if !include_components => {}
+                    Item::Ingredient { name, alias, .. } => text += &alias.unwrap_or(name),
+                    Item::Cookware { name, .. } => text += &name,
+                    Item::Timer { name, amount } => {
+                        match amount.as_ref().and_then(into_original_quantity) {
+                            Some(q) => text += &q.to_string(),
+                            None => text += &name.unwrap_or_default(),
+                        }
+                    }
+                }
+            }
+            // no space left before punctuation when a component is left out
+            let mut line = String::new();
+            for word in text.split_whitespace() {
+                if !line.is_empty() && !word.starts_with(['.', ',', ';', ':', '!', '?']) {
+                    line.push(' ');
+                }
+                line += word;
+            }
+            if !line.is_empty() {
+                lines.push(line);
+            }
+        }
+        if !lines.is_empty() {
+            blocks.push(lines.join("\n"));
+        }
+    }
+    blocks.join("\n\n")
+}
+
+/// Number of steps of the recipe in all the sections
+///
+/// The recipes are parsed without text blocks, so every paragraph of the
+/// method is a step, like a line starting with `>`.
+#[uniffi::export]
+pub fn step_count(recipe: CooklangRecipe) -> u32 {
+    recipe.steps.len() as u32
+}
+
+/// Returns the steps where each ingredient is used, to highlight them
+///
+/// Steps are numbered from 1, in the order of [`CooklangRecipe::steps`].
+/// References use the same name, so they count for the ingredient they point
+/// to. Hidden ingredients are included.
+#[uniffi::export]
+pub fn ingredient_steps(recipe: CooklangRecipe) -> HashMap<String, Vec<u32>> {
+    let mut steps: HashMap<String, Vec<u32>> = HashMap::new();
+    for (number, step) in (1..).zip(&recipe.steps) {
+        for item in &step.items {
+            let Item::Ingredient { name, .. } = item else {
+                continue;
+            };
+            let numbers = steps.entry(name.clone()).or_default();
+            if numbers.last() != Some(&number) {
+                numbers.push(number);
+            }
+        }
+    }
+    steps
+}
+
+/// Returns the steps where each cookware item is used, like
+/// [`ingredient_steps`]
+///
+/// Steps are numbered from 1, in the order of [`CooklangRecipe::steps`].
+/// References use the same name, so they count for the item they point to.
+#[uniffi::export]
+pub fn cookware_steps(recipe: CooklangRecipe) -> HashMap<String, Vec<u32>> {
+    let mut steps: HashMap<String, Vec<u32>> = HashMap::new();
+    for (number, step) in (1..).zip(&recipe.steps) {
+        for item in &step.items {
+            let Item::Cookware { name, .. } = item else {
+                continue;
+            };
+            let numbers = steps.entry(name.clone()).or_default();
+            if numbers.last() != Some(&number) {
+                numbers.push(number);
+            }
+        }
+    }
+    steps
+}
+
+/// Splits the steps in a prep and a cook phase
+///
+/// A step is prep when it has actions and all of them are prep verbs, like
+/// "chop" or "peel". `extra_prep_verbs` are added to the default ones. The
+/// classification uses [`Step::actions`], so a prep verb that is not a
+/// default action verb also has to be in
+/// [`RecipeOptions::extra_action_verbs`] when parsing. Both phases keep the
+/// order of [`CooklangRecipe::steps`].
+#[uniffi::export]
+pub fn recipe_phases(recipe: CooklangRecipe, extra_prep_verbs: Vec<String>) -> RecipePhases {
+    let verbs = ActionVerbs::prep().with_verbs(&extra_prep_verbs);
+    let (prep, cook) = recipe.steps.into_iter().partition(|step| {
+        !step.actions.is_empty() && step.actions.iter().all(|a| verbs.contains(a))
+    });
+    RecipePhases { prep, cook }
+}
+
+/// The amount of one ingredient multiplied by `factor`, without scaling the
+/// whole recipe
+///
+/// `ingredient_index` counts the ingredients in the steps in order, starting
+/// at 0. Numbers and ranges are scaled and text is left as is. The recipe
+/// doesn't keep which values are fixed, so every number is scaled. Returns
+/// `None` if there is no ingredient at the index or it has no amount.
+#[uniffi::export]
+pub fn scaled_amount(recipe: CooklangRecipe, ingredient_index: u32, factor: f64) -> Option<Amount> {
+    let amount = recipe
+        .steps
+        .into_iter()
+        .flat_map(|step| step.items)
+        .filter_map(|item| match item {
+            Item::Ingredient { amount, .. } => Some(amount),
+            _ => None,
+        })
+        .nth(ingredient_index as usize)??;
+    let Some(quantity) = into_original_quantity(&amount) else {
+        return Some(amount);
+    };
+    let value = ScalableValue::Linear(quantity.value).scale_by(factor);
+    Some(Amount {
+        quantity: extract_value(&value),
+        units: amount.units,
+        raw: None,
+        approximate: amount.approximate,
+        unit_locked: amount.unit_locked,
+    })
+}
 
-    into_simple_recipe(&parsed)
+/// Formats an amount with the number conventions of a language tag like
+/// `en`, `fr-FR` or `de`
+///
+/// Unknown languages use plain numbers like `1234.5`.
+///
+/// With `ascii`, non ASCII characters are replaced, like `°C` with `degC`.
+#[uniffi::export(default(ascii = false))]
+pub fn format_amount(amount: Amount, locale: String, ascii: bool) -> String {
+    let options = format_options(&locale, ascii);
+    match into_original_quantity(&amount) {
+        Some(quantity) => quantity.display_with(&options).to_string(),
+        None if ascii => to_ascii(&amount.units.unwrap_or_default()).into_owned(),
+        None => amount.units.unwrap_or_default(),
+    }
+}
+
+pub(crate) fn format_options(locale: &str, ascii: bool) -> FormatOptions {
+    FormatOptions {
+        locale: NumberLocale::from_tag(locale).unwrap_or_default(),
+        ascii,
+        ..Default::default()
+    }
 }
 
 #[uniffi::export]
 pub fn parse_metadata(input: String) -> CooklangMetadata {
+    metadata_with_converter(&input, &Converter::empty())
+}
+
+/// Parses the metadata of many recipes at once
+///
+/// Same as calling [`parse_metadata`] for each input, but all the work is
+/// done in a single call. Use it to index many files, where the cost of every
+/// call from the foreign language adds up. The parsing itself is only a bit
+/// faster, around 5% for 5000 copies of the benchmark recipe, so most of the
+/// gain is not crossing the language boundary once per file.
+#[uniffi::export]
+pub fn parse_metadata_batch(inputs: Vec<String>) -> Vec<CooklangMetadata> {
+    let converter = Converter::empty();
+    inputs
+        .iter()
+        .map(|input| metadata_with_converter(input, &converter))
+        .collect()
+}
+
+fn metadata_with_converter(input: &str, converter: &Converter) -> CooklangMetadata {
     let mut metadata = CooklangMetadata::new();
     let extensions = Extensions::empty();
-    let converter = Converter::empty();
 
-    let parser = PullParser::new(&input, extensions);
+    let parser = PullParser::new(input, extensions);
 
     let parsed = parse_events(
         parser.into_meta_iter(),
-        &input,
+        input,
         extensions,
-        &converter,
+        converter,
         Default::default(),
     )
     .map(|c| c.metadata.map)
@@ -55,31 +617,164 @@ pub fn parse_metadata(input: String) -> CooklangMetadata {
     metadata
 }
 
+/// The keys of the metadata, sorted, to build an edit form with exactly the
+/// keys the recipe has
 #[uniffi::export]
-pub fn parse_aisle_config(input: String) -> Arc<AisleConf> {
-    let mut categories: Vec<AisleCategory> = Vec::new();
-    let mut cache: AisleReverseCategory = AisleReverseCategory::default();
+pub fn metadata_keys(recipe: CooklangRecipe) -> Vec<String> {
+    let mut keys = recipe.metadata.into_keys().collect::<Vec<_>>();
+    keys.sort();
+    keys
+}
+
+/// Returns the image paths or URLs from the `image`/`images` metadata key
+///
+/// The first one is the primary image. Empty if there are none or a relative
+/// path points outside of the recipe directory.
+#[uniffi::export]
+pub fn metadata_images(metadata: CooklangMetadata) -> Vec<String> {
+    let converter = Converter::empty();
+    into_original_metadata(&metadata, &converter)
+        .images()
+        .map(|images| images.to_vec())
+        .unwrap_or_default()
+}
+
+/// Returns the config keys of a recipe, like `[mode]`, with their values
+///
+/// The recipes are parsed without the modes, so these keys don't change
+/// anything and are also in [`CooklangRecipe::metadata`]. This separates
+/// them for editors that want to show the directives. Keys keep their
+/// brackets.
+#[uniffi::export]
+pub fn config_directives(metadata: CooklangMetadata) -> HashMap<String, String> {
+    metadata
+        .into_iter()
+        .filter(|(key, _)| key.starts_with('[') && key.ends_with(']'))
+        .collect()
+}
 
-    let parsed = parse_aisle_config_original(&input).unwrap();
+/// Returns the primary image of a recipe, the first one in the
+/// `image`/`images` metadata key
+///
+/// Use it for the picture of a recipe in a list. [`None`] if there are no
+/// images, the rest are available with [`metadata_images`].
+#[uniffi::export]
+pub fn thumbnail(recipe: CooklangRecipe) -> Option<String> {
+    let converter = Converter::empty();
+    into_original_metadata(&recipe.metadata, &converter)
+        .thumbnail()
+        .map(String::from)
+}
 
-    let _ = &(parsed).categories.iter().for_each(|c| {
-        let category = into_category(c);
+/// Returns the servings the recipe is written for, from the `servings`
+/// metadata key
+///
+/// If many are given, like `2|4`, this is the first one. [`None`] when the
+/// key is missing or can't be parsed, so it's never confused with `1`.
+#[uniffi::export]
+pub fn metadata_servings(metadata: CooklangMetadata) -> Option<u32> {
+    let converter = Converter::empty();
+    into_original_metadata(&metadata, &converter)
+        .servings()
+        .and_then(|servings| servings.first().copied())
+}
 
-        // building cache
-        category.ingredients.iter().for_each(|i| {
-            cache.insert(i.name.clone(), category.name.clone());
+/// Returns the factor the recipe is usually scaled by, from the
+/// `default scale` metadata key, like `2` for a recipe that is usually doubled
+///
+/// [`None`] when the key is missing or is not a positive number.
+#[uniffi::export]
+pub fn metadata_default_scale(metadata: CooklangMetadata) -> Option<f64> {
+    let converter = Converter::empty();
+    into_original_metadata(&metadata, &converter).default_scale()
+}
 
-            i.aliases.iter().for_each(|a| {
-                cache.insert(a.to_string(), category.name.clone());
-            });
-        });
+/// Returns the range of servings the recipe is written for, from the
+/// `servings` metadata key written like `4-6`
+///
+/// [`None`] when the servings are not a range, see [`metadata_servings`].
+#[uniffi::export]
+pub fn metadata_servings_range(metadata: CooklangMetadata) -> Option<ServingsRange> {
+    let converter = Converter::empty();
+    into_original_metadata(&metadata, &converter)
+        .servings_range()
+        .map(|range| ServingsRange {
+            start: range.start,
+            end: range.end,
+        })
+}
 
-        categories.push(category);
-    });
+/// Parses an aisle config
+///
+/// Ingredients in more than one category stay in the first one, see
+/// [`validate_aisle_config`]. Fails with [`CooklangError::Aisle`] when the
+/// config can't be parsed.
+#[uniffi::export]
+pub fn parse_aisle_config(input: String) -> Result<Arc<AisleConf>, CooklangError> {
+    let (parsed, _) = parse_aisle_config_original(&input)?;
+    let categories = parsed.categories.iter().map(into_category).collect();
+    Ok(AisleConf::from_categories(categories))
+}
+
+/// Writes an aisle config in the aisle file format
+///
+/// Categories and ingredients are written in the order of
+/// [`AisleConf::categories`], so editing a parsed config and writing it back
+/// keeps the rest of the file as it was. Comments and blank lines are not
+/// kept.
+#[uniffi::export]
+pub fn render_aisle_config(conf: Arc<AisleConf>) -> String {
+    let mut original = cooklang::aisle::AisleConf::default();
+    original.categories = conf.categories.iter().map(into_original_category).collect();
+    let mut out = Vec::new();
+    cooklang::aisle::write(&original, &mut out).expect("writing to a vec can't fail");
+    String::from_utf8(out).expect("the config is valid UTF-8")
+}
+
+/// Checks an aisle config and returns a message for every problem found,
+/// like an ingredient assigned to more than one category
+#[uniffi::export]
+pub fn validate_aisle_config(input: String) -> Vec<String> {
+    match parse_aisle_config_original(&input) {
+        Ok((_, duplicates)) => duplicates.iter().map(|e| e.to_string()).collect(),
+        Err(e) => vec![e.to_string()],
+    }
+}
 
-    let config = AisleConf { categories, cache };
+/// Names of the ingredients of a recipe that have no category in the aisle
+/// config, in the order they first appear in the recipe
+#[uniffi::export]
+pub fn uncategorized_ingredients(recipe: CooklangRecipe, conf: Arc<AisleConf>) -> Vec<String> {
+    recipe
+        .ingredient_order
+        .into_iter()
+        .filter(|name| conf.category_for(name.clone()).is_none())
+        .collect()
+}
 
-    Arc::new(config)
+/// Removes the pantry staples from an ingredient list, to use it as a
+/// shopping list
+///
+/// Staples are the ingredients in `extra_staples` and, if `exclude_pantry` is
+/// true, the ones in [`AisleConf::pantry_staples`]. The names are compared in
+/// singular like in [`AisleConf::category_for`], so `egg` excludes `eggs`.
+#[uniffi::export(default(exclude_pantry = true))]
+pub fn exclude_staples(
+    mut list: IngredientList,
+    conf: Arc<AisleConf>,
+    extra_staples: Vec<String>,
+    exclude_pantry: bool,
+) -> IngredientList {
+    let extra_staples = extra_staples
+        .iter()
+        .map(|name| english_singular(name))
+        .collect::<HashSet<_>>();
+    let is_staple = |name: &String| {
+        extra_staples.contains(&english_singular(name))
+            || (exclude_pantry && conf.is_pantry_staple(name.clone()))
+    };
+    list.retain(|name, _| !is_staple(name));
+    list
 }
 
 #[uniffi::export]
@@ -93,6 +788,118 @@ pub fn combine_ingredient_lists(lists: Vec<IngredientList>) -> IngredientList {
     combined
 }
 
+/// Like [`combine_ingredient_lists`] but choosing how the quantities of an
+/// ingredient are combined, see [`AggregateMode`]
+///
+/// Quantities with units of the same physical quantity, like `g` and `kg`,
+/// are converted with `converter` to add or compare them. With
+/// [`AggregateMode::Max`], the largest one is kept with its own unit.
+/// Quantities that can't be combined, like text, are kept apart.
+#[uniffi::export]
+pub fn combine_ingredient_lists_with_mode(
+    lists: Vec<IngredientList>,
+    mode: AggregateMode,
+    converter: Arc<UnitConverter>,
+) -> IngredientList {
+    let converter = &converter.converter;
+    let lists = lists
+        .iter()
+        .map(|list| into_original_ingredient_list(list, converter));
+    into_ingredient_list(&combine_ingredients(lists, mode.into(), converter))
+}
+
+/// Like [`combine_ingredient_lists`] but also reports the ingredients that
+/// end up with quantities of different types, like a number in one list and
+/// text in another, with the index of the lists each one came from
+#[uniffi::export]
+pub fn combine_ingredient_lists_with_conflicts(
+    lists: Vec<IngredientList>,
+) -> CombinedIngredientList {
+    combine_ingredient_lists_with_policy(lists, RangePolicy::Sum)
+}
+
+/// Like [`combine_ingredient_lists_with_conflicts`] but choosing how ranges
+/// are added, see [`RangePolicy`]
+///
+/// With [`RangePolicy::Separate`], ingredients with more than one range of
+/// the same units are reported as conflicts with every range.
+#[uniffi::export]
+pub fn combine_ingredient_lists_with_policy(
+    lists: Vec<IngredientList>,
+    range_policy: RangePolicy,
+) -> CombinedIngredientList {
+    let mut combined: IngredientList = IngredientList::default();
+    let mut sources: HashMap<(String, GroupedQuantityKey), Vec<u32>> = HashMap::new();
+    // values that didn't match the type of their key and were left out
+    let mut clashes: HashMap<String, Vec<SourcedQuantity>> = HashMap::new();
+
+    for (index, list) in lists.iter().enumerate() {
+        let index = index as u32;
+        for (name, grouped_quantity) in list {
+            let quantity = combined.entry(name.to_string()).or_default();
+            let clashed =
+                merge_grouped_quantity_with_policy(quantity, grouped_quantity, range_policy)
+                    .err()
+                    .map(|e| e.keys)
+                    .unwrap_or_default();
+
+            for (key, value) in grouped_quantity {
+                if clashed.contains(key) {
+                    clashes
+                        .entry(name.clone())
+                        .or_default()
+                        .push(SourcedQuantity {
+                            key: key.clone(),
+                            value: value.clone(),
+                            sources: vec![index],
+                        });
+                } else {
+                    sources
+                        .entry((name.clone(), key.clone()))
+                        .or_default()
+                        .push(index);
+                }
+            }
+        }
+    }
+
+    let mut conflicts = Vec::new();
+    for (name, quantity) in &combined {
+        let types = quantity
+            .keys()
+            .map(|k| &k.unit_type)
+            .filter(|t| **t != QuantityType::Empty)
+            .collect::<HashSet<_>>();
+        let clashed = clashes.remove(name).unwrap_or_default();
+        if types.len() < 2 && clashed.is_empty() {
+            continue;
+        }
+
+        let mut quantities = quantity
+            .iter()
+            .map(|(key, value)| SourcedQuantity {
+                key: key.clone(),
+                value: value.clone(),
+                sources: sources
+                    .remove(&(name.clone(), key.clone()))
+                    .unwrap_or_default(),
+            })
+            .chain(clashed)
+            .collect::<Vec<_>>();
+        quantities.sort_by_key(|q| q.sources.first().copied());
+        conflicts.push(QuantityConflict {
+            ingredient: name.clone(),
+            quantities,
+        });
+    }
+    conflicts.sort_by(|a, b| a.ingredient.cmp(&b.ingredient));
+
+    CombinedIngredientList {
+        ingredients: combined,
+        conflicts,
+    }
+}
+
 uniffi::setup_scaffolding!();
 
 #[cfg(test)]
@@ -107,7 +914,8 @@ mod tests {
 a test @step @salt{1%mg} more text
 "#
             .to_string(),
-        );
+        )
+        .unwrap();
 
         assert_eq!(
             recipe.steps.into_iter().nth(0).unwrap().items,
@@ -117,7 +925,15 @@ a test @step @salt{1%mg} more text
                 },
                 Item::Ingredient {
                     name: "step".to_string(),
-                    amount: None
+                    amount: None,
+                    prep_order: None,
+                    hidden: false,
+                    reference: false,
+                    optional: false,
+                    recipe: false,
+                    new: false,
+                    alias: None,
+                    note: None,
                 },
                 Item::Text {
                     value: " ".to_string()
@@ -126,8 +942,19 @@ a test @step @salt{1%mg} more text
                     name: "salt".to_string(),
                     amount: Some(Amount {
                         quantity: Value::Number { value: 1.0 },
-                        units: Some("mg".to_string())
-                    })
+                        units: Some("mg".to_string()),
+                        raw: None,
+                        approximate: false,
+                        unit_locked: false,
+                    }),
+                    prep_order: None,
+                    hidden: false,
+                    reference: false,
+                    optional: false,
+                    recipe: false,
+                    new: false,
+                    alias: None,
+                    note: None,
                 },
                 Item::Text {
                     value: " more text".to_string()
@@ -136,6 +963,1304 @@ a test @step @salt{1%mg} more text
         );
     }
 
+    #[test]
+    fn test_sections() {
+        use crate::{parse_recipe, parse_recipe_with_options, RecipeOptions};
+
+        let recipe = parse_recipe("Boil @water.\n\nAdd @pasta.\n".to_string()).unwrap();
+        assert_eq!(recipe.steps.len(), 2);
+        assert!(recipe.sections.is_empty());
+
+        let recipe = parse_recipe_with_options(
+            "Boil @water.\n\nAdd @pasta.\n".to_string(),
+            RecipeOptions {
+                collapse_single_section: false,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(recipe.sections.len(), 1);
+        assert_eq!(recipe.sections[0].name, None);
+        assert_eq!(recipe.sections[0].steps.len(), 2);
+
+        let recipe = parse_recipe(
+            r#"
+Preheat the #oven.
+
+= Dough
+Mix @flour and @water.
+
+= Filling
+Chop the @apples.
+Add @sugar.
+"#
+            .to_string(),
+        )
+        .unwrap();
+        assert_eq!(recipe.steps.len(), 4);
+        let sections = recipe
+            .sections
+            .iter()
+            .map(|s| (s.name.as_deref(), s.steps.len()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            sections,
+            vec![(None, 1), (Some("Dough"), 1), (Some("Filling"), 2)]
+        );
+    }
+
+    #[test]
+    fn test_section_yields() {
+        use crate::{parse_recipe, render_cooklang, Amount, Value};
+
+        let input = "== Meatballs {12%meatballs} ==\n\nMix @beef{500%g}.\n\n== Sauce ==\n\nSimmer @tomatoes{400%g}.\n";
+        let recipe = parse_recipe(input.to_string()).unwrap();
+        assert_eq!(recipe.sections[0].name.as_deref(), Some("Meatballs"));
+        assert_eq!(
+            recipe.sections[0].yields,
+            Some(Amount {
+                quantity: Value::Number { value: 12.0 },
+                units: Some("meatballs".to_string()),
+                raw: None,
+                approximate: false,
+                unit_locked: false,
+            })
+        );
+        assert_eq!(recipe.sections[1].yields, None);
+        assert_eq!(render_cooklang(recipe), input);
+    }
+
+    #[test]
+    fn test_actions() {
+        use crate::{parse_recipe, parse_recipe_with_options, RecipeOptions};
+
+        let recipe =
+            parse_recipe("Chop the @onion, then fry it. Flambé with @rum.".to_string()).unwrap();
+        assert_eq!(recipe.steps[0].actions, vec!["Chop", "fry"]);
+
+        let recipe = parse_recipe_with_options(
+            "Chop the @onion, then fry it. Flambé with @rum.".to_string(),
+            RecipeOptions {
+                extra_action_verbs: vec!["flambé".to_string()],
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(recipe.steps[0].actions, vec!["Chop", "fry", "Flambé"]);
+    }
+
+    #[test]
+    fn test_detect_links() {
+        use crate::{
+            parse_recipe, parse_recipe_with_options, render_cooklang, Item, RecipeOptions,
+        };
+
+        let input = "Proof the dough, see www.example.com/proofing.".to_string();
+        let recipe = parse_recipe(input.clone()).unwrap();
+        assert_eq!(recipe.steps[0].items.len(), 1);
+
+        let recipe = parse_recipe_with_options(
+            input,
+            RecipeOptions {
+                detect_links: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            recipe.steps[0].items[1],
+            Item::Link {
+                url: "https://www.example.com/proofing".to_string(),
+                text: "www.example.com/proofing".to_string(),
+            }
+        );
+        assert_eq!(
+            render_cooklang(recipe),
+            "Proof the dough, see www.example.com/proofing.\n"
+        );
+    }
+
+    #[test]
+    fn test_recipe_refs_as_ingredients() {
+        use crate::{parse_recipe, parse_recipe_with_options, Item, RecipeOptions};
+
+        let input = "Pour @./sauces/tomato sauce{200%ml} over the @pasta{}.".to_string();
+
+        let recipe = parse_recipe(input.clone()).unwrap();
+        assert_eq!(
+            recipe.ingredient_order,
+            vec!["./sauces/tomato sauce", "pasta"]
+        );
+
+        let recipe = parse_recipe_with_options(
+            input,
+            RecipeOptions {
+                recipe_refs_as_ingredients: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(recipe.ingredient_order, vec!["tomato sauce", "pasta"]);
+        assert!(recipe.ingredients.contains_key("tomato sauce"));
+        assert!(matches!(
+            &recipe.steps[0].items[1],
+            Item::Ingredient { name, .. } if name == "./sauces/tomato sauce"
+        ));
+    }
+
+    #[test]
+    fn test_keep_raw_quantities() {
+        use crate::{parse_recipe, parse_recipe_with_options, Item, RecipeOptions};
+
+        let input =
+            "Add @flour{1 1/2%cups} to a #bowl{ 2 } and @salt{}. Wait ~{1/2%h}.".to_string();
+
+        let recipe = parse_recipe(input.clone()).unwrap();
+        let Item::Ingredient { amount, .. } = &recipe.steps[0].items[1] else {
+            panic!("not an ingredient");
+        };
+        assert_eq!(amount.as_ref().unwrap().raw, None);
+
+        let recipe = parse_recipe_with_options(
+            input,
+            RecipeOptions {
+                keep_raw_quantities: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let raw = recipe.steps[0]
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                Item::Ingredient { amount, .. }
+                | Item::Cookware { amount, .. }
+                | Item::Timer { amount, .. } => {
+                    Some(amount.as_ref().and_then(|a| a.raw.as_deref()))
+                }
+                Item::Text { .. } | Item::Link { .. } => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(raw, vec![Some("1 1/2"), Some("2"), None, Some("1/2")]);
+    }
+
+    #[test]
+    fn test_scaled_amount() {
+        use crate::{parse_recipe, scaled_amount, Amount, Value};
+
+        let input = "Mix @flour{200%g}, @eggs{2}, @salt{a pinch} and @water.";
+        let scaled = |index| scaled_amount(parse_recipe(input.to_string()).unwrap(), index, 1.5);
+        assert_eq!(
+            scaled(0),
+            Some(Amount {
+                quantity: Value::Number { value: 300.0 },
+                units: Some("g".to_string()),
+                raw: None,
+                approximate: false,
+                unit_locked: false,
+            })
+        );
+        assert_eq!(scaled(1).unwrap().quantity, Value::Number { value: 3.0 });
+        assert_eq!(
+            scaled(2).unwrap().quantity,
+            Value::Text {
+                value: "a pinch".to_string()
+            }
+        );
+        assert_eq!(scaled(3), None);
+        assert_eq!(scaled(4), None);
+    }
+
+    #[test]
+    fn test_relative_quantities() {
+        use crate::{parse_recipe, Amount, Item, Value};
+
+        let recipe = parse_recipe("Mix @flour{500%g} with @water{=flour}.".to_string()).unwrap();
+        assert_eq!(recipe.ingredients["water"], recipe.ingredients["flour"],);
+        let Item::Ingredient { amount, .. } = &recipe.steps[0].items[3] else {
+            panic!("not an ingredient")
+        };
+        assert_eq!(
+            amount,
+            &Some(Amount {
+                quantity: Value::Number { value: 500.0 },
+                units: Some("g".to_string()),
+                raw: None,
+                approximate: false,
+                unit_locked: false,
+            })
+        );
+    }
+
+    #[test]
+    fn test_hidden_ingredients() {
+        use crate::{parse_recipe, parse_recipe_with_options, Item, RecipeOptions};
+
+        let input = "Boil @water{2%l} with @-salt{1%tbsp}.";
+        let recipe = parse_recipe(input.to_string()).unwrap();
+        assert_eq!(recipe.ingredient_order, vec!["water".to_string()]);
+        assert!(!recipe.ingredients.contains_key("salt"));
+        assert!(recipe.steps[0].items.iter().any(|item| matches!(
+            item,
+            Item::Ingredient { name, hidden: true, .. } if name == "salt"
+        )));
+
+        let recipe = parse_recipe_with_options(
+            input.to_string(),
+            RecipeOptions {
+                include_hidden_ingredients: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            recipe.ingredient_order,
+            vec!["water".to_string(), "salt".to_string()]
+        );
+        assert!(recipe.ingredients.contains_key("salt"));
+    }
+
+    #[test]
+    fn test_prep_order() {
+        use crate::{parse_recipe, Item};
+
+        let recipe = parse_recipe(
+            r#"
+Fry the @onion{1}[#2] with @garlic{2}[#1] and @salt.
+"#
+            .to_string(),
+        )
+        .unwrap();
+
+        let prep_orders = recipe.steps[0]
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                Item::Ingredient {
+                    name, prep_order, ..
+                } => Some((name.as_str(), *prep_order)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            prep_orders,
+            vec![("onion", Some(2)), ("garlic", Some(1)), ("salt", None)]
+        );
+    }
+
+    #[test]
+    fn test_ingredient_order() {
+        use crate::parse_recipe;
+
+        let recipe = parse_recipe(
+            r#"
+Mix @zucchini{1}, @apple{2} and @milk{1%l}.
+Add more @apple{1}, then @banana and @milk{200%ml}.
+"#
+            .to_string(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            recipe.ingredient_order,
+            vec!["zucchini", "apple", "milk", "banana"]
+        );
+        assert!(recipe
+            .ingredient_order
+            .iter()
+            .all(|name| recipe.ingredients.contains_key(name)));
+        assert_eq!(recipe.ingredient_order.len(), recipe.ingredients.len());
+    }
+
+    #[test]
+    fn test_strip_quantities() {
+        use crate::{parse_recipe, strip_quantities, Item};
+
+        let recipe = strip_quantities(
+            parse_recipe("Cook @rice{200%g} in a #pot{1} for ~{15%min}".to_string()).unwrap(),
+        );
+
+        assert!(recipe.steps[0].items.iter().all(|item| match item {
+            Item::Ingredient { amount, .. }
+            | Item::Cookware { amount, .. }
+            | Item::Timer { amount, .. } => amount.is_none(),
+            Item::Text { .. } | Item::Link { .. } => true,
+        }));
+        assert_eq!(
+            recipe.cookware[0],
+            Item::Cookware {
+                name: "pot".to_string(),
+                amount: None
+            }
+        );
+        assert_eq!(
+            recipe.ingredients["rice"],
+            crate::into_group_quantity(&None)
+        );
+    }
+
+    #[test]
+    fn test_timers_with_context() {
+        use crate::{parse_recipe, timers_with_context};
+
+        let recipe = parse_recipe(
+            "Chop the @onion.\n\nFry it in a #pan for ~{5%min}, then add @garlic. Simmer ~sauce{20%minutes}!"
+                .to_string(),
+        ).unwrap();
+        let timers = timers_with_context(recipe)
+            .into_iter()
+            .map(|t| (t.step_number, t.text))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            timers,
+            vec![
+                (2, "Fry it in a pan for 5 min, then add garlic".to_string()),
+                (2, "Simmer 20 minutes".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_step_summaries() {
+        use crate::{parse_recipe, step_summaries, StepSummary};
+
+        let recipe = parse_recipe(
+            "Mix @flour{200%g}, @water and @salt in a #bowl{}. Add more @water.\n\nRest ~{10%min}, then ~{5%min} more.\n\nServe."
+                .to_string(),
+        ).unwrap();
+        let summary = |step_number, ingredients, cookware, timers| StepSummary {
+            step_number,
+            ingredients,
+            cookware,
+            timers,
+        };
+
+        assert_eq!(
+            step_summaries(recipe),
+            vec![
+                summary(1, 3, 1, 0),
+                summary(2, 0, 0, 2),
+                summary(3, 0, 0, 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_complexity_metrics() {
+        use crate::parse_recipe;
+        use crate::units::{ComplexityMetrics, UnitConverter};
+
+        let converter = UnitConverter::bundled();
+        let recipe = parse_recipe(
+            "Mix @flour{200%g} and @water{100%ml} in a #bowl.\n\nKnead for ~{10%min}.\n\nAdd more @flour{50%g} and bake in the #oven for ~{1%hour} in a #bowl{}. Rest ~{a bit%min}."
+                .to_string(),
+        ).unwrap();
+        assert_eq!(
+            converter.complexity_metrics(recipe),
+            ComplexityMetrics {
+                ingredients: 2,
+                steps: 3,
+                timer_seconds: 4200.0,
+                cookware: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn test_totals_by_category() {
+        use crate::units::{PhysicalQuantity, UnitConverter};
+        use crate::{parse_recipe, Value};
+
+        let converter = UnitConverter::bundled();
+        let recipe = parse_recipe(
+            "Mix @flour{1%kg}, @sugar{200%g}, @water{500%ml} and @milk{1/4%l}. Add @eggs{2}, \
+             @salt{a pinch}, @saffron{3%threads} and @butter{20%°C}."
+                .to_string(),
+        )
+        .unwrap();
+        let totals = converter.totals_by_category(recipe);
+        assert_eq!(totals.len(), 2);
+        let mass = &totals[&PhysicalQuantity::Mass];
+        assert_eq!(mass.quantity, Value::Number { value: 1200.0 });
+        assert_eq!(mass.units.as_deref(), Some("g"));
+        let volume = &totals[&PhysicalQuantity::Volume];
+        assert_eq!(volume.quantity, Value::Number { value: 0.75 });
+        assert_eq!(volume.units.as_deref(), Some("l"));
+    }
+
+    #[test]
+    fn test_ingredient_grams() {
+        use crate::parse_recipe;
+        use crate::units::{IngredientGrams, UnitConverter};
+
+        let converter = UnitConverter::bundled();
+        let recipe = parse_recipe(
+            "Mix @flour{1%kg}, @milk{200%ml}, @butter{2%oz}, @eggs{2} and @salt.".to_string(),
+        )
+        .unwrap();
+        let grams = converter.ingredient_grams(recipe);
+        let grams = grams
+            .iter()
+            .map(|IngredientGrams { name, grams }| (name.as_str(), grams.map(f64::round)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            grams,
+            vec![
+                ("flour", Some(1000.0)),
+                ("milk", None),
+                ("butter", Some(57.0)),
+                ("eggs", None),
+                ("salt", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ingredient_grams_density() {
+        use crate::parse_recipe;
+        use crate::units::UnitConverter;
+
+        let converter = UnitConverter::bundled();
+        let recipe = parse_recipe(
+            ">> density.flour: 0.53 g/ml\nMix @flour{1%cup} and @milk{200%ml}.".to_string(),
+        )
+        .unwrap();
+        let grams = converter
+            .ingredient_grams(recipe)
+            .into_iter()
+            .map(|g| g.grams.map(f64::round))
+            .collect::<Vec<_>>();
+        assert_eq!(grams, vec![Some(125.0), None]);
+    }
+
+    #[test]
+    fn test_canonicalize() {
+        use crate::{canonicalize, parse_recipe};
+
+        let a = parse_recipe(
+            ">> title: Pancakes\n>> servings: 2\n\nMix @Flour{200%grams} and @flour{50%g} with  @milk{1 1/2%cups}.\n".to_string(),
+        ).unwrap();
+        let b = parse_recipe(
+            ">> servings:  2\n>> title:   Pancakes\n\n  Mix @flour{150%g}   and @FLOUR{100%gram} with @milk{1.5%cup}.".to_string(),
+        ).unwrap();
+        let a = canonicalize(a);
+        let b = canonicalize(b);
+        assert_eq!(a.ingredient_order, ["flour", "milk"]);
+        assert_eq!(a.metadata, b.metadata);
+        assert_eq!(a.ingredients, b.ingredients);
+
+        let c = parse_recipe("Mix @flour{250%g} and @flour{} with @milk{1.5%cup}.".to_string())
+            .unwrap();
+        let d =
+            parse_recipe("Mix  @Flour{250%gram}  and @flour{} with @milk{1.5%cups}.".to_string())
+                .unwrap();
+        assert_eq!(canonicalize(c), canonicalize(d));
+    }
+
+    #[test]
+    fn test_method_prose() {
+        use crate::{method_prose, parse_recipe};
+
+        let input = "== Dough ==\nMix @flour{200%g} in a #bowl.\n\nRest ~{1%hour}.\n\n== Bake ==\nBake @&dough{} for ~{20%min}, then serve.";
+        assert_eq!(
+            method_prose(parse_recipe(input.to_string()).unwrap(), true),
+            "Mix flour in a bowl.\nRest 1 hour.\n\nBake dough for 20 min, then serve."
+        );
+        assert_eq!(
+            method_prose(parse_recipe(input.to_string()).unwrap(), false),
+            "Mix in a.\nRest.\n\nBake for, then serve."
+        );
+    }
+
+    #[test]
+    fn test_metadata_keys() {
+        use crate::{metadata_keys, parse_recipe};
+
+        let recipe =
+            parse_recipe(">> title: Soup\n>> Servings: 2\nBoil @water.".to_string()).unwrap();
+        assert_eq!(metadata_keys(recipe), ["Servings", "title"]);
+    }
+
+    #[test]
+    fn test_config_directives() {
+        use crate::{config_directives, parse_recipe};
+
+        let recipe = parse_recipe(
+            ">> [mode]: components\n>> [duplicate]: ref\n>> servings: 2\n@water{1%l}".to_string(),
+        )
+        .unwrap();
+        let directives = config_directives(recipe.metadata);
+        assert_eq!(directives.len(), 2);
+        assert_eq!(directives["[mode]"], "components");
+        assert_eq!(directives["[duplicate]"], "ref");
+    }
+
+    #[test]
+    fn test_errors() {
+        use crate::units::UnitConverter;
+        use crate::{parse_aisle_config, parse_recipe, Amount, CooklangError, Value};
+
+        assert!(matches!(
+            parse_recipe("Mix @flour{1/0%cup}.".to_string()),
+            Err(CooklangError::Parse { .. })
+        ));
+        assert!(matches!(
+            parse_aisle_config("[produce\npotatoes".to_string()),
+            Err(CooklangError::Aisle { .. })
+        ));
+
+        let converter = UnitConverter::bundled();
+        let amount = |value, units: &str| Amount {
+            quantity: Value::Number { value },
+            units: Some(units.to_string()),
+            raw: None,
+            approximate: false,
+            unit_locked: false,
+        };
+        assert_eq!(
+            converter.convert(amount(1.0, "l"), "ml".to_string()),
+            Ok(amount(1000.0, "ml"))
+        );
+        assert!(matches!(
+            converter.convert(amount(1.0, "l"), "g".to_string()),
+            Err(CooklangError::Conversion { .. })
+        ));
+    }
+
+    #[test]
+    fn test_ingredient_list_for_servings() {
+        use crate::{
+            ingredient_list_for_servings, CooklangError, GroupedQuantityKey, QuantityType, Value,
+        };
+
+        let input = ">> servings: 2\nMix @flour{200*%g}, @eggs{2*} and @salt{1%tsp}.\nAdd more @flour{50*%g}.";
+        let list = ingredient_list_for_servings(input.to_string(), 4).unwrap();
+        let value = |name: &str, unit: &str, unit_type| {
+            list[name][&GroupedQuantityKey {
+                name: unit.to_string(),
+                unit_type,
+            }]
+                .clone()
+        };
+        assert_eq!(
+            value("flour", "g", QuantityType::Number),
+            Value::Number { value: 500.0 }
+        );
+        assert_eq!(
+            value("eggs", "", QuantityType::Count),
+            Value::Number { value: 4.0 }
+        );
+        assert_eq!(
+            value("salt", "tsp", QuantityType::Number),
+            Value::Number { value: 1.0 }
+        );
+
+        assert_eq!(
+            ingredient_list_for_servings("Mix @flour{200%g}.".to_string(), 4),
+            Err(CooklangError::NoServings)
+        );
+    }
+
+    #[test]
+    fn test_amount_markers() {
+        use crate::{format_amount, parse_recipe, render_cooklang, Item};
+
+        let recipe = parse_recipe("Add @flour{~200%g} and @salt{1%tsp}.".to_string()).unwrap();
+        let amount =
+            |recipe: &crate::CooklangRecipe, index: usize| match &recipe.steps[0].items[index] {
+                Item::Ingredient { amount, .. } => amount.clone().unwrap(),
+                _ => panic!("not an ingredient"),
+            };
+        assert!(amount(&recipe, 1).approximate);
+        assert!(!amount(&recipe, 3).approximate);
+        assert_eq!(
+            format_amount(amount(&recipe, 1), "en".to_string(), false),
+            "~200 g"
+        );
+
+        let rendered = render_cooklang(recipe);
+        assert_eq!(rendered, "Add @flour{~200%g} and @salt{1%tsp}.\n");
+        let recipe = parse_recipe(rendered).unwrap();
+        assert!(amount(&recipe, 1).approximate);
+
+        let recipe = parse_recipe("Add @milk{250%ml!}.".to_string()).unwrap();
+        assert!(amount(&recipe, 1).unit_locked);
+        assert_eq!(render_cooklang(recipe), "Add @milk{250%ml!}.\n");
+    }
+
+    #[test]
+    fn test_step_count() {
+        use crate::{parse_recipe, step_count};
+
+        let recipe = parse_recipe(
+            "Mix @flour{500%g}.\n\n> Let it rest.\n\n== Bake ==\nBake it.\n\nServe.".to_string(),
+        )
+        .unwrap();
+        assert_eq!(step_count(recipe), 4);
+    }
+
+    #[test]
+    fn test_ingredient_steps() {
+        use crate::{ingredient_steps, parse_recipe};
+
+        let recipe = parse_recipe(
+            "Mix @flour{500%g} and @water{300%ml}.\n\n== Bake ==\nDust with @&flour{} and @flour{}."
+                .to_string(),
+        )
+        .unwrap();
+        let steps = ingredient_steps(recipe);
+        assert_eq!(steps.len(), 2);
+        assert_eq!(steps["flour"], vec![1, 2]);
+        assert_eq!(steps["water"], vec![1]);
+    }
+
+    #[test]
+    fn test_cookware_steps() {
+        use crate::{cookware_steps, parse_recipe};
+
+        let recipe = parse_recipe(
+            "Blend in a #blender{} and pour into a #pot{}.\n\nBoil.\n\nBlend again in the #&blender{}, then the #blender{}."
+                .to_string(),
+        )
+        .unwrap();
+        let steps = cookware_steps(recipe);
+        assert_eq!(steps.len(), 2);
+        assert_eq!(steps["blender"], vec![1, 3]);
+        assert_eq!(steps["pot"], vec![1]);
+    }
+
+    #[test]
+    fn test_ingredient_rows() {
+        use crate::parse_recipe;
+        use crate::units::{PhysicalQuantity, UnitConverter};
+
+        let converter = UnitConverter::bundled();
+        let recipe = parse_recipe(
+            "Mix @flour{500%g} and @?sugar{1.5%tbsp}.\n\nServe with @-salt{some}.".to_string(),
+        )
+        .unwrap();
+        let rows = converter.ingredient_rows(recipe);
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].name, "flour");
+        assert_eq!(rows[0].base_value, Some(500.0));
+        assert_eq!(rows[0].physical_quantity, Some(PhysicalQuantity::Mass));
+        assert_eq!(rows[1].value, Some(1.5));
+        assert_eq!(rows[1].unit.as_deref(), Some("tbsp"));
+        assert!(rows[1].optional);
+        assert_eq!(rows[2].value, None);
+        assert!(rows[2].hidden);
+        assert_eq!(rows[2].step_number, Some(2));
+    }
+
+    #[test]
+    fn test_recipe_phases() {
+        use crate::{parse_recipe_with_options, recipe_phases, RecipeOptions};
+
+        let parse = || {
+            let options = RecipeOptions {
+                extra_action_verbs: vec!["trim".to_string()],
+                ..Default::default()
+            };
+            parse_recipe_with_options(
+                "Peel and dice the @onion{}.\n\nTrim the @beans{}.\n\nFry the onion, then add the beans."
+                    .to_string(),
+                options,
+            )
+            .unwrap()
+        };
+
+        let phases = recipe_phases(parse(), vec![]);
+        assert_eq!(phases.prep.len(), 1);
+        assert_eq!(phases.prep[0].actions, vec!["Peel"]);
+        assert_eq!(phases.cook.len(), 2);
+
+        let phases = recipe_phases(parse(), vec!["trim".to_string()]);
+        assert_eq!(phases.prep.len(), 2);
+        assert_eq!(phases.cook.len(), 1);
+        assert_eq!(phases.cook[0].actions, vec!["Fry", "add"]);
+    }
+
+    #[test]
+    fn test_shopping_list_for_plan() {
+        use crate::units::UnitConverter;
+        use crate::{
+            parse_aisle_config, shopping_list_for_plan, GroupedQuantityKey, PlanRecipe,
+            QuantityType, Value,
+        };
+
+        let aisle = parse_aisle_config("[baking]\nflour\n".to_string()).unwrap();
+        let items = vec![
+            PlanRecipe {
+                input: ">> servings: 2\nMix @flour{500*%g} and @eggs{2*}.".to_string(),
+                servings: 4,
+            },
+            PlanRecipe {
+                input: "Dust with @flour{200%g} and @salt.".to_string(),
+                servings: 2,
+            },
+            PlanRecipe {
+                input: "Add the @&sauce{}.".to_string(),
+                servings: 1,
+            },
+        ];
+        let shopping = shopping_list_for_plan(items, UnitConverter::bundled(), aisle);
+
+        let flour = &shopping.categories["baking"]["flour"];
+        assert_eq!(
+            flour[&GroupedQuantityKey {
+                name: "kg".to_string(),
+                unit_type: QuantityType::Number,
+            }],
+            Value::Number { value: 1.2 }
+        );
+        assert_eq!(
+            shopping.other["eggs"][&GroupedQuantityKey {
+                name: "".to_string(),
+                unit_type: QuantityType::Count,
+            }],
+            Value::Number { value: 4.0 }
+        );
+        assert!(shopping.other.contains_key("salt"));
+        assert_eq!(shopping.failed.len(), 1);
+        assert_eq!(shopping.failed[0].index, 2);
+        assert!(shopping.failed[0].message.contains("sauce"));
+    }
+
+    #[test]
+    fn test_step_has_temperature() {
+        use crate::{parse_recipe, Item};
+
+        let recipe = parse_recipe(
+            "Mix @flour{500%g} and @water{300%ml}.\n\nBake at 220°C in the #oven{} for ~{30%min}."
+                .to_string(),
+        )
+        .unwrap();
+        assert!(!recipe.steps[0].has_temperature);
+        assert!(recipe.steps[1].has_temperature);
+        assert_eq!(
+            recipe.steps[1].items[0],
+            Item::Text {
+                value: "Bake at 220°C in the ".to_string()
+            }
+        );
+        assert!(crate::render_cooklang(recipe).contains("Bake at 220°C in the #oven{}"));
+    }
+
+    #[test]
+    fn test_longest_timer() {
+        use crate::units::UnitConverter;
+        use crate::{parse_recipe, Amount, Item, Value};
+
+        let converter = UnitConverter::bundled();
+        let recipe = parse_recipe(
+            "Knead for ~{10%min}.\n\nLet it ~proof{2%hours}.\n\nBake ~{45%min}.".to_string(),
+        )
+        .unwrap();
+        let longest = converter.longest_timer(recipe).unwrap();
+        assert_eq!(longest.seconds, 7200.0);
+        assert_eq!(longest.step_number, 2);
+        assert_eq!(longest.name, Some("proof".to_string()));
+        assert_eq!(
+            longest.amount,
+            Some(Amount {
+                quantity: Value::Number { value: 2.0 },
+                units: Some("hours".to_string()),
+                raw: None,
+                approximate: false,
+                unit_locked: false,
+            })
+        );
+
+        // a range is as long as its end
+        let mut recipe = parse_recipe("Wait ~{10%min}.\n\nRest ~{5%min}.".to_string()).unwrap();
+        let Some(Item::Timer {
+            amount: Some(amount),
+            ..
+        }) = recipe.steps[1].items.get_mut(1)
+        else {
+            panic!("no timer");
+        };
+        amount.quantity = Value::Range {
+            start: 5.0,
+            end: 30.0,
+        };
+        let longest = converter.longest_timer(recipe).unwrap();
+        assert_eq!(longest.seconds, 1800.0);
+        assert_eq!(longest.step_number, 2);
+
+        let recipe = parse_recipe("Mix @flour.".to_string()).unwrap();
+        assert_eq!(converter.longest_timer(recipe), None);
+    }
+
+    #[test]
+    fn test_time_breakdown() {
+        use crate::parse_recipe;
+        use crate::units::{TimeBreakdown, UnitConverter};
+
+        let converter = UnitConverter::bundled();
+        let recipe =
+            parse_recipe("Fry the @onion for ~{5%min}.\n\nLet it rest for ~{1%hour}.".to_string())
+                .unwrap();
+        assert_eq!(
+            converter.time_breakdown(recipe),
+            TimeBreakdown {
+                active: 300.0,
+                passive: 3600.0,
+            }
+        );
+
+        let recipe = parse_recipe("Mix @flour.".to_string()).unwrap();
+        assert_eq!(
+            converter.time_breakdown(recipe),
+            TimeBreakdown {
+                active: 0.0,
+                passive: 0.0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_unit_category() {
+        use crate::units::{PhysicalQuantity, UnitConverter};
+
+        let converter = UnitConverter::bundled();
+        assert_eq!(
+            converter.unit_category("kg".to_string()),
+            Some(PhysicalQuantity::Mass)
+        );
+        assert_eq!(
+            converter.unit_category("cups".to_string()),
+            Some(PhysicalQuantity::Volume)
+        );
+        assert_eq!(
+            converter.unit_category("min".to_string()),
+            Some(PhysicalQuantity::Time)
+        );
+        assert_eq!(
+            converter.unit_category("ºC".to_string()),
+            Some(PhysicalQuantity::Temperature)
+        );
+        assert_eq!(converter.unit_category("handful".to_string()), None);
+    }
+
+    #[test]
+    fn test_readable_steps() {
+        use crate::{parse_recipe, readable_steps};
+
+        let recipe = parse_recipe(
+            "Mix @flour{1%cup}, @eggs{2} and @salt{to taste} in a #bowl{}.\n\nAdd @butter and wait ~{10%min} next to #oven{}."
+                .to_string(),
+        ).unwrap();
+        assert_eq!(
+            readable_steps(recipe),
+            vec![
+                "Mix 1 cup of flour, 2 eggs and the salt (to taste) in a bowl.",
+                "Add the butter and wait 10 min next to the oven.",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_recipe_builder() {
+        use crate::{render_cooklang, Amount, Item, RecipeBuilder, Value};
+
+        let amount = |value: f64, units: &str| {
+            Some(Amount {
+                quantity: Value::Number { value },
+                units: Some(units.to_string()),
+                raw: None,
+                approximate: false,
+                unit_locked: false,
+            })
+        };
+        let text = |value: &str| Item::Text {
+            value: value.to_string(),
+        };
+
+        let recipe = RecipeBuilder::new()
+            .metadata("servings".to_string(), "2".to_string())
+            .section(Some("Dough".to_string()))
+            .step(vec![
+                text("Mix "),
+                Item::Ingredient {
+                    name: "flour".to_string(),
+                    amount: amount(500.0, "g"),
+                    prep_order: None,
+                    hidden: false,
+                    reference: false,
+                    optional: false,
+                    recipe: false,
+                    new: false,
+                    alias: None,
+                    note: None,
+                },
+                text(" and "),
+                Item::Ingredient {
+                    name: "water".to_string(),
+                    amount: amount(300.0, "ml"),
+                    prep_order: None,
+                    hidden: false,
+                    reference: false,
+                    optional: false,
+                    recipe: false,
+                    new: false,
+                    alias: None,
+                    note: None,
+                },
+                text(" in a "),
+                Item::Cookware {
+                    name: "bowl".to_string(),
+                    amount: None,
+                },
+                text(". Rest ~2 h, #1 tip!"),
+            ])
+            .section(Some("Baking".to_string()))
+            .step(vec![
+                text("Bake for "),
+                Item::Timer {
+                    name: None,
+                    amount: amount(40.0, "min"),
+                },
+                text("."),
+            ])
+            .build();
+
+        assert_eq!(recipe.steps.len(), 2);
+        assert_eq!(recipe.sections.len(), 2);
+        assert_eq!(recipe.ingredient_order, vec!["flour", "water"]);
+        assert_eq!(recipe.cookware.len(), 1);
+
+        assert_eq!(
+            render_cooklang(recipe),
+            r#">> servings: 2
+
+== Dough ==
+
+Mix @flour{500%g} and @water{300%ml} in a #bowl{}. Rest \~2 h, \#1 tip!
+
+== Baking ==
+
+Bake for ~{40%min}.
+"#
+        );
+    }
+
+    #[test]
+    fn test_render_cooklang_round_trip() {
+        use crate::{parse_recipe, parse_recipe_with_options, render_cooklang, RecipeOptions};
+
+        let input = r#">> title: Pancakes
+
+Whisk @eggs{2} with @milk{1 1/2%cups} and a pinch of @salt{}.
+
+Cook in a #pan{} for ~{2-3%min} on each side. Enjoy \-- warm. Flip when it \> bubbles.
+"#;
+        let recipe = parse_recipe_with_options(
+            input.to_string(),
+            RecipeOptions {
+                keep_raw_quantities: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let rendered = render_cooklang(recipe);
+        assert!(rendered.contains("@milk{1 1/2%cups}"));
+        assert!(rendered.contains("Enjoy \\-- warm. Flip when it \\> bubbles."));
+
+        let original = parse_recipe(input.to_string()).unwrap();
+        let reparsed = parse_recipe(rendered).unwrap();
+        assert_eq!(reparsed.metadata, original.metadata);
+        assert_eq!(reparsed.ingredients, original.ingredients);
+        assert_eq!(
+            reparsed.steps.iter().map(|s| &s.items).collect::<Vec<_>>(),
+            original.steps.iter().map(|s| &s.items).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_render_cooklang_relative_quantity() {
+        use crate::{parse_recipe_with_options, render_cooklang, RecipeOptions};
+
+        let options = || RecipeOptions {
+            keep_raw_quantities: true,
+            ..Default::default()
+        };
+        let input = "Mix @flour{500%g} with @water{=flour}.";
+        let recipe = parse_recipe_with_options(input.to_string(), options()).unwrap();
+        let rendered = render_cooklang(recipe);
+        assert!(rendered.contains("@water{500%g}"), "{rendered}");
+
+        let original = parse_recipe_with_options(input.to_string(), options()).unwrap();
+        let reparsed = parse_recipe_with_options(rendered, options()).unwrap();
+        assert_eq!(reparsed.ingredients, original.ingredients);
+    }
+
+    #[test]
+    fn test_format_ingredient() {
+        use crate::{format_ingredient, parse_recipe_with_options, Item, RecipeOptions};
+
+        let recipe = parse_recipe_with_options(
+            "Add @green onion|scallions{2}(sliced)[#1], @-salt{1%tsp}, @pepper and \
+             @butter{}(softened) to the #pan{}."
+                .to_string(),
+            RecipeOptions {
+                keep_raw_quantities: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let formatted = recipe.steps[0]
+            .items
+            .iter()
+            .cloned()
+            .map(format_ingredient)
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            formatted,
+            vec![
+                "@green onion|scallions{2}(sliced)[#1]",
+                "@-salt{1%tsp}",
+                "@pepper{}",
+                "@butter{}(softened)",
+            ]
+        );
+
+        assert_eq!(
+            format_ingredient(Item::Ingredient {
+                name: "flour".to_string(),
+                amount: None,
+                prep_order: None,
+                hidden: false,
+                reference: false,
+                optional: false,
+                recipe: false,
+                new: false,
+                alias: None,
+                note: Some("sifted".to_string()),
+            }),
+            "@flour{}(sifted)"
+        );
+    }
+
+    #[test]
+    fn test_format_ingredient_modifiers() {
+        use crate::{format_ingredient, parse_recipe, render_cooklang, Item};
+
+        for (input, expected) in [
+            ("Mix @flour{200%g} and @&flour{}.", "@&flour{}"),
+            ("Top with @?nuts{}.", "@?nuts{}"),
+            ("Add @@sauce{}.", "@@sauce{}"),
+            ("Add @-salt{}.", "@-salt{}"),
+            ("Mix @flour{} and @+flour{}.", "@+flour{}"),
+        ] {
+            let recipe = parse_recipe(input.to_string()).unwrap();
+            let formatted = recipe.steps[0]
+                .items
+                .iter()
+                .rfind(|i| matches!(i, Item::Ingredient { .. }))
+                .cloned()
+                .map(format_ingredient)
+                .unwrap();
+            assert_eq!(formatted, expected, "{input}");
+
+            let items = recipe.steps[0].items.clone();
+            let reparsed = parse_recipe(render_cooklang(recipe)).unwrap();
+            assert_eq!(reparsed.steps[0].items, items, "{input}");
+        }
+    }
+
+    #[test]
+    fn test_step_notes() {
+        use crate::{parse_recipe, render_cooklang, Item, RecipeBuilder};
+
+        // notes are not parsed, so a `>` in a step is text
+        let recipe = parse_recipe("Heat until > 70°C.".to_string()).unwrap();
+        assert_eq!(recipe.steps[0].note, None);
+        assert_eq!(
+            recipe.steps[0].items,
+            vec![Item::Text {
+                value: "Heat until > 70°C.".to_string()
+            }]
+        );
+
+        let recipe = RecipeBuilder::new()
+            .step_with_note(
+                vec![Item::Text {
+                    value: "Knead it.".to_string(),
+                }],
+                Some("Wet your hands.".to_string()),
+            )
+            .build();
+        assert_eq!(render_cooklang(recipe), "Knead it. > Wet your hands.\n");
+    }
+
+    #[test]
+    fn test_ingredients_to_csv() {
+        use crate::{ingredients_to_csv, parse_recipe};
+
+        let recipe = parse_recipe(
+            r#"Mix @flour{200%g}, @eggs{2-3}, @salt{to taste}, @water{} and @cheese, grated{1.5%"cups"}.
+Add @flour{1/2%cup} more and @eggs{}."#
+                .to_string(),
+        ).unwrap();
+        assert_eq!(
+            ingredients_to_csv(recipe.ingredients),
+            r#"name,quantity,unit
+"cheese, grated",1.5,"""cups"""
+eggs,2-3,
+eggs,,
+flour,0.5,cup
+flour,200,g
+salt,to taste,
+water,,
+"#
+        );
+    }
+
+    #[test]
+    fn test_to_mealmaster() {
+        use crate::{parse_recipe, to_mealmaster};
+
+        let recipe = parse_recipe(
+            r#">> title: Pancakes
+>> tags: breakfast, sweet
+>> servings: 4|8
+
+== Batter ==
+
+Whisk @flour{1.5%cups}, @milk{300%ml}, @eggs{2} and @salt{to taste} in a #bowl.
+
+== Cooking ==
+
+Fry in a #pan{} for ~{2%min} per side with @butter{1/2%tbsp}. Serve with @lemon zest{1%strip}.
+"#
+            .to_string(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            to_mealmaster(recipe),
+            r#"MMMMM----- Recipe via Meal-Master (tm) v8.05
+
+      Title: Pancakes
+ Categories: breakfast, sweet
+      Yield: 4 servings
+
+  1 1/2 c  flour
+    300 ml milk
+      2    eggs
+           salt, to taste
+    1/2 tb butter
+      1    strip lemon zest
+
+  BATTER:
+
+  1. Whisk flour, milk, eggs and salt in a bowl.
+
+  COOKING:
+
+  2. Fry in a pan for 2 min per side with butter. Serve with lemon zest.
+
+MMMMM
+"#
+        );
+    }
+
+    #[test]
+    fn test_shared_ingredients() {
+        use crate::{parse_recipe, shared_ingredients};
+
+        let a =
+            parse_recipe("Fry @Garlic{} in @olive oil{}, add @tomatoes and @pasta.".to_string())
+                .unwrap();
+        let b = parse_recipe("Cook @rice{} with @garlic{2%cloves} and @olive  oil{}.".to_string())
+            .unwrap();
+        assert_eq!(shared_ingredients(a, b), vec!["garlic", "olive oil"]);
+    }
+
+    #[test]
+    fn test_heat_cookware() {
+        use crate::{heat_cookware, parse_recipe};
+
+        let recipe = parse_recipe(
+            "Preheat the #oven. Mix in a #bowl, pour into a #baking pan{} and put it in the #oven. Serve on a #plank."
+                .to_string(),
+        ).unwrap();
+        assert_eq!(heat_cookware(recipe, vec![]), vec!["oven", "baking pan"]);
+
+        let recipe = parse_recipe("Put it on the #plank.".to_string()).unwrap();
+        assert_eq!(
+            heat_cookware(recipe, vec!["plank".to_string()]),
+            vec!["plank"]
+        );
+    }
+
+    #[test]
+    fn test_detect_language() {
+        use crate::{detect_language, parse_recipe};
+
+        let recipe = parse_recipe(
+            r#"
+>> language: es
+Mix the @flour{200%g} with the @water until smooth, then rest for ~{1%hour}.
+"#
+            .to_string(),
+        )
+        .unwrap();
+        assert_eq!(detect_language(recipe), Some("es".to_string()));
+
+        let recipe = parse_recipe(
+            r#"
+Mix the @flour{200%g} with the @water until smooth, then rest for ~{1%hour}.
+"#
+            .to_string(),
+        )
+        .unwrap();
+        assert_eq!(detect_language(recipe), Some("en".to_string()));
+
+        let recipe = parse_recipe("@salt".to_string()).unwrap();
+        assert_eq!(detect_language(recipe), None);
+    }
+
+    #[test]
+    fn test_format_amount() {
+        use crate::{format_amount, Amount, Value};
+
+        let amount = Amount {
+            quantity: Value::Number { value: 1234.5 },
+            units: Some("g".to_string()),
+            raw: None,
+            approximate: false,
+            unit_locked: false,
+        };
+        assert_eq!(
+            format_amount(amount.clone(), "en".to_string(), false),
+            "1,234.5 g"
+        );
+        assert_eq!(
+            format_amount(amount.clone(), "fr".to_string(), false),
+            "1 234,5 g"
+        );
+        assert_eq!(
+            format_amount(amount, "unknown".to_string(), false),
+            "1234.5 g"
+        );
+
+        let amount = Amount {
+            quantity: Value::Number { value: 180.0 },
+            units: Some("°C".to_string()),
+            raw: None,
+            approximate: false,
+            unit_locked: false,
+        };
+        assert_eq!(
+            format_amount(amount.clone(), "en".to_string(), false),
+            "180 °C"
+        );
+        assert_eq!(format_amount(amount, "en".to_string(), true), "180 degC");
+    }
+
     #[test]
     fn test_parse_metadata() {
         use crate::parse_metadata;
@@ -155,6 +2280,132 @@ a test @step @salt{1%mg} more text
         );
     }
 
+    #[test]
+    fn test_parse_metadata_batch() {
+        use crate::{parse_metadata, parse_metadata_batch};
+
+        let inputs = vec![
+            ">> title: Bread\nMix @flour{500%g}.".to_string(),
+            String::new(),
+            ">> servings: 4\n>> tags: quick".to_string(),
+        ];
+        let expected = inputs
+            .iter()
+            .map(|input| parse_metadata(input.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(parse_metadata_batch(inputs), expected);
+    }
+
+    #[test]
+    fn test_metadata_images() {
+        use crate::{metadata_images, parse_metadata};
+
+        let metadata = parse_metadata(
+            r#"
+>> image: cover.jpg
+"#
+            .to_string(),
+        );
+        assert_eq!(metadata_images(metadata), vec!["cover.jpg".to_string()]);
+
+        let metadata = parse_metadata(
+            r#"
+>> images: photos/cover.jpg, https://example.com/plated.png
+"#
+            .to_string(),
+        );
+        assert_eq!(
+            metadata_images(metadata),
+            vec![
+                "photos/cover.jpg".to_string(),
+                "https://example.com/plated.png".to_string()
+            ]
+        );
+
+        let metadata = parse_metadata(
+            r#"
+>> image: ../../cover.jpg
+"#
+            .to_string(),
+        );
+        assert!(metadata_images(metadata).is_empty());
+    }
+
+    #[test]
+    fn test_thumbnail() {
+        use crate::{parse_recipe, thumbnail};
+
+        let recipe = parse_recipe(
+            ">> images: photos/cover.jpg, photos/plated.jpg\nMix @flour{500%g}.".to_string(),
+        )
+        .unwrap();
+        assert_eq!(thumbnail(recipe), Some("photos/cover.jpg".to_string()));
+
+        let recipe = parse_recipe("Mix @flour{500%g}.".to_string()).unwrap();
+        assert_eq!(thumbnail(recipe), None);
+    }
+
+    #[test]
+    fn test_metadata_servings() {
+        use crate::{metadata_servings, parse_metadata};
+
+        let metadata = parse_metadata(">> servings: 1\n".to_string());
+        assert_eq!(metadata_servings(metadata), Some(1));
+
+        let metadata = parse_metadata(">> servings: 4|8\n".to_string());
+        assert_eq!(metadata_servings(metadata), Some(4));
+
+        let metadata = parse_metadata(">> source: grandma\n".to_string());
+        assert_eq!(metadata_servings(metadata), None);
+
+        let metadata = parse_metadata(">> servings: a few\n".to_string());
+        assert_eq!(metadata_servings(metadata), None);
+    }
+
+    #[test]
+    fn test_metadata_servings_range() {
+        use crate::{metadata_servings, metadata_servings_range, parse_metadata, ServingsRange};
+
+        let metadata = parse_metadata(">> servings: 4-6\n".to_string());
+        assert_eq!(
+            metadata_servings_range(metadata.clone()),
+            Some(ServingsRange { start: 4, end: 6 })
+        );
+        assert_eq!(metadata_servings(metadata), None);
+
+        let metadata = parse_metadata(">> servings: 4|8\n".to_string());
+        assert_eq!(metadata_servings_range(metadata), None);
+    }
+
+    #[test]
+    fn test_ingredient_list_default_scaled() {
+        use crate::{ingredient_list_default_scaled, Value};
+
+        let list = ingredient_list_default_scaled(
+            ">> default scale: 1.5\nAdd @water{300*%ml} and @salt{1%tsp}.".to_string(),
+        )
+        .unwrap();
+        assert_eq!(
+            list["water"].values().next().unwrap(),
+            &Value::Number { value: 450.0 }
+        );
+        assert_eq!(
+            list["salt"].values().next().unwrap(),
+            &Value::Number { value: 1.0 }
+        );
+    }
+
+    #[test]
+    fn test_metadata_default_scale() {
+        use crate::{metadata_default_scale, parse_metadata};
+
+        let metadata = parse_metadata(">> default scale: 2\n".to_string());
+        assert_eq!(metadata_default_scale(metadata), Some(2.0));
+
+        let metadata = parse_metadata(">> default scale: double\n".to_string());
+        assert_eq!(metadata_default_scale(metadata), None);
+    }
+
     #[test]
     fn test_parse_aisle_config() {
         use crate::parse_aisle_config;
@@ -180,7 +2431,8 @@ cayenne pepper
 dried oregano
 "#
             .to_string(),
-        );
+        )
+        .unwrap();
 
         assert_eq!(
             config.category_for("bay leaves".to_string()),
@@ -196,6 +2448,228 @@ dried oregano
             config.category_for("some weird ingredient".to_string()),
             None
         );
+
+        let config =
+            parse_aisle_config("[dairy]\nbutter\n\n[baking]\nbutter\n".to_string()).unwrap();
+        assert_eq!(
+            config.category_for("butter".to_string()),
+            Some("dairy".to_string())
+        );
+    }
+
+    #[test]
+    fn test_aisle_config_round_trip() {
+        use crate::{parse_aisle_config, render_aisle_config, AisleConf};
+
+        let input = "[fruit and veg] {icon: 🥕}\napple gala|apples\naubergine\n\n[milk and dairy] {order: 1}\nbutter\n\n";
+        let config = parse_aisle_config(input.to_string()).unwrap();
+        let mut categories = config.categories();
+        assert_eq!(categories.len(), 2);
+        assert_eq!(categories[0].name, "fruit and veg");
+        assert_eq!(categories[0].icon.as_deref(), Some("🥕"));
+        assert_eq!(categories[0].ingredients[0].name, "apple gala");
+        assert_eq!(categories[0].ingredients[0].aliases, vec!["apples"]);
+        assert_eq!(categories[1].order, Some(1));
+        assert_eq!(render_aisle_config(config), input);
+
+        categories[1].ingredients[0]
+            .aliases
+            .push("ghee".to_string());
+        let config = AisleConf::from_categories(categories);
+        assert_eq!(
+            config.category_for("ghee".to_string()),
+            Some("milk and dairy".to_string())
+        );
+        let rendered = render_aisle_config(config.clone());
+        assert!(rendered.contains("butter|ghee\n"));
+        let reparsed = parse_aisle_config(rendered).unwrap();
+        assert_eq!(reparsed.categories(), config.categories());
+    }
+
+    #[test]
+    fn test_aisle_plurals() {
+        use crate::{parse_aisle_config, AisleIndex, NameNormalizer};
+        use std::sync::Arc;
+
+        let config =
+            parse_aisle_config("[fruit and veg]\ntomato\nberry\npotato\n".to_string()).unwrap();
+        assert_eq!(
+            config.category_for("tomatoes".to_string()),
+            Some("fruit and veg".to_string())
+        );
+        assert_eq!(
+            config.category_for("Berries".to_string()),
+            Some("fruit and veg".to_string())
+        );
+        assert_eq!(config.category_for("rolls".to_string()), None);
+
+        // in Spanish the plural of `tomate` is `tomates`, but the English
+        // heuristic would not match `limones` with `limón`
+        struct Spanish;
+        impl NameNormalizer for Spanish {
+            fn normalize(&self, name: String) -> String {
+                let name = name.to_lowercase().replace('ó', "o");
+                match name.strip_suffix("es") {
+                    Some(stem) if !stem.ends_with('t') => stem.to_string(),
+                    _ => name.strip_suffix('s').unwrap_or(&name).to_string(),
+                }
+            }
+        }
+        let config =
+            parse_aisle_config("[frutas y verduras]\nlimón\ntomate\n".to_string()).unwrap();
+        assert_eq!(config.category_for("limones".to_string()), None);
+        let index = AisleIndex::new(config, Arc::new(Spanish));
+        for name in ["limones", "tomates", "limón"] {
+            assert_eq!(
+                index.category_for(name.to_string()),
+                Some("frutas y verduras".to_string())
+            );
+        }
+
+        let config = parse_aisle_config(
+            "[pantry]
+egg
+"
+            .to_string(),
+        )
+        .unwrap();
+        assert!(config.is_pantry_staple("eggs".to_string()));
+        assert!(!config.is_pantry_staple("flour".to_string()));
+    }
+
+    #[test]
+    fn test_exclude_staples() {
+        use crate::{exclude_staples, parse_aisle_config, parse_recipe};
+
+        let config =
+            parse_aisle_config("[baking]\nflour\n\n[pantry]\nwater\nsalt\n".to_string()).unwrap();
+        assert_eq!(config.pantry_staples(), vec!["salt", "water"]);
+
+        let recipe = parse_recipe(
+            "Mix @flour{500%g} with @water{300%ml}, @salt{10%g} and @yeast{7%g}.".to_string(),
+        )
+        .unwrap();
+
+        let mut list = exclude_staples(recipe.ingredients.clone(), config.clone(), vec![], true)
+            .into_keys()
+            .collect::<Vec<_>>();
+        list.sort();
+        assert_eq!(list, vec!["flour", "yeast"]);
+
+        let mut list =
+            exclude_staples(recipe.ingredients, config, vec!["yeast".to_string()], false)
+                .into_keys()
+                .collect::<Vec<_>>();
+        list.sort();
+        assert_eq!(list, vec!["flour", "salt", "water"]);
+
+        let config = parse_aisle_config("[pantry]\negg\n".to_string()).unwrap();
+        let recipe = parse_recipe("Beat @eggs{2} with @onions{2}.".to_string()).unwrap();
+        let list = exclude_staples(recipe.ingredients, config, vec!["onion".to_string()], true);
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn test_validate_aisle_config() {
+        use crate::{parse_aisle_config, validate_aisle_config};
+
+        let input = r#"
+[fruit and veg]
+apple gala | apples
+
+[snacks]
+apples
+"#;
+        assert_eq!(
+            validate_aisle_config(input.to_string()),
+            vec!["Duplicate ingredient: 'apples' is in 'fruit and veg' and 'snacks'".to_string()]
+        );
+
+        let config = parse_aisle_config(input.to_string()).unwrap();
+        assert_eq!(
+            config.category_for("apples".to_string()),
+            Some("fruit and veg".to_string())
+        );
+    }
+
+    #[test]
+    fn test_uncategorized_ingredients() {
+        use crate::{parse_aisle_config, parse_recipe, uncategorized_ingredients};
+
+        let config = parse_aisle_config("[baking]\nflour\n\n[spices]\nsalt\n".to_string()).unwrap();
+        let recipe = parse_recipe(
+            "Mix @sumac, @flour{200%g} and @salt. Top with @dried oregano{}.".to_string(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            uncategorized_ingredients(recipe, config),
+            vec!["sumac".to_string(), "dried oregano".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_to_base() {
+        use crate::units::UnitConverter;
+        use crate::{Amount, Value};
+
+        let converter = UnitConverter::bundled();
+        let amount = |quantity, units: Option<&str>| Amount {
+            quantity,
+            units: units.map(String::from),
+            raw: None,
+            approximate: false,
+            unit_locked: false,
+        };
+
+        assert_eq!(
+            converter.to_base(amount(Value::Number { value: 2.0 }, Some("kg"))),
+            Some(2000.0)
+        );
+        assert_eq!(
+            converter.to_base(amount(Value::Number { value: 2.0 }, None)),
+            None
+        );
+        assert_eq!(converter.to_base(amount(Value::Empty, Some("g"))), None);
+    }
+
+    #[test]
+    fn test_count_quantities() {
+        use crate::{parse_recipe, GroupedQuantityKey, QuantityType, Value};
+        use std::collections::HashMap;
+
+        let key = |name: &str, unit_type| GroupedQuantityKey {
+            name: name.to_string(),
+            unit_type,
+        };
+
+        let recipe = parse_recipe(
+            "Peel @apples{3}.\n\nAdd @apples{2} and @sugar{100%g}.\n\nTop with @apples{50%g} and @apples{a few}."
+                .to_string(),
+        ).unwrap();
+        assert_eq!(
+            recipe.ingredients["apples"],
+            HashMap::from([
+                (key("", QuantityType::Count), Value::Number { value: 5.0 }),
+                (
+                    key("g", QuantityType::Number),
+                    Value::Number { value: 50.0 }
+                ),
+                (
+                    key("", QuantityType::Text),
+                    Value::Text {
+                        value: "a few".to_string()
+                    }
+                ),
+            ])
+        );
+        assert_eq!(
+            recipe.ingredients["sugar"],
+            HashMap::from([(
+                key("g", QuantityType::Number),
+                Value::Number { value: 100.0 }
+            )])
+        );
     }
 
     #[test]
@@ -312,4 +2786,347 @@ dried oregano
             ])
         );
     }
+
+    #[test]
+    fn test_combine_ingredient_lists_with_mode() {
+        use std::collections::HashMap;
+
+        use crate::units::UnitConverter;
+        use crate::{
+            combine_ingredient_lists_with_mode, AggregateMode, GroupedQuantityKey, QuantityType,
+            Value,
+        };
+
+        let quantity = |unit: &str, value: f64| {
+            HashMap::from([(
+                GroupedQuantityKey {
+                    name: unit.to_string(),
+                    unit_type: QuantityType::Number,
+                },
+                Value::Number { value },
+            )])
+        };
+        let lists = vec![
+            HashMap::from([("flour".to_string(), quantity("g", 500.0))]),
+            HashMap::from([("flour".to_string(), quantity("kg", 1.0))]),
+            HashMap::from([("flour".to_string(), quantity("g", 200.0))]),
+        ];
+        let converter = UnitConverter::bundled();
+
+        let sum = combine_ingredient_lists_with_mode(
+            lists.clone(),
+            AggregateMode::Sum,
+            converter.clone(),
+        );
+        assert_eq!(sum["flour"], quantity("g", 1700.0));
+
+        let max = combine_ingredient_lists_with_mode(lists, AggregateMode::Max, converter);
+        assert_eq!(max["flour"], quantity("kg", 1.0));
+    }
+
+    #[test]
+    fn test_combine_ingredient_lists_with_conflicts() {
+        use crate::{
+            combine_ingredient_lists_with_conflicts, GroupedQuantityKey, QuantityType,
+            SourcedQuantity, Value,
+        };
+        use std::collections::HashMap;
+
+        let grams = GroupedQuantityKey {
+            name: "g".to_string(),
+            unit_type: QuantityType::Number,
+        };
+        let text = GroupedQuantityKey {
+            name: "".to_string(),
+            unit_type: QuantityType::Text,
+        };
+        let list = |name: &str, key: &GroupedQuantityKey, value: Value| {
+            HashMap::from([(name.to_string(), HashMap::from([(key.clone(), value)]))])
+        };
+
+        let combined = combine_ingredient_lists_with_conflicts(vec![
+            list("salt", &grams, Value::Number { value: 5.0 }),
+            list(
+                "salt",
+                &text,
+                Value::Text {
+                    value: "to taste".to_string(),
+                },
+            ),
+            list("flour", &grams, Value::Number { value: 100.0 }),
+            list("salt", &grams, Value::Number { value: 2.0 }),
+            list("flour", &grams, Value::Number { value: 50.0 }),
+            // a value that doesn't match its key
+            list("flour", &grams, Value::Empty),
+        ]);
+
+        assert_eq!(
+            combined.ingredients["flour"][&grams],
+            Value::Number { value: 150.0 }
+        );
+        assert_eq!(combined.conflicts.len(), 2);
+
+        assert_eq!(combined.conflicts[0].ingredient, "flour");
+        assert_eq!(
+            combined.conflicts[0].quantities,
+            vec![
+                SourcedQuantity {
+                    key: grams.clone(),
+                    value: Value::Number { value: 150.0 },
+                    sources: vec![2, 4],
+                },
+                SourcedQuantity {
+                    key: grams.clone(),
+                    value: Value::Empty,
+                    sources: vec![5],
+                },
+            ]
+        );
+
+        assert_eq!(combined.conflicts[1].ingredient, "salt");
+        assert_eq!(
+            combined.conflicts[1].quantities,
+            vec![
+                SourcedQuantity {
+                    key: grams.clone(),
+                    value: Value::Number { value: 7.0 },
+                    sources: vec![0, 3],
+                },
+                SourcedQuantity {
+                    key: text.clone(),
+                    value: Value::Text {
+                        value: "to taste".to_string(),
+                    },
+                    sources: vec![1],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_combine_ingredient_lists_with_policy() {
+        use crate::{
+            combine_ingredient_lists_with_policy, GroupedQuantityKey, QuantityType, RangePolicy,
+            SourcedQuantity, Value,
+        };
+        use std::collections::HashMap;
+
+        let cups = GroupedQuantityKey {
+            name: "cups".to_string(),
+            unit_type: QuantityType::Range,
+        };
+        let list = |start: f64, end: f64| {
+            HashMap::from([(
+                "flour".to_string(),
+                HashMap::from([(cups.clone(), Value::Range { start, end })]),
+            )])
+        };
+        let lists = vec![list(1.0, 2.0), list(3.0, 4.0)];
+
+        let combined = combine_ingredient_lists_with_policy(lists.clone(), RangePolicy::Sum);
+        assert_eq!(
+            combined.ingredients["flour"][&cups],
+            Value::Range {
+                start: 4.0,
+                end: 6.0
+            }
+        );
+        assert!(combined.conflicts.is_empty());
+
+        let combined = combine_ingredient_lists_with_policy(lists.clone(), RangePolicy::Envelope);
+        assert_eq!(
+            combined.ingredients["flour"][&cups],
+            Value::Range {
+                start: 1.0,
+                end: 4.0
+            }
+        );
+        assert!(combined.conflicts.is_empty());
+
+        let combined = combine_ingredient_lists_with_policy(lists, RangePolicy::Separate);
+        assert_eq!(
+            combined.ingredients["flour"][&cups],
+            Value::Range {
+                start: 1.0,
+                end: 2.0
+            }
+        );
+        assert_eq!(
+            combined.conflicts[0].quantities,
+            vec![
+                SourcedQuantity {
+                    key: cups.clone(),
+                    value: Value::Range {
+                        start: 1.0,
+                        end: 2.0
+                    },
+                    sources: vec![0],
+                },
+                SourcedQuantity {
+                    key: cups.clone(),
+                    value: Value::Range {
+                        start: 3.0,
+                        end: 4.0
+                    },
+                    sources: vec![1],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_grouped_quantity() {
+        use crate::model::{merge_grouped_quantity, MergeError};
+        use crate::{GroupedQuantityKey, QuantityType, Value};
+        use std::collections::HashMap;
+
+        let key = |name: &str, unit_type: QuantityType| GroupedQuantityKey {
+            name: name.to_string(),
+            unit_type,
+        };
+
+        let mut dst = HashMap::from([
+            (key("g", QuantityType::Number), Value::Number { value: 5.0 }),
+            (
+                key("cup", QuantityType::Range),
+                Value::Range {
+                    start: 1.0,
+                    end: 2.0,
+                },
+            ),
+        ]);
+        let src = HashMap::from([
+            (key("g", QuantityType::Number), Value::Number { value: 2.0 }),
+            // value doesn't match the key type
+            (
+                key("cup", QuantityType::Range),
+                Value::Number { value: 1.0 },
+            ),
+            (key("", QuantityType::Empty), Value::Empty),
+        ]);
+
+        let result = merge_grouped_quantity(&mut dst, &src);
+
+        assert_eq!(
+            result,
+            Err(MergeError {
+                keys: vec![key("cup", QuantityType::Range)]
+            })
+        );
+        assert_eq!(
+            dst,
+            HashMap::from([
+                (key("g", QuantityType::Number), Value::Number { value: 7.0 }),
+                (
+                    key("cup", QuantityType::Range),
+                    Value::Range {
+                        start: 1.0,
+                        end: 2.0,
+                    },
+                ),
+                (key("", QuantityType::Empty), Value::Empty),
+            ])
+        );
+
+        assert_eq!(merge_grouped_quantity(&mut dst, &HashMap::new()), Ok(()));
+    }
+
+    #[test]
+    fn test_fit_amount() {
+        use crate::units::{DisplayPreferences, UnitConverter};
+        use crate::{Amount, Value};
+
+        let converter = UnitConverter::bundled();
+        let amount = |value: f64, units: &str| Amount {
+            quantity: Value::Number { value },
+            units: Some(units.to_string()),
+            raw: None,
+            approximate: false,
+            unit_locked: false,
+        };
+        let preferences = DisplayPreferences {
+            volume: Some("ml".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            converter.fit_amount(amount(1.5, "l"), preferences.clone()),
+            amount(1500.0, "ml")
+        );
+        assert_eq!(
+            converter.fit_amount(amount(1500.0, "g"), preferences.clone()),
+            amount(1.5, "kg")
+        );
+        // unknown units are left as they are
+        assert_eq!(
+            converter.fit_amount(amount(2.0, "handful"), preferences.clone()),
+            amount(2.0, "handful")
+        );
+        assert_eq!(
+            converter.format_amount(amount(1.5, "l"), preferences, "fr".to_string(), false),
+            "1 500 ml"
+        );
+
+        let metric = UnitConverter::metric();
+        let fitted = metric.fit_amount(amount(2.0, "cup"), DisplayPreferences::default());
+        assert_eq!(fitted.units.as_deref(), Some("ml"));
+        // preferences still win
+        let preferences = DisplayPreferences {
+            volume: Some("l".to_string()),
+            ..Default::default()
+        };
+        let fitted = metric.fit_amount(amount(2.0, "cup"), preferences);
+        assert_eq!(fitted.units.as_deref(), Some("l"));
+        let fitted =
+            UnitConverter::imperial().fit_amount(amount(1.0, "l"), DisplayPreferences::default());
+        assert_eq!(fitted.units.as_deref(), Some("c"));
+
+        // locked units are kept
+        let locked = Amount {
+            unit_locked: true,
+            ..amount(1.0, "l")
+        };
+        let fitted =
+            UnitConverter::imperial().fit_amount(locked.clone(), DisplayPreferences::default());
+        assert_eq!(fitted, locked);
+    }
+
+    #[test]
+    fn test_ingredient_percentages() {
+        use crate::parse_recipe;
+        use crate::units::{IngredientPercentage, UnitConverter};
+
+        let converter = UnitConverter::bundled();
+        let recipe = parse_recipe(
+            "Mix @flour{750%g} with @water{200%g}, @flour{250%g}, @salt{5%g}, @milk{1%cup} and @yeast."
+                .to_string(),
+        ).unwrap();
+        let result = converter.ingredient_percentages(recipe);
+        let percentages = result
+            .percentages
+            .into_iter()
+            .map(|p| IngredientPercentage {
+                percentage: (p.percentage * 10.0).round() / 10.0,
+                ..p
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            percentages,
+            vec![
+                IngredientPercentage {
+                    name: "flour".to_string(),
+                    percentage: 83.0
+                },
+                IngredientPercentage {
+                    name: "water".to_string(),
+                    percentage: 16.6
+                },
+                IngredientPercentage {
+                    name: "salt".to_string(),
+                    percentage: 0.4
+                },
+            ]
+        );
+        assert_eq!(result.excluded, vec!["milk", "yeast"]);
+    }
 }
@@ -0,0 +1,212 @@
+//! Export to the MealMaster text format
+//!
+//! MealMaster is a plain text format used by a lot of legacy recipe software.
+//! Not everything in a recipe can be represented:
+//!
+//! - Only the `title`, `tags` and `servings` metadata are kept, as the title,
+//!   categories and yield.
+//! - Ingredients are listed once with all their amounts, like in
+//!   [`CooklangRecipe::ingredients`]. Amounts of different units are written
+//!   in separate lines with the same name.
+//! - Units without a MealMaster code are written before the ingredient name.
+//!   Text amounts are written after the name, like `salt, to taste`.
+//! - Cookware, timers and section names only appear in the directions text.
+//! - Notes and prep order are lost.
+
+use crate::model::{into_original_quantity, CooklangRecipe, Item, QuantityType, Value};
+
+/// Max width of a line of the directions
+const LINE_WIDTH: usize = 72;
+
+/// Converts a recipe to the MealMaster format
+#[uniffi::export]
+pub fn to_mealmaster(recipe: CooklangRecipe) -> String {
+    let mut out = String::from("MMMMM----- Recipe via Meal-Master (tm) v8.05\n\n");
+
+    let title = recipe
+        .metadata
+        .get("title")
+        .map(|t| t.trim())
+        .filter(|t| !t.is_empty())
+        .unwrap_or("Untitled");
+    out += &format!("      Title: {title}\n");
+    let categories = recipe
+        .metadata
+        .get("tags")
+        .map(|tags| {
+            tags.split(',')
+                .map(str::trim)
+                .filter(|t| !t.is_empty())
+                .collect::<Vec<_>>()
+                .join(", ")
+        })
+        .unwrap_or_default();
+    out += &format!(" Categories: {categories}\n");
+    if let Some(servings) = recipe
+        .metadata
+        .get("servings")
+        .and_then(|s| s.split('|').next())
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+    {
+        out += &format!("      Yield: {servings} servings\n");
+    }
+    out += "\n";
+
+    for name in &recipe.ingredient_order {
+        let mut quantities = recipe.ingredients[name].iter().collect::<Vec<_>>();
+        quantities.sort_by(|(a, _), (b, _)| {
+            (a.name.as_str(), type_order(&a.unit_type))
+                .cmp(&(b.name.as_str(), type_order(&b.unit_type)))
+        });
+        for (key, value) in quantities {
+            out += &ingredient_line(name, &key.name, value);
+            out += "\n";
+        }
+    }
+    out += "\n";
+
+    let sections = if recipe.sections.is_empty() {
+        vec![(None, recipe.steps.as_slice())]
+    } else {
+        recipe
+            .sections
+            .iter()
+            .map(|s| (s.name.as_deref(), s.steps.as_slice()))
+            .collect()
+    };
+    let mut step_number = 1;
+    for (name, steps) in sections {
+        if let Some(name) = name {
+            out += &format!("  {}:\n\n", name.to_uppercase());
+        }
+        for step in steps {
+            let text = step.items.iter().map(item_text).collect::<String>();
+            for line in wrap(&format!("{step_number}. {}", text.trim())) {
+                out += &format!("  {line}\n");
+            }
+            out += "\n";
+            step_number += 1;
+        }
+    }
+
+    out += "MMMMM\n";
+    out
+}
+
+pub(crate) fn type_order(t: &QuantityType) -> u8 {
+    match t {
+        QuantityType::Number | QuantityType::Count => 0,
+        QuantityType::Range => 1,
+        QuantityType::Text => 2,
+        QuantityType::Empty => 3,
+    }
+}
+
+/// One ingredient line with the amount in the first 7 columns and the unit
+/// code in the next 2
+fn ingredient_line(name: &str, unit: &str, value: &Value) -> String {
+    let (code, name) = match unit_code(unit) {
+        Some(code) => (code, name.to_string()),
+        None if unit.is_empty() => ("", name.to_string()),
+        None => ("", format!("{unit} {name}")),
+    };
+    let (amount, name) = match value {
+        Value::Number { value } => (number(*value), name),
+        Value::Range { start, end } => (format!("{}-{}", number(*start), number(*end)), name),
+        Value::Text { value } => (String::new(), format!("{name}, {value}")),
+        Value::Empty => (String::new(), name),
+    };
+    format!("{amount:>7} {code:<2} {name}")
+        .trim_end()
+        .to_string()
+}
+
+/// MealMaster unit codes
+fn unit_code(unit: &str) -> Option<&'static str> {
+    let code = match unit.to_lowercase().trim_end_matches('.') {
+        "tsp" | "teaspoon" | "teaspoons" => "ts",
+        "tbsp" | "tablespoon" | "tablespoons" => "tb",
+        "cup" | "cups" | "c" => "c",
+        "oz" | "ounce" | "ounces" => "oz",
+        "lb" | "lbs" | "pound" | "pounds" => "lb",
+        "pt" | "pint" | "pints" => "pt",
+        "qt" | "quart" | "quarts" => "qt",
+        "gal" | "gallon" | "gallons" => "ga",
+        "g" | "gram" | "grams" => "g",
+        "kg" | "kilogram" | "kilograms" => "kg",
+        "mg" | "milligram" | "milligrams" => "mg",
+        "ml" | "millilitre" | "milliliter" | "millilitres" | "milliliters" => "ml",
+        "cl" | "centilitre" | "centiliter" => "cl",
+        "dl" | "decilitre" | "deciliter" => "dl",
+        "l" | "litre" | "liter" | "litres" | "liters" => "l",
+        "pinch" | "pinches" => "pn",
+        "dash" | "dashes" => "ds",
+        "drop" | "drops" => "dr",
+        "can" | "cans" => "cn",
+        "package" | "packages" | "pkg" => "pk",
+        "small" => "sm",
+        "medium" => "md",
+        "large" => "lg",
+        _ => return None,
+    };
+    Some(code)
+}
+
+/// Formats a number with common fractions, like `1 1/2`
+fn number(value: f64) -> String {
+    const FRACTIONS: [(f64, &str); 7] = [
+        (1.0 / 8.0, "1/8"),
+        (1.0 / 4.0, "1/4"),
+        (1.0 / 3.0, "1/3"),
+        (1.0 / 2.0, "1/2"),
+        (2.0 / 3.0, "2/3"),
+        (3.0 / 4.0, "3/4"),
+        (7.0 / 8.0, "7/8"),
+    ];
+    let whole = value.trunc();
+    let fract = value - whole;
+    if fract.abs() < 0.01 {
+        return format!("{whole}");
+    }
+    if let Some((_, fraction)) = FRACTIONS.iter().find(|(f, _)| (fract - f).abs() < 0.01) {
+        return if whole == 0.0 {
+            fraction.to_string()
+        } else {
+            format!("{whole} {fraction}")
+        };
+    }
+    let rounded = (value * 100.0).round() / 100.0;
+    format!("{rounded}")
+}
+
+fn item_text(item: &Item) -> String {
+    match item {
+        Item::Text { value } | Item::Link { text: value, .. } => value.clone(),
+        Item::Ingredient { name, .. } | Item::Cookware { name, .. } => name.clone(),
+        Item::Timer { name, amount } => amount
+            .as_ref()
+            .and_then(into_original_quantity)
+            .map(|q| q.to_string())
+            .or_else(|| name.clone())
+            .unwrap_or_default(),
+    }
+}
+
+fn wrap(text: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.len() + 1 + word.len() > LINE_WIDTH {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
@@ -1,22 +1,106 @@
 use std::collections::HashMap;
+use std::sync::LazyLock;
 
+use cooklang::actions::{step_actions, ActionVerbs};
+use cooklang::metadata::Metadata as OriginalMetadata;
 use cooklang::model::Item as OriginalItem;
+use cooklang::parser::{Event, Modifiers};
 use cooklang::quantity::{
     Quantity as OriginalQuantity, ScalableValue as OriginalScalableValue, Value as OriginalValue,
 };
 use cooklang::ScalableRecipe as OriginalRecipe;
+use cooklang::{Converter, Span};
 
-#[derive(uniffi::Record, Debug)]
+/// Bundled units, only to find the temperatures for [`Step::has_temperature`]
+///
+/// Recipes are parsed without units, and building these is slow, so they
+/// are built once.
+static TEMPERATURE_CONVERTER: LazyLock<Converter> = LazyLock::new(Converter::bundled);
+
+#[derive(uniffi::Record, Debug, PartialEq)]
 pub struct CooklangRecipe {
     pub metadata: HashMap<String, String>,
     pub steps: Vec<Step>,
     pub ingredients: IngredientList,
+    /// Names of the keys in `ingredients` in the order they first appear in
+    /// the recipe
+    pub ingredient_order: Vec<String>,
     pub cookware: Vec<Item>,
+    /// Steps grouped by section, the same steps as in `steps`
+    ///
+    /// Empty if the recipe has just one unnamed section and
+    /// [`RecipeOptions::collapse_single_section`] is set.
+    pub sections: Vec<Section>,
 }
 
-#[derive(uniffi::Record, Debug)]
+#[derive(uniffi::Record, Debug, Clone, PartialEq)]
 pub struct Step {
     pub items: Vec<Item>,
+    /// Cooking verbs that start the sentences of the step, like "Mix" or
+    /// "Bake"
+    pub actions: Vec<String>,
+    /// Tip of the author, written after a `>` in the step
+    pub note: Option<String>,
+    /// The step has a temperature in the text, like `Bake at 180 ºC`, so it
+    /// probably uses an oven or some other heat
+    #[uniffi(default = false)]
+    pub has_temperature: bool,
+}
+
+#[derive(uniffi::Record, Debug, Clone, PartialEq)]
+pub struct Section {
+    pub name: Option<String>,
+    pub steps: Vec<Step>,
+    /// How much the section makes, from `== Meatballs {12%meatballs} ==`
+    pub yields: Option<Amount>,
+}
+
+/// Options for [`parse_recipe_with_options`](crate::parse_recipe_with_options)
+#[derive(uniffi::Record, Debug, Clone)]
+pub struct RecipeOptions {
+    /// When the recipe only has one section without a name, leave `sections`
+    /// empty and use only the flat `steps`
+    #[uniffi(default = true)]
+    pub collapse_single_section: bool,
+    /// Verbs recognised as [`Step::actions`] in addition to the common English
+    /// ones
+    #[uniffi(default = [])]
+    pub extra_action_verbs: Vec<String>,
+    /// List recipe references like `@./sauce{}` in
+    /// [`CooklangRecipe::ingredients`] with the name of the referenced recipe,
+    /// `sauce`, instead of the path as written
+    ///
+    /// Step items always keep the path.
+    #[uniffi(default = false)]
+    pub recipe_refs_as_ingredients: bool,
+    /// Fill [`Amount::raw`] with the quantities as written in the recipe
+    #[uniffi(default = false)]
+    pub keep_raw_quantities: bool,
+    /// Add the hidden ingredients, like `@-salt{}`, to
+    /// [`CooklangRecipe::ingredients`]
+    ///
+    /// They are always in the step items.
+    #[uniffi(default = false)]
+    pub include_hidden_ingredients: bool,
+    /// Make the URLs in the steps, like `https://cooklang.org`, an
+    /// [`Item::Link`] instead of text
+    ///
+    /// A URL with `@`, `#` or `~` ends there, because they start a component.
+    #[uniffi(default = false)]
+    pub detect_links: bool,
+}
+
+impl Default for RecipeOptions {
+    fn default() -> Self {
+        Self {
+            collapse_single_section: true,
+            extra_action_verbs: Vec::new(),
+            recipe_refs_as_ingredients: false,
+            keep_raw_quantities: false,
+            include_hidden_ingredients: false,
+            detect_links: false,
+        }
+    }
 }
 
 #[derive(uniffi::Enum, Debug, Clone, PartialEq)]
@@ -27,6 +111,23 @@ pub enum Item {
     Ingredient {
         name: String,
         amount: Option<Amount>,
+        /// Prep order hint from `@igr{}[#2]`
+        prep_order: Option<u32>,
+        /// Marked with `@-igr`, not meant to be displayed
+        hidden: bool,
+        /// A reference to an ingredient before, like `@&igr`
+        reference: bool,
+        /// Marked with `@?igr`, not needed to make the recipe
+        optional: bool,
+        /// A reference to another recipe, like `@@sauce`
+        recipe: bool,
+        /// Marked with `@+igr`, a new ingredient even if one with the same
+        /// name is before
+        new: bool,
+        /// Name to display instead of `name`, from `@igr|alias{}`
+        alias: Option<String>,
+        /// Note from `@igr{}(note)`
+        note: Option<String>,
     },
     Cookware {
         name: String,
@@ -36,6 +137,14 @@ pub enum Item {
         name: Option<String>,
         amount: Option<Amount>,
     },
+    /// A URL in the text, only with [`RecipeOptions::detect_links`]
+    Link {
+        /// The URL to open
+        url: String,
+        /// The URL as written, like `www.cooklang.org` for
+        /// `https://www.cooklang.org`
+        text: String,
+    },
 }
 
 pub type IngredientList = HashMap<String, GroupedQuantity>;
@@ -63,6 +172,10 @@ pub(crate) fn into_group_quantity(amount: &Option<Amount>) -> GroupedQuantity {
         let units = amount.units.as_ref().unwrap_or(&empty_units);
 
         match &amount.quantity {
+            Value::Number { .. } if amount.units.is_none() => GroupedQuantityKey {
+                name: units.to_string(),
+                unit_type: QuantityType::Count,
+            },
             Value::Number { .. } => GroupedQuantityKey {
                 name: units.to_string(),
                 unit_type: QuantityType::Number,
@@ -96,9 +209,43 @@ pub(crate) fn into_group_quantity(amount: &Option<Amount>) -> GroupedQuantity {
     GroupedQuantity::from([(key, value)])
 }
 
+/// Range of servings from metadata like `servings: 4-6`
+#[derive(uniffi::Record, Debug, Clone, PartialEq)]
+pub struct ServingsRange {
+    pub start: u32,
+    pub end: u32,
+}
+
+/// Counts of what is used in a step
+#[derive(uniffi::Record, Debug, Clone, PartialEq)]
+pub struct StepSummary {
+    /// Number of the step in [`CooklangRecipe::steps`], starting at 1
+    pub step_number: u32,
+    /// Distinct ingredients by name
+    pub ingredients: u32,
+    /// Distinct cookware by name
+    pub cookware: u32,
+    pub timers: u32,
+}
+
+/// A timer with the sentence of the step around it
+#[derive(uniffi::Record, Debug, Clone, PartialEq)]
+pub struct TimerContext {
+    pub name: Option<String>,
+    pub amount: Option<Amount>,
+    /// Number of the step in [`CooklangRecipe::steps`], starting at 1
+    pub step_number: u32,
+    /// Sentence around the timer, including it, like `Simmer for 20 min`
+    pub text: String,
+}
+
 #[derive(uniffi::Enum, Debug, Clone, Hash, Eq, PartialEq)]
 pub enum QuantityType {
     Number,
+    /// A number without units, like the 3 in `@apples{3}`
+    ///
+    /// Counts are only added to other counts and never converted.
+    Count,
     Range, // how to combine ranges?
     Text,
     Empty,
@@ -116,6 +263,19 @@ pub type GroupedQuantity = HashMap<GroupedQuantityKey, Value>;
 pub struct Amount {
     pub(crate) quantity: Value,
     pub(crate) units: Option<String>,
+    /// Quantity as written in the recipe, like `1 1/2` for `1.5`
+    ///
+    /// Only set when parsing with [`RecipeOptions::keep_raw_quantities`].
+    /// Relative quantities, like `=flour`, are never kept.
+    #[uniffi(default = None)]
+    pub(crate) raw: Option<String>,
+    /// The amount is approximate, written with a `~` like `@flour{~200%g}`
+    #[uniffi(default = false)]
+    pub(crate) approximate: bool,
+    /// The unit is not changed by conversions, written with a `!` like
+    /// `@milk{250%ml!}`
+    #[uniffi(default = false)]
+    pub(crate) unit_locked: bool,
 }
 
 #[derive(uniffi::Enum, Debug, Clone, PartialEq)]
@@ -128,6 +288,22 @@ pub enum Value {
 
 pub type CooklangMetadata = HashMap<String, String>;
 
+/// Builds the core metadata from the raw key/value pairs, parsing the special
+/// keys. Values that fail to parse are left out of the special keys.
+pub(crate) fn into_original_metadata(
+    metadata: &CooklangMetadata,
+    converter: &Converter,
+) -> OriginalMetadata {
+    let mut original = OriginalMetadata::default();
+    original.map.extend(
+        metadata
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string())),
+    );
+    let _ = original.parse_special(converter);
+    original
+}
+
 trait Amountable {
     fn extract_amount(&self) -> Amount;
 }
@@ -138,7 +314,25 @@ impl Amountable for OriginalQuantity<OriginalScalableValue> {
 
         let units = self.unit().as_ref().map(|u| u.to_string());
 
-        Amount { quantity, units }
+        Amount {
+            quantity,
+            units,
+            raw: None,
+            approximate: self.approximate,
+            unit_locked: self.unit_locked,
+        }
+    }
+}
+
+impl Amountable for OriginalQuantity<OriginalValue> {
+    fn extract_amount(&self) -> Amount {
+        Amount {
+            quantity: extract_value(&self.value),
+            units: self.unit().map(|u| u.to_string()),
+            raw: None,
+            approximate: self.approximate,
+            unit_locked: self.unit_locked,
+        }
     }
 }
 
@@ -149,6 +343,9 @@ impl Amountable for OriginalScalableValue {
         Amount {
             quantity,
             units: None,
+            raw: None,
+            approximate: false,
+            unit_locked: false,
         }
     }
 }
@@ -161,7 +358,23 @@ fn extract_quantity(value: &OriginalScalableValue) -> Value {
     }
 }
 
-fn extract_value(value: &OriginalValue) -> Value {
+pub(crate) fn into_original_quantity(amount: &Amount) -> Option<OriginalQuantity<OriginalValue>> {
+    let value = match &amount.quantity {
+        Value::Number { value } => OriginalValue::from(*value),
+        Value::Range { start, end } => OriginalValue::Range {
+            start: (*start).into(),
+            end: (*end).into(),
+        },
+        Value::Text { value } => OriginalValue::from(value.clone()),
+        Value::Empty => return None,
+    };
+    let mut quantity = OriginalQuantity::new(value, amount.units.clone());
+    quantity.approximate = amount.approximate;
+    quantity.unit_locked = amount.unit_locked;
+    Some(quantity)
+}
+
+pub(crate) fn extract_value(value: &OriginalValue) -> Value {
     match value {
         OriginalValue::Number(num) => Value::Number { value: num.value() },
         OriginalValue::Range { start, end } => Value::Range {
@@ -181,7 +394,8 @@ pub fn add_to_ingredient_list(
     quantity_to_add: &GroupedQuantity,
 ) {
     if let Some(quantity) = list.get_mut(name) {
-        merge_grouped_quantities(quantity, quantity_to_add);
+        // mismatched values are left out, there's nothing better to do here
+        let _ = merge_grouped_quantity(quantity, quantity_to_add);
     } else {
         list.insert(name.to_string(), quantity_to_add.clone());
     }
@@ -196,13 +410,164 @@ pub fn merge_ingredient_lists(left: &mut IngredientList, right: &IngredientList)
                 .entry(ingredient_name.to_string())
                 .or_insert(GroupedQuantity::default());
 
-            merge_grouped_quantities(quantity, grouped_quantity);
+            // mismatched values are left out, there's nothing better to do here
+            let _ = merge_grouped_quantity(quantity, grouped_quantity);
         });
 }
 
+/// Error returned by [`merge_grouped_quantity`] when a value doesn't have
+/// the type its key says it has
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeError {
+    /// Keys that were not merged
+    pub keys: Vec<GroupedQuantityKey>,
+}
+
+impl std::fmt::Display for MergeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Could not merge quantities with units: ")?;
+        for (i, key) in self.keys.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "'{}' ({:?})", key.name, key.unit_type)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for MergeError {}
+
+/// Result of [`combine_ingredient_lists_with_conflicts`](crate::combine_ingredient_lists_with_conflicts)
+#[derive(uniffi::Record, Debug, Clone, PartialEq)]
+pub struct CombinedIngredientList {
+    pub ingredients: IngredientList,
+    /// Ingredients with quantities that could not be added together, sorted
+    /// by ingredient name
+    pub conflicts: Vec<QuantityConflict>,
+}
+
+/// An ingredient with quantities of different types, like `5 g` and
+/// `to taste`
+#[derive(uniffi::Record, Debug, Clone, PartialEq)]
+pub struct QuantityConflict {
+    pub ingredient: String,
+    /// Every quantity of the ingredient with the lists it came from
+    pub quantities: Vec<SourcedQuantity>,
+}
+
+/// A quantity and where it came from
+#[derive(uniffi::Record, Debug, Clone, PartialEq)]
+pub struct SourcedQuantity {
+    pub key: GroupedQuantityKey,
+    pub value: Value,
+    /// Indices of the input lists that added up to this value
+    pub sources: Vec<u32>,
+}
+
+/// A recipe of a meal plan and the servings to make of it
+#[derive(uniffi::Record, Debug, Clone, PartialEq)]
+pub struct PlanRecipe {
+    /// Text of the recipe
+    pub input: String,
+    pub servings: u32,
+}
+
+/// Result of [`shopping_list_for_plan`](crate::shopping_list_for_plan)
+#[derive(uniffi::Record, Debug, Clone, PartialEq)]
+pub struct PlanShoppingList {
+    /// Ingredients by category
+    pub categories: HashMap<String, IngredientList>,
+    /// Ingredients with no category in the aisle config
+    pub other: IngredientList,
+    /// Recipes left out because they have errors
+    pub failed: Vec<PlanFailure>,
+}
+
+/// A recipe of a meal plan that couldn't be parsed
+#[derive(uniffi::Record, Debug, Clone, PartialEq)]
+pub struct PlanFailure {
+    /// Index of the recipe in the plan
+    pub index: u32,
+    /// The errors, like in [`CooklangError::Parse`](crate::CooklangError::Parse)
+    pub message: String,
+}
+
+/// Steps of a recipe split by [`recipe_phases`](crate::recipe_phases)
+#[derive(uniffi::Record, Debug, Clone, PartialEq)]
+pub struct RecipePhases {
+    /// Steps that only prepare the ingredients
+    pub prep: Vec<Step>,
+    /// The rest of the steps
+    pub cook: Vec<Step>,
+}
+
+/// How to add two ranges with the same units
+///
+/// No policy is right for every list:
+///
+/// - `Sum` adds the starts and the ends, `1-2` + `3-4` is `4-6`. It's the
+///   total when every range is a different use, but the gap between start
+///   and end grows with every range added, so the max can be far more than
+///   needed.
+/// - `Envelope` keeps the lowest start and the highest end, `1-2` + `3-4` is
+///   `1-4`. It never overstates the max of a single use, but it understates
+///   the total when the ranges are for different uses.
+/// - `Separate` doesn't add them. The first range is kept and the others are
+///   reported as not merged, so each contribution can be shown on its own.
+#[derive(uniffi::Enum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RangePolicy {
+    #[default]
+    Sum,
+    Envelope,
+    Separate,
+}
+
+/// How [`combine_ingredient_lists_with_mode`](crate::combine_ingredient_lists_with_mode)
+/// combines the quantities of an ingredient
+#[derive(uniffi::Enum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AggregateMode {
+    /// Add them
+    #[default]
+    Sum,
+    /// Keep the largest one, like when only the biggest pack has to be bought
+    Max,
+}
+
+impl From<AggregateMode> for cooklang::quantity::AggregateMode {
+    fn from(value: AggregateMode) -> Self {
+        match value {
+            AggregateMode::Sum => Self::Sum,
+            AggregateMode::Max => Self::Max,
+        }
+    }
+}
+
 // I(dubadub) haven't found a way to export these methods with mutable argument
 // Right should be always smaller?
-pub(crate) fn merge_grouped_quantities(left: &mut GroupedQuantity, right: &GroupedQuantity) {
+/// Merges `src` into `dst` entry by entry
+///
+/// Values are added when both have the same key. If a value doesn't match the
+/// type of its key, that entry is left as it was in `dst` and its key is
+/// returned in the error. The rest of the entries are still merged.
+///
+/// Ranges are added with [`RangePolicy::Sum`].
+pub fn merge_grouped_quantity(
+    dst: &mut GroupedQuantity,
+    src: &GroupedQuantity,
+) -> Result<(), MergeError> {
+    merge_grouped_quantity_with_policy(dst, src, RangePolicy::Sum)
+}
+
+/// Like [`merge_grouped_quantity`] but choosing how ranges are added
+///
+/// With [`RangePolicy::Separate`], every range that was not added is
+/// returned in the error.
+pub fn merge_grouped_quantity_with_policy(
+    dst: &mut GroupedQuantity,
+    src: &GroupedQuantity,
+    range_policy: RangePolicy,
+) -> Result<(), MergeError> {
     // options here:
     // - same units:
     //    - same value type
@@ -219,41 +584,113 @@ pub(crate) fn merge_grouped_quantities(left: &mut GroupedQuantity, right: &Group
     //
     //
     // TODO define rules on language spec level
+    let mut clashes = Vec::new();
+
+    for (key, value) in src {
+        let Some(stored) = dst.get_mut(key) else {
+            dst.insert(key.clone(), value.clone());
+            continue;
+        };
+
+        let merged = match (&key.unit_type, &mut *stored, value) {
+            (
+                QuantityType::Number | QuantityType::Count,
+                Value::Number { value: stored },
+                Value::Number { value },
+            ) => {
+                *stored += value;
+                true
+            }
+            (
+                QuantityType::Range,
+                Value::Range { start: s, end: e },
+                Value::Range { start, end },
+            ) => match range_policy {
+                RangePolicy::Sum => {
+                    *s += start;
+                    *e += end;
+                    true
+                }
+                RangePolicy::Envelope => {
+                    *s = s.min(*start);
+                    *e = e.max(*end);
+                    true
+                }
+                RangePolicy::Separate => false,
+            },
+            (QuantityType::Text, Value::Text { value: stored }, Value::Text { value }) => {
+                *stored += value;
+                true
+            }
+            // nothing is required to do, Some + Some = Some
+            (QuantityType::Empty, Value::Empty, Value::Empty) => true,
+            _ => false,
+        };
 
-    right.iter().for_each(|(key, value)| {
-        left
-            .entry(key.clone()) // isn't really necessary?
-            .and_modify(|v| {
-                match key.unit_type {
-                    QuantityType::Number => {
-                        let Value::Number { value: assignable } = value else { panic!("Unexpected type") };
-                        let Value::Number { value: stored } = v else { panic!("Unexpected type") };
-
-                        *stored += assignable
-                    },
-                    QuantityType::Range => {
-                        let Value::Range { start, end } = value else { panic!("Unexpected type") };
-                        let Value::Range { start: s, end: e } = v else { panic!("Unexpected type") };
-
-                        // is it even correct?
-                        *s += start;
-                        *e += end;
-                    },
-                    QuantityType::Text => {
-                        let Value::Text { value: ref assignable } = value else { panic!("Unexpected type") };
-                        let Value::Text { value: stored } = v else { panic!("Unexpected type") };
-
-                        *stored += assignable;
-                    },
-                    QuantityType::Empty => {}, // nothing is required to do, Some + Some = Some
+        if !merged {
+            clashes.push(key.clone());
+        }
+    }
 
-                }
-            })
-            .or_insert(value.clone());
-    });
+    if clashes.is_empty() {
+        Ok(())
+    } else {
+        Err(MergeError { keys: clashes })
+    }
 }
 
-pub(crate) fn into_item(item: &OriginalItem, recipe: &OriginalRecipe) -> Item {
+impl Item {
+    /// Removes the amount of a component, text is left as is
+    pub(crate) fn strip_amount(&mut self) {
+        match self {
+            Item::Ingredient { amount, .. }
+            | Item::Cookware { amount, .. }
+            | Item::Timer { amount, .. } => *amount = None,
+            Item::Text { .. } | Item::Link { .. } => {}
+        }
+    }
+}
+
+/// Quantities of the components as written in the recipe, in the same order
+/// as the components of the parsed recipe
+#[derive(Debug, Default)]
+pub(crate) struct RawQuantities {
+    ingredients: Vec<Option<String>>,
+    cookware: Vec<Option<String>>,
+    timers: Vec<Option<String>>,
+}
+
+impl RawQuantities {
+    /// Records the quantity text of a component event
+    ///
+    /// A relative quantity, like `=flour`, is resolved to the value and unit
+    /// of the other ingredient, so its text is not kept. Writing the text
+    /// with the resolved unit would not be a valid quantity.
+    pub(crate) fn record(&mut self, event: &Event, input: &str) {
+        let raw = |span: Span| {
+            let text = input[span.range()].trim();
+            (!text.starts_with('=')).then(|| text.to_string())
+        };
+        match event {
+            Event::Ingredient(i) => self
+                .ingredients
+                .push(i.quantity.as_ref().and_then(|q| raw(q.value.span()))),
+            Event::Cookware(c) => self
+                .cookware
+                .push(c.quantity.as_ref().and_then(|q| raw(q.span()))),
+            Event::Timer(t) => self
+                .timers
+                .push(t.quantity.as_ref().and_then(|q| raw(q.value.span()))),
+            _ => {}
+        }
+    }
+}
+
+pub(crate) fn into_item(item: &OriginalItem, recipe: &OriginalRecipe, raw: &RawQuantities) -> Item {
+    let with_raw = |amount: Amount, raw: &[Option<String>], index: usize| Amount {
+        raw: raw.get(index).cloned().flatten(),
+        ..amount
+    };
     match item {
         OriginalItem::Text { value } => Item::Text {
             value: value.to_string(),
@@ -263,7 +700,19 @@ pub(crate) fn into_item(item: &OriginalItem, recipe: &OriginalRecipe) -> Item {
 
             Item::Ingredient {
                 name: ingredient.name.clone(),
-                amount: ingredient.quantity.as_ref().map(|q| q.extract_amount()),
+                amount: ingredient
+                    .quantity
+                    .as_ref()
+                    .map(|q| with_raw(q.extract_amount(), &raw.ingredients, *index)),
+                prep_order: ingredient.prep_order,
+                hidden: ingredient.modifiers().is_hidden(),
+                reference: ingredient.modifiers().is_reference()
+                    || ingredient.relation.is_regular_reference(),
+                optional: ingredient.modifiers().is_optional(),
+                recipe: ingredient.modifiers().is_recipe(),
+                new: ingredient.modifiers().contains(Modifiers::NEW),
+                alias: ingredient.alias.clone(),
+                note: ingredient.note.clone(),
             }
         }
 
@@ -271,7 +720,10 @@ pub(crate) fn into_item(item: &OriginalItem, recipe: &OriginalRecipe) -> Item {
             let cookware = &recipe.cookware[*index];
             Item::Cookware {
                 name: cookware.name.clone(),
-                amount: cookware.quantity.as_ref().map(|q| q.extract_amount()),
+                amount: cookware
+                    .quantity
+                    .as_ref()
+                    .map(|q| with_raw(q.extract_amount(), &raw.cookware, *index)),
             }
         }
 
@@ -280,58 +732,186 @@ pub(crate) fn into_item(item: &OriginalItem, recipe: &OriginalRecipe) -> Item {
 
             Item::Timer {
                 name: timer.name.clone(),
-                amount: timer.quantity.as_ref().map(|q| q.extract_amount()),
+                amount: timer
+                    .quantity
+                    .as_ref()
+                    .map(|q| with_raw(q.extract_amount(), &raw.timers, *index)),
             }
         }
 
-        // returning an empty block of text as it's not supported by the spec
-        OriginalItem::InlineQuantity { index: _ } => Item::Text {
-            value: "".to_string(),
+        // inline quantities are not in the spec, so they are kept as text
+        OriginalItem::InlineQuantity { index } => Item::Text {
+            value: recipe.inline_quantities[*index].to_string(),
         },
+
+        OriginalItem::Link { url, text } => Item::Link {
+            url: url.clone(),
+            text: text.clone(),
+        },
+    }
+}
+
+/// Ingredient list of a scaled recipe, built like the one of
+/// [`simplify_recipe_data`] with the default options
+pub(crate) fn scaled_ingredient_list(recipe: &cooklang::ScaledRecipe) -> IngredientList {
+    let mut list = IngredientList::default();
+    let items = recipe
+        .sections
+        .iter()
+        .flat_map(|section| &section.content)
+        .filter_map(|content| match content {
+            cooklang::Content::Step(step) => Some(&step.items),
+            cooklang::Content::Text(_) => None,
+        })
+        .flatten();
+    for item in items {
+        let OriginalItem::Ingredient { index } = item else {
+            continue;
+        };
+        let ingredient = &recipe.ingredients[*index];
+        if ingredient.modifiers().is_hidden() {
+            continue;
+        }
+        let amount = ingredient.quantity.as_ref().map(|q| q.extract_amount());
+        add_to_ingredient_list(&mut list, &ingredient.name, &into_group_quantity(&amount));
     }
+    list
 }
 
-pub(crate) fn into_simple_recipe(recipe: &OriginalRecipe) -> CooklangRecipe {
+/// Converts an ingredient list of the parser to the one of the bindings
+pub(crate) fn into_ingredient_list(
+    original: &cooklang::ingredient_list::IngredientList,
+) -> IngredientList {
+    let mut list = IngredientList::default();
+    for (name, grouped) in original.iter() {
+        if grouped.is_empty() {
+            add_to_ingredient_list(&mut list, name, &into_group_quantity(&None));
+        }
+        for quantity in grouped.iter() {
+            let amount = Some(quantity.extract_amount());
+            add_to_ingredient_list(&mut list, name, &into_group_quantity(&amount));
+        }
+    }
+    list
+}
+
+/// Converts an ingredient list of the bindings to the one of the parser
+pub(crate) fn into_original_ingredient_list(
+    list: &IngredientList,
+    converter: &Converter,
+) -> cooklang::ingredient_list::IngredientList {
+    let mut original = cooklang::ingredient_list::IngredientList::new();
+    for (name, quantity) in list {
+        let mut grouped = cooklang::quantity::GroupedQuantity::empty();
+        for (key, value) in quantity {
+            let amount = Amount {
+                quantity: value.clone(),
+                units: Some(key.name.clone()).filter(|u| !u.is_empty()),
+                raw: None,
+                approximate: false,
+                unit_locked: false,
+            };
+            if let Some(q) = into_original_quantity(&amount) {
+                grouped.add(&q, converter);
+            }
+        }
+        original.add_ingredient(name.clone(), &grouped, converter);
+    }
+    original
+}
+
+pub(crate) fn simplify_recipe_data(
+    recipe: &OriginalRecipe,
+    options: &RecipeOptions,
+    raw: &RawQuantities,
+) -> CooklangRecipe {
     let mut metadata = CooklangMetadata::new();
     let mut steps: Vec<Step> = Vec::new();
+    let mut sections: Vec<Section> = Vec::new();
     let mut ingredients: IngredientList = IngredientList::default();
+    let mut ingredient_order: Vec<String> = Vec::new();
     let mut cookware: Vec<Item> = Vec::new();
     let mut items: Vec<Item> = Vec::new();
+    let verbs = ActionVerbs::default().with_verbs(&options.extra_action_verbs);
 
     recipe.sections.iter().for_each(|section| {
+        let section_start = steps.len();
         section.content.iter().for_each(|content| {
             if let cooklang::Content::Step(step) = content {
                 step.items.iter().for_each(|i| {
-                    let item = into_item(i, recipe);
+                    let item = into_item(i, recipe, raw);
 
                     match item {
                         Item::Ingredient {
                             ref name,
                             ref amount,
-                        } => {
+                            hidden,
+                            ..
+                        } if !hidden || options.include_hidden_ingredients => {
+                            let referenced = match i {
+                                OriginalItem::Ingredient { index } => {
+                                    recipe.ingredients[*index].referenced_recipe()
+                                }
+                                _ => None,
+                            };
+                            let name = match referenced {
+                                Some(recipe_name) if options.recipe_refs_as_ingredients => {
+                                    recipe_name.to_string()
+                                }
+                                _ => name.clone(),
+                            };
+                            if !ingredients.contains_key(&name) {
+                                ingredient_order.push(name.clone());
+                            }
                             let quantity = into_group_quantity(amount);
 
-                            add_to_ingredient_list(&mut ingredients, name, &quantity);
+                            add_to_ingredient_list(&mut ingredients, &name, &quantity);
                         }
                         Item::Cookware { .. } => {
                             cookware.push(item.clone());
                         }
-                        // don't need anything if timer or text
+                        // the text of an inline quantity joins the text around
+                        Item::Text { ref value } => {
+                            if let Some(Item::Text { value: last }) = items.last_mut() {
+                                last.push_str(value);
+                                return;
+                            }
+                        }
+                        // don't need anything if timer
                         _ => (),
                     };
                     items.push(item);
                 });
+                let has_temperature = items.iter().any(|i| match i {
+                    Item::Text { value } => TEMPERATURE_CONVERTER.has_temperature(value),
+                    _ => false,
+                });
                 // TODO: think how to make it faster as we probably
                 // can switch items content directly into the step object without cloning it
                 steps.push(Step {
                     items: items.clone(),
+                    actions: step_actions(step, &verbs),
+                    note: step.note.clone(),
+                    has_temperature,
                 });
 
                 items.clear();
             }
         });
+        sections.push(Section {
+            name: section.name.clone(),
+            steps: steps[section_start..].to_vec(),
+            yields: section.yields.as_ref().map(|q| q.extract_amount()),
+        });
     });
 
+    if options.collapse_single_section
+        && sections.len() == 1
+        && sections.iter().all(|s| s.name.is_none())
+    {
+        sections.clear();
+    }
+
     recipe.metadata.map.iter().for_each(|(key, value)| {
         metadata.insert(key.to_string(), value.to_string());
     });
@@ -340,6 +920,8 @@ pub(crate) fn into_simple_recipe(recipe: &OriginalRecipe) -> CooklangRecipe {
         metadata,
         steps,
         ingredients,
+        ingredient_order,
         cookware,
+        sections,
     }
 }
@@ -0,0 +1,549 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use cooklang::convert::{
+    DisplayPreferences as OriginalDisplayPreferences, PhysicalQuantity as OriginalPhysicalQuantity,
+    System,
+};
+use cooklang::ingredient_list::quantity_totals;
+use cooklang::quantity::UnitInfo;
+use cooklang::timers::TimeBreakdown as OriginalTimeBreakdown;
+use cooklang::Converter;
+
+use crate::error::CooklangError;
+use crate::model::{
+    extract_value, into_original_metadata, into_original_quantity, Amount, CooklangRecipe,
+    GroupedQuantity, Item, Value,
+};
+
+/// Preferred unit to show each physical quantity, like `ml` for volume
+///
+/// Physical quantities without a preference use the best fitting unit.
+#[derive(uniffi::Record, Debug, Clone, Default)]
+pub struct DisplayPreferences {
+    #[uniffi(default = None)]
+    pub volume: Option<String>,
+    #[uniffi(default = None)]
+    pub mass: Option<String>,
+    #[uniffi(default = None)]
+    pub length: Option<String>,
+    #[uniffi(default = None)]
+    pub temperature: Option<String>,
+    #[uniffi(default = None)]
+    pub time: Option<String>,
+}
+
+impl DisplayPreferences {
+    fn into_original(self) -> OriginalDisplayPreferences {
+        let mut preferences = OriginalDisplayPreferences::default();
+        let units = [
+            (OriginalPhysicalQuantity::Volume, self.volume),
+            (OriginalPhysicalQuantity::Mass, self.mass),
+            (OriginalPhysicalQuantity::Length, self.length),
+            (OriginalPhysicalQuantity::Temperature, self.temperature),
+            (OriginalPhysicalQuantity::Time, self.time),
+        ];
+        for (quantity, unit) in units {
+            if let Some(unit) = unit {
+                preferences.set_unit(quantity, unit);
+            }
+        }
+        preferences
+    }
+}
+
+/// What a unit measures
+#[derive(uniffi::Enum, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PhysicalQuantity {
+    Volume,
+    Mass,
+    Length,
+    Temperature,
+    Time,
+}
+
+impl From<OriginalPhysicalQuantity> for PhysicalQuantity {
+    fn from(value: OriginalPhysicalQuantity) -> Self {
+        match value {
+            OriginalPhysicalQuantity::Volume => Self::Volume,
+            OriginalPhysicalQuantity::Mass => Self::Mass,
+            OriginalPhysicalQuantity::Length => Self::Length,
+            OriginalPhysicalQuantity::Temperature => Self::Temperature,
+            OriginalPhysicalQuantity::Time => Self::Time,
+        }
+    }
+}
+
+/// Share of the total mass of an ingredient, from 0 to 100
+#[derive(uniffi::Record, Debug, Clone, PartialEq)]
+pub struct IngredientPercentage {
+    pub name: String,
+    pub percentage: f64,
+}
+
+#[derive(uniffi::Record, Debug, Clone, PartialEq)]
+pub struct IngredientPercentages {
+    /// In the order of [`CooklangRecipe::ingredient_order`]
+    pub percentages: Vec<IngredientPercentage>,
+    /// Ingredients with some quantity missing, text or not a mass
+    pub excluded: Vec<String>,
+}
+
+/// Mass of an ingredient in grams
+#[derive(uniffi::Record, Debug, Clone, PartialEq)]
+pub struct IngredientGrams {
+    pub name: String,
+    /// [`None`] if some quantity is missing, text or not a mass
+    pub grams: Option<f64>,
+}
+
+/// One ingredient of a recipe as a flat record, like a row of a table
+///
+/// Created from [`UnitConverter::ingredient_rows`].
+#[derive(uniffi::Record, Debug, Clone, PartialEq)]
+pub struct IngredientRow {
+    pub name: String,
+    /// Number of the amount, [`None`] if there is no amount or it is text
+    ///
+    /// For ranges, the start of the range is used.
+    pub value: Option<f64>,
+    /// Unit as written in the recipe
+    pub unit: Option<String>,
+    /// The value in the base unit, like grams for a mass
+    ///
+    /// [`None`] if the unit is not known.
+    pub base_value: Option<f64>,
+    /// [`None`] if the unit is not known
+    pub physical_quantity: Option<PhysicalQuantity>,
+    /// Marked as optional with `@?`
+    pub optional: bool,
+    /// Marked as hidden with `@-`
+    pub hidden: bool,
+    /// Number of the step, starting at 1 across all the sections
+    pub step_number: Option<u32>,
+}
+
+/// The timer with the longest duration in a recipe
+#[derive(uniffi::Record, Debug, Clone, PartialEq)]
+pub struct LongestTimer {
+    /// Duration of the timer in seconds
+    pub seconds: f64,
+    /// Number of the step in [`CooklangRecipe::steps`], starting at 1
+    pub step_number: u32,
+    pub name: Option<String>,
+    pub amount: Option<Amount>,
+}
+
+/// Active and passive time of a recipe, in seconds
+///
+/// Created from [`UnitConverter::time_breakdown`].
+#[derive(uniffi::Record, Debug, Clone, PartialEq)]
+pub struct TimeBreakdown {
+    /// Time of the steps that need attention
+    pub active: f64,
+    /// Time waiting
+    pub passive: f64,
+}
+
+/// Counts to estimate how hard a recipe is
+#[derive(uniffi::Record, Debug, Clone, PartialEq)]
+pub struct ComplexityMetrics {
+    /// Number of different ingredients
+    pub ingredients: u32,
+    pub steps: u32,
+    /// Sum of the duration of all timers in seconds
+    ///
+    /// Timers without a time unit or with a text value are skipped.
+    pub timer_seconds: f64,
+    /// Number of different cookware items
+    pub cookware: u32,
+}
+
+/// Unit conversion configuration
+#[derive(uniffi::Object, Debug)]
+pub struct UnitConverter {
+    pub(crate) converter: Converter,
+}
+
+#[uniffi::export]
+impl UnitConverter {
+    /// Converter with the bundled units
+    #[uniffi::constructor]
+    pub fn bundled() -> Arc<Self> {
+        Arc::new(Self {
+            converter: Converter::bundled(),
+        })
+    }
+
+    /// Converter with the bundled units that prefers metric units when
+    /// fitting amounts
+    #[uniffi::constructor]
+    pub fn metric() -> Arc<Self> {
+        Self::with_preferred_system(System::Metric)
+    }
+
+    /// Converter with the bundled units that prefers imperial units when
+    /// fitting amounts
+    #[uniffi::constructor]
+    pub fn imperial() -> Arc<Self> {
+        Self::with_preferred_system(System::Imperial)
+    }
+
+    /// Converts the amount to the base unit of its physical quantity, like
+    /// grams for mass or litres for volume
+    ///
+    /// Returns `None` for text or empty values, and amounts without units or
+    /// with unknown units.
+    pub fn to_base(&self, amount: Amount) -> Option<f64> {
+        into_original_quantity(&amount)?.to_base(&self.converter)
+    }
+
+    /// Converts the amount to the preferred unit for its physical quantity,
+    /// or to the best fitting unit if there is no preference
+    ///
+    /// If it can't be converted, the amount is returned unchanged.
+    pub fn fit_amount(&self, amount: Amount, preferences: DisplayPreferences) -> Amount {
+        let Some(mut quantity) = into_original_quantity(&amount) else {
+            return amount;
+        };
+        let preferences = preferences.into_original();
+        if quantity
+            .fit_with_preferences(&preferences, &self.converter)
+            .is_err()
+        {
+            return amount;
+        }
+        Amount {
+            quantity: extract_value(&quantity.value),
+            units: quantity.unit_text().map(String::from),
+            raw: None,
+            approximate: quantity.approximate,
+            unit_locked: quantity.unit_locked,
+        }
+    }
+
+    /// Formats an amount like [`format_amount`](crate::format_amount), but
+    /// first converted to the preferred unit like in
+    /// [`UnitConverter::fit_amount`]
+    #[uniffi::method(default(ascii = false))]
+    pub fn format_amount(
+        &self,
+        amount: Amount,
+        preferences: DisplayPreferences,
+        locale: String,
+        ascii: bool,
+    ) -> String {
+        let Some(quantity) = into_original_quantity(&amount) else {
+            return crate::format_amount(amount, locale, ascii);
+        };
+        quantity.format_with_preferences(
+            &crate::format_options(&locale, ascii),
+            &preferences.into_original(),
+            &self.converter,
+        )
+    }
+
+    /// Converts an amount to another unit, like `cup` to `ml`
+    ///
+    /// Fails with [`CooklangError::Conversion`] for text or empty amounts,
+    /// amounts without a unit, unknown units and units of another physical
+    /// quantity.
+    pub fn convert(&self, amount: Amount, unit: String) -> Result<Amount, CooklangError> {
+        let mut quantity =
+            into_original_quantity(&amount).ok_or_else(|| CooklangError::Conversion {
+                message: "The amount has no value".to_string(),
+            })?;
+        quantity.convert(unit.as_str(), &self.converter)?;
+        Ok(Amount {
+            quantity: extract_value(&quantity.value),
+            units: quantity.unit_text().map(String::from),
+            raw: None,
+            approximate: quantity.approximate,
+            unit_locked: quantity.unit_locked,
+        })
+    }
+
+    /// What a unit measures, like mass for `kg`
+    ///
+    /// Returns `None` for unknown units.
+    pub fn unit_category(&self, unit: String) -> Option<PhysicalQuantity> {
+        match UnitInfo::new(&unit, &self.converter) {
+            UnitInfo::Known(unit) => Some(unit.physical_quantity.into()),
+            UnitInfo::Unknown => None,
+        }
+    }
+
+    /// Share of the total mass of each ingredient
+    pub fn ingredient_percentages(&self, recipe: CooklangRecipe) -> IngredientPercentages {
+        let metadata = into_original_metadata(&recipe.metadata, &self.converter);
+        let mut masses = Vec::new();
+        let mut excluded = Vec::new();
+        for name in recipe.ingredient_order {
+            let density = metadata.density(&name, &self.converter);
+            match self.grams(&recipe.ingredients[&name], density) {
+                Some(mass) => masses.push((name, mass)),
+                None => excluded.push(name),
+            }
+        }
+
+        let total: f64 = masses.iter().map(|(_, m)| m).sum();
+        if total <= 0.0 {
+            excluded.extend(masses.into_iter().map(|(name, _)| name));
+            masses = Vec::new();
+        }
+        IngredientPercentages {
+            percentages: masses
+                .into_iter()
+                .map(|(name, mass)| IngredientPercentage {
+                    name,
+                    percentage: mass / total * 100.0,
+                })
+                .collect(),
+            excluded,
+        }
+    }
+
+    /// Mass in grams of each ingredient, for things like nutrition
+    /// calculators
+    ///
+    /// Volumes are converted with the density of the ingredient in the
+    /// metadata, like `>> density.flour: 0.53 g/ml`.
+    ///
+    /// In the order of [`CooklangRecipe::ingredient_order`].
+    pub fn ingredient_grams(&self, recipe: CooklangRecipe) -> Vec<IngredientGrams> {
+        let metadata = into_original_metadata(&recipe.metadata, &self.converter);
+        recipe
+            .ingredient_order
+            .into_iter()
+            .map(|name| {
+                let density = metadata.density(&name, &self.converter);
+                IngredientGrams {
+                    grams: self.grams(&recipe.ingredients[&name], density),
+                    name,
+                }
+            })
+            .collect()
+    }
+
+    /// Every ingredient of a recipe, references included, as an
+    /// [`IngredientRow`], for data pipelines like loading them in a database
+    ///
+    /// Rows are in the order of the ingredients in
+    /// [`CooklangRecipe::steps`].
+    pub fn ingredient_rows(&self, recipe: CooklangRecipe) -> Vec<IngredientRow> {
+        let mut rows = Vec::new();
+        for (step_index, step) in recipe.steps.into_iter().enumerate() {
+            for item in step.items {
+                let Item::Ingredient {
+                    name,
+                    amount,
+                    hidden,
+                    optional,
+                    ..
+                } = item
+                else {
+                    continue;
+                };
+                let quantity = amount.as_ref().and_then(into_original_quantity);
+                let value = amount.as_ref().and_then(|a| match a.quantity {
+                    Value::Number { value } => Some(value),
+                    Value::Range { start, .. } => Some(start),
+                    Value::Text { .. } | Value::Empty => None,
+                });
+                let unit = amount.and_then(|a| a.units);
+                rows.push(IngredientRow {
+                    name,
+                    value,
+                    base_value: quantity.and_then(|q| q.to_base(&self.converter)),
+                    physical_quantity: unit.clone().and_then(|u| self.unit_category(u)),
+                    unit,
+                    optional,
+                    hidden,
+                    step_number: Some(step_index as u32 + 1),
+                });
+            }
+        }
+        rows
+    }
+
+    /// The timer with the longest duration, like a 2 hour proof
+    ///
+    /// Timers without a time unit or with a text value are skipped. A range
+    /// is as long as its end. If many timers are the longest, the first one
+    /// is returned.
+    pub fn longest_timer(&self, recipe: CooklangRecipe) -> Option<LongestTimer> {
+        let mut longest: Option<LongestTimer> = None;
+        for (step_index, step) in recipe.steps.into_iter().enumerate() {
+            for item in step.items {
+                let Item::Timer { name, amount } = item else {
+                    continue;
+                };
+                let Some(seconds) = amount.as_ref().and_then(|a| {
+                    let mut longest = a.clone();
+                    if let Value::Range { end, .. } = a.quantity {
+                        longest.quantity = Value::Number { value: end };
+                    }
+                    self.in_base(&longest, OriginalPhysicalQuantity::Time)
+                }) else {
+                    continue;
+                };
+                if longest.as_ref().is_none_or(|l| seconds > l.seconds) {
+                    longest = Some(LongestTimer {
+                        seconds,
+                        step_number: step_index as u32 + 1,
+                        name,
+                        amount,
+                    });
+                }
+            }
+        }
+        longest
+    }
+
+    /// Split of the timers time in active and passive time
+    ///
+    /// A timer in a step without ingredients is a wait, so it is passive
+    /// time. Any other timer is active time. The `time` metadata is not used.
+    /// Timers without a time unit or with a text value are skipped, so a
+    /// recipe without timers has both times at 0.
+    pub fn time_breakdown(&self, recipe: CooklangRecipe) -> TimeBreakdown {
+        let mut breakdown = OriginalTimeBreakdown::default();
+        for step in &recipe.steps {
+            let has_ingredients = step
+                .items
+                .iter()
+                .any(|item| matches!(item, Item::Ingredient { .. }));
+            let timers = step
+                .items
+                .iter()
+                .filter_map(|item| match item {
+                    Item::Timer {
+                        amount: Some(amount),
+                        ..
+                    } => into_original_quantity(amount),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            breakdown.add_step(has_ingredients, &timers, &self.converter);
+        }
+        TimeBreakdown {
+            active: breakdown.active,
+            passive: breakdown.passive,
+        }
+    }
+
+    /// Number of ingredients, steps and cookware, and the total timer time
+    pub fn complexity_metrics(&self, recipe: CooklangRecipe) -> ComplexityMetrics {
+        let timer_seconds = recipe
+            .steps
+            .iter()
+            .flat_map(|step| &step.items)
+            .filter_map(|item| match item {
+                Item::Timer {
+                    amount: Some(amount),
+                    ..
+                } => self.in_base(amount, OriginalPhysicalQuantity::Time),
+                _ => None,
+            })
+            .sum();
+        let cookware = recipe
+            .cookware
+            .iter()
+            .filter_map(|item| match item {
+                Item::Cookware { name, .. } => Some(name),
+                _ => None,
+            })
+            .collect::<HashSet<_>>();
+        ComplexityMetrics {
+            ingredients: recipe.ingredient_order.len() as u32,
+            steps: recipe.steps.len() as u32,
+            timer_seconds,
+            cookware: cookware.len() as u32,
+        }
+    }
+
+    /// Total amount of the ingredients of each physical quantity, like the
+    /// total mass and the total volume
+    ///
+    /// Each total is in the base unit, grams for mass or litres for volume.
+    /// Quantities without units, with unknown units or with a text value are
+    /// skipped, and so are temperatures.
+    pub fn totals_by_category(&self, recipe: CooklangRecipe) -> HashMap<PhysicalQuantity, Amount> {
+        let quantities = recipe
+            .ingredients
+            .values()
+            .flatten()
+            .filter_map(|(key, value)| {
+                into_original_quantity(&Amount {
+                    quantity: value.clone(),
+                    units: Some(key.name.clone()).filter(|u| !u.is_empty()),
+                    raw: None,
+                    approximate: false,
+                    unit_locked: false,
+                })
+            })
+            .collect::<Vec<_>>();
+        quantity_totals(&quantities, &self.converter)
+            .into_iter()
+            .map(|(physical_quantity, total)| {
+                let amount = Amount {
+                    quantity: Value::Number { value: total },
+                    units: self.base_unit(physical_quantity),
+                    raw: None,
+                    approximate: false,
+                    unit_locked: false,
+                };
+                (physical_quantity.into(), amount)
+            })
+            .collect()
+    }
+}
+
+impl UnitConverter {
+    /// Symbol of the unit the others are defined relative to
+    fn base_unit(&self, physical_quantity: OriginalPhysicalQuantity) -> Option<String> {
+        self.converter
+            .all_units()
+            .find(|u| {
+                u.physical_quantity == physical_quantity && u.ratio == 1.0 && u.difference == 0.0
+            })
+            .map(|u| u.symbol().to_string())
+    }
+
+    fn with_preferred_system(system: System) -> Arc<Self> {
+        let mut converter = Converter::bundled();
+        converter.set_preferred_system(Some(system));
+        Arc::new(Self { converter })
+    }
+
+    /// Sum of the quantities in grams, if all of them are a mass or a volume
+    /// with a density
+    fn grams(&self, quantity: &GroupedQuantity, density: Option<f64>) -> Option<f64> {
+        quantity
+            .iter()
+            .map(|(key, value)| {
+                let amount = Amount {
+                    quantity: value.clone(),
+                    units: Some(key.name.clone()).filter(|u| !u.is_empty()),
+                    raw: None,
+                    approximate: false,
+                    unit_locked: false,
+                };
+                self.in_base(&amount, OriginalPhysicalQuantity::Mass)
+                    .or_else(|| {
+                        Some(self.in_base(&amount, OriginalPhysicalQuantity::Volume)? * density?)
+                    })
+            })
+            .sum()
+    }
+
+    fn in_base(&self, amount: &Amount, physical_quantity: OriginalPhysicalQuantity) -> Option<f64> {
+        let quantity = into_original_quantity(amount)?;
+        match quantity.unit()?.unit_info_or_parse(&self.converter) {
+            UnitInfo::Known(unit) if unit.physical_quantity == physical_quantity => {
+                quantity.to_base(&self.converter)
+            }
+            _ => None,
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use cooklang::ast::build_ast;
 use cooklang::error::SourceReport;
+use cooklang::quantity::{to_ascii, FormatOptions};
 use cooklang::{parser::PullParser, Extensions};
 use cooklang::{Converter, CooklangParser, IngredientReferenceTarget, Item};
 use std::fmt::Write;
@@ -10,11 +11,17 @@ pub fn version() -> String {
     include_str!(concat!(env!("OUT_DIR"), "/version")).to_string()
 }
 
+/// All the extensions but the number separators, so `1.000` is still one
+fn default_extensions() -> Extensions {
+    Extensions::all().difference(Extensions::GROUP_SEPARATORS | Extensions::DECIMAL_COMMA)
+}
+
 #[wasm_bindgen]
 pub struct State {
     parser: CooklangParser,
     load_units: bool,
     extensions: Extensions,
+    ascii: bool,
 }
 
 #[wasm_bindgen]
@@ -22,9 +29,10 @@ impl State {
     #[wasm_bindgen(constructor)]
     pub fn new() -> Self {
         Self {
-            parser: CooklangParser::new(Extensions::all(), Converter::bundled()),
+            parser: CooklangParser::new(default_extensions(), Converter::bundled()),
             load_units: true,
-            extensions: Extensions::all(),
+            extensions: default_extensions(),
+            ascii: false,
         }
     }
 
@@ -38,6 +46,15 @@ impl State {
         self.update_parser();
     }
 
+    #[wasm_bindgen(getter)]
+    pub fn ascii(&self) -> bool {
+        self.ascii
+    }
+    #[wasm_bindgen(setter)]
+    pub fn set_ascii(&mut self, ascii: bool) {
+        self.ascii = ascii;
+    }
+
     #[wasm_bindgen(getter)]
     pub fn extensions(&self) -> u32 {
         self.extensions.bits()
@@ -97,7 +114,11 @@ impl State {
                 } else {
                     r.default_scale()
                 };
-                render(r, self.parser.converter())
+                let options = FormatOptions {
+                    ascii: self.ascii,
+                    ..Default::default()
+                };
+                render(r, self.parser.converter(), &options)
             }
             None => "<no ouput>".to_string(),
         };
@@ -137,14 +158,21 @@ impl FallibleResult {
     }
 }
 
-fn render(r: cooklang::ScaledRecipe, converter: &Converter) -> String {
+fn render(r: cooklang::ScaledRecipe, converter: &Converter, options: &FormatOptions) -> String {
     let ingredient_list = r.group_ingredients(converter);
+    let text = |s: &str| {
+        if options.ascii {
+            to_ascii(s).into_owned()
+        } else {
+            s.to_string()
+        }
+    };
     maud::html! {
         @if !r.metadata.map.is_empty() {
             ul {
                 @for (key, value) in &r.metadata.map {
                     li.metadata {
-                        span.key { (key) } ":" (value)
+                        span.key { (text(key)) } ":" (text(value))
                     }
                 }
             }
@@ -157,9 +185,12 @@ fn render(r: cooklang::ScaledRecipe, converter: &Converter) -> String {
             ul {
                 @for entry in &ingredient_list {
                     li {
-                        b { (entry.ingredient.display_name()) }
-                        @if !entry.quantity.is_empty() {": " (entry.quantity) }
-                        @if let Some(n) = &entry.ingredient.note { " (" (n) ")" }
+                        b { (text(&entry.ingredient.display_name())) }
+                        @if !entry.quantity.is_empty() {
+                            ": "
+                            (entry.quantity.iter().map(|q| q.display_with(options).to_string()).collect::<Vec<_>>().join(", "))
+                        }
+                        @if let Some(n) = &entry.ingredient.note { " (" (text(n)) ")" }
                     }
                 }
             }
@@ -169,13 +200,13 @@ fn render(r: cooklang::ScaledRecipe, converter: &Converter) -> String {
             ul {
                 @for item in r.cookware.iter().filter(|c| c.modifiers().should_be_listed()) {
                     @let amount = item.group_amounts(&r.cookware).iter()
-                                        .map(|q| q.to_string())
+                                        .map(|q| q.display_with(options).to_string())
                                         .reduce(|s, q| format!("{s}, {q}"))
                                         .unwrap_or(String::new());
                     li {
-                        b { (item.display_name()) }
+                        b { (text(item.display_name())) }
                         @if !amount.is_empty() { ": " (amount) }
-                        @if let Some(n) = &item.note { " (" (n) ")" }
+                        @if let Some(n) = &item.note { " (" (text(n)) ")" }
                     }
                 }
             }
@@ -186,14 +217,14 @@ fn render(r: cooklang::ScaledRecipe, converter: &Converter) -> String {
         @for (s_index, section) in r.sections.iter().enumerate() {
             @let s_num = s_index + 1;
             @if let Some(name) = &section.name {
-                h3 { "(" (s_num) ") " (name) }
+                h3 { "(" (s_num) ") " (text(name)) }
             } @else if r.sections.len() > 1 {
                 h3 { "Section " (s_num) }
             }
 
             @for content in &section.content {
                 @match content {
-                    cooklang::Content::Text(t) => p { (t) },
+                    cooklang::Content::Text(t) => p { (text(t)) },
                     cooklang::Content::Step(s) => p {
                         b { (s.number) ". " }
                         @for item in &s.items {
@@ -201,9 +232,9 @@ fn render(r: cooklang::ScaledRecipe, converter: &Converter) -> String {
                                 Item::Ingredient { index } => {
                                     @let igr = &r.ingredients[*index];
                                     span.ingredient {
-                                        (igr.display_name())
+                                        (text(&igr.display_name()))
                                         @if let Some(q) = &igr.quantity {
-                                            i { "(" (q) ")" }
+                                            i { "(" (q.display_with(options)) ")" }
                                         }
                                         @if let Some((index, target)) = &igr.relation.references_to() {
                                             @match target {
@@ -222,9 +253,9 @@ fn render(r: cooklang::ScaledRecipe, converter: &Converter) -> String {
                                 Item::Cookware { index } => {
                                     @let cw = &r.cookware[*index];
                                     span.cookware {
-                                        (cw.display_name())
+                                        (text(cw.display_name()))
                                         @if let Some(q) = &cw.quantity {
-                                            i { "(" (q) ")" }
+                                            i { "(" (q.display_with(options)) ")" }
                                         }
                                     }
                                 }
@@ -232,19 +263,22 @@ fn render(r: cooklang::ScaledRecipe, converter: &Converter) -> String {
                                     @let tm = &r.timers[*index];
                                     span.timer {
                                         @if let Some(name) = &tm.name {
-                                            "(" (name) ")"
+                                            "(" (text(name)) ")"
                                         }
                                         @if let Some(q) = &tm.quantity {
-                                            i { (q) }
+                                            i { (q.display_with(options)) }
                                         }
                                     }
                                 }
                                 Item::InlineQuantity { index } => {
                                     @let q = &r.inline_quantities[*index];
-                                    i.temp { (q) }
+                                    i.temp { (q.display_with(options)) }
                                 }
                                 Item::Text { value } => {
-                                    (value)
+                                    (text(value))
+                                }
+                                Item::Link { url, text: t } => {
+                                    a href=(url) { (text(t)) }
                                 }
                             }
                         }
@@ -0,0 +1,265 @@
+//! Extract cooking actions from steps
+//!
+//! Cooking steps are usually written in the imperative: "Chop the onions,
+//! then fry them". The actions are the verbs that start each sentence or
+//! clause. Only words in a known list of [`ActionVerbs`] are recognised, so
+//! the result is conservative.
+
+use std::collections::HashSet;
+
+use crate::model::{Content, Item, Recipe, Step};
+use crate::quantity::QuantityValue;
+
+/// Common cooking verbs in English
+const DEFAULT_VERBS: &[&str] = &[
+    "add", "arrange", "bake", "baste", "beat", "blanch", "blend", "boil", "braise", "bring",
+    "broil", "brown", "brush", "chill", "chop", "combine", "cook", "cool", "cover", "cream",
+    "crush", "cut", "deglaze", "dice", "drain", "drizzle", "dust", "fold", "fry", "garnish",
+    "grate", "grill", "heat", "knead", "let", "marinate", "mash", "melt", "mince", "mix", "peel",
+    "place", "poach", "pour", "preheat", "reduce", "remove", "rest", "roast", "roll", "rinse",
+    "saute", "sauté", "season", "sear", "serve", "set", "simmer", "slice", "soak", "spread",
+    "sprinkle", "steam", "stir", "strain", "transfer", "toss", "whisk",
+];
+
+/// Verbs of the [`DEFAULT_VERBS`] that prepare the ingredients before cooking
+const DEFAULT_PREP_VERBS: &[&str] = &[
+    "chop", "crush", "cut", "dice", "grate", "marinate", "mince", "peel", "rinse", "slice", "soak",
+];
+
+/// Words that start a new clause inside a sentence, like "..., then bake"
+const CLAUSE_STARTERS: &[&str] = &["then", "and"];
+
+/// Set of verbs recognised as actions
+///
+/// The [`Default`] contains common cooking verbs in English. Matching is case
+/// insensitive.
+///
+/// ```
+/// # use cooklang::actions::ActionVerbs;
+/// let verbs = ActionVerbs::default().with_verbs(["flambé"]);
+/// assert!(verbs.contains("Bake"));
+/// assert!(verbs.contains("flambé"));
+/// assert!(!ActionVerbs::new(["hornear"]).contains("bake"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ActionVerbs {
+    verbs: HashSet<String>,
+}
+
+impl ActionVerbs {
+    /// Create a new set with only the given verbs
+    pub fn new<S: AsRef<str>>(verbs: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            verbs: verbs
+                .into_iter()
+                .map(|v| v.as_ref().to_lowercase())
+                .collect(),
+        }
+    }
+
+    /// Add more verbs to the set
+    pub fn with_verbs<S: AsRef<str>>(mut self, verbs: impl IntoIterator<Item = S>) -> Self {
+        self.verbs
+            .extend(verbs.into_iter().map(|v| v.as_ref().to_lowercase()));
+        self
+    }
+
+    /// Check if a word is in the set
+    pub fn contains(&self, word: &str) -> bool {
+        self.verbs.contains(&word.to_lowercase())
+    }
+
+    /// Common English verbs to prepare the ingredients, like "chop" or
+    /// "peel"
+    ///
+    /// Used to split a recipe with [`Recipe::phases`].
+    pub fn prep() -> Self {
+        Self::new(DEFAULT_PREP_VERBS)
+    }
+}
+
+impl Default for ActionVerbs {
+    fn default() -> Self {
+        Self::new(DEFAULT_VERBS)
+    }
+}
+
+/// Extract the leading verbs of each sentence or clause of a step
+///
+/// The verbs are returned as written in the step, in order, and can be
+/// repeated.
+///
+/// ```
+/// # use cooklang::actions::{step_actions, ActionVerbs};
+/// let recipe = cooklang::parse("Chop the @onion, then fry it. Serve hot.")
+///     .into_output()
+///     .unwrap();
+/// let step = match &recipe.sections[0].content[0] {
+///     cooklang::Content::Step(step) => step,
+///     _ => unreachable!(),
+/// };
+/// assert_eq!(
+///     step_actions(step, &ActionVerbs::default()),
+///     ["Chop", "fry", "Serve"]
+/// );
+/// ```
+pub fn step_actions(step: &Step, verbs: &ActionVerbs) -> Vec<String> {
+    let mut actions = Vec::new();
+    // at the start of the step, a sentence or a clause
+    let mut leading = true;
+    for item in &step.items {
+        let Item::Text { value } = item else {
+            // components are nouns, whatever comes after is not leading
+            leading = false;
+            continue;
+        };
+        let mut rest = value.as_str();
+        while !rest.is_empty() {
+            let word_start = rest
+                .find(|c: char| c.is_alphabetic() || is_boundary(c))
+                .unwrap_or(rest.len());
+            rest = &rest[word_start..];
+            let Some(c) = rest.chars().next() else { break };
+            if is_boundary(c) {
+                leading = true;
+                rest = &rest[c.len_utf8()..];
+                continue;
+            }
+            let word_end = rest
+                .find(|c: char| !c.is_alphabetic())
+                .unwrap_or(rest.len());
+            let word = &rest[..word_end];
+            rest = &rest[word_end..];
+
+            if leading && CLAUSE_STARTERS.contains(&word.to_lowercase().as_str()) {
+                continue;
+            }
+            if leading && verbs.contains(word) {
+                actions.push(word.to_string());
+            }
+            leading = false;
+        }
+    }
+    actions
+}
+
+/// Steps of a recipe split into the prep and the cook phases
+///
+/// Created from [`Recipe::phases`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Phases<'a> {
+    /// Steps that only prepare the ingredients
+    pub prep: Vec<&'a Step>,
+    /// The rest of the steps
+    pub cook: Vec<&'a Step>,
+}
+
+impl<D, V: QuantityValue> Recipe<D, V> {
+    /// Splits the steps in a prep and a cook phase
+    ///
+    /// This is for the "read everything, prep, then cook" flow. A step is
+    /// prep when it has actions and all of them are in `prep_verbs`, like
+    /// "Peel and chop the @onions". Any other step, including steps without
+    /// actions, is cook. The actions are found like in [`step_actions`], with
+    /// the default verbs and `prep_verbs`.
+    ///
+    /// Both phases keep the order of the recipe. Text paragraphs are not
+    /// steps and are left out.
+    ///
+    /// ```
+    /// # use cooklang::actions::ActionVerbs;
+    /// let recipe = cooklang::parse("Preheat the #oven{}.\n\nPeel and chop the @onions{2}.\n\nRoast them.")
+    ///     .into_output()
+    ///     .unwrap();
+    /// let phases = recipe.phases(&ActionVerbs::prep());
+    /// assert_eq!(phases.prep.len(), 1);
+    /// assert_eq!(phases.prep[0].number, 2);
+    /// assert_eq!(phases.cook.len(), 2);
+    /// ```
+    pub fn phases(&self, prep_verbs: &ActionVerbs) -> Phases<'_> {
+        let verbs = ActionVerbs::default().with_verbs(&prep_verbs.verbs);
+        let mut phases = Phases {
+            prep: Vec::new(),
+            cook: Vec::new(),
+        };
+        let steps = self.sections.iter().flat_map(|s| &s.content);
+        for step in steps.filter_map(|c| match c {
+            Content::Step(step) => Some(step),
+            Content::Text(_) => None,
+        }) {
+            let actions = step_actions(step, &verbs);
+            if !actions.is_empty() && actions.iter().all(|a| prep_verbs.contains(a)) {
+                phases.prep.push(step);
+            } else {
+                phases.cook.push(step);
+            }
+        }
+        phases
+    }
+}
+
+fn is_boundary(c: char) -> bool {
+    matches!(c, '.' | ',' | ';' | '!' | '?' | ':' | '\n')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn actions(input: &str, verbs: &ActionVerbs) -> Vec<Vec<String>> {
+        let recipe = crate::parse(input).into_output().unwrap();
+        recipe.sections[0]
+            .content
+            .iter()
+            .filter_map(|c| match c {
+                Content::Step(step) => Some(step_actions(step, verbs)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn leading_verbs() {
+        let verbs = ActionVerbs::default();
+        assert_eq!(
+            actions("Mix @flour and @water. Let it rest, then bake.", &verbs),
+            [["Mix", "Let", "bake"]]
+        );
+        // verbs in the middle of a sentence are not actions
+        assert_eq!(
+            actions("The @dough should rest and mix well.", &verbs),
+            [Vec::<String>::new()]
+        );
+        assert_eq!(
+            actions("Preheat the #oven{}.\n\nChop the @onion{}.", &verbs),
+            [["Preheat"], ["Chop"]]
+        );
+    }
+
+    #[test]
+    fn custom_verbs() {
+        let verbs = ActionVerbs::new(["mezclar", "hornear"]);
+        assert_eq!(
+            actions("Mezclar la @harina. Hornear, luego servir.", &verbs),
+            [["Mezclar", "Hornear"]]
+        );
+    }
+
+    #[test]
+    fn phases() {
+        let recipe = crate::parse(
+            "Rinse and slice the @potatoes.\n\nFry them, then peel the @garlic.\n\n= Sauce\nTrim the @beans{}.\n\nServe.",
+        )
+        .into_output()
+        .unwrap();
+        let numbers = |steps: &[&Step]| steps.iter().map(|s| s.number).collect::<Vec<_>>();
+
+        let phases = recipe.phases(&ActionVerbs::prep());
+        assert_eq!(numbers(&phases.prep), [1]);
+        assert_eq!(numbers(&phases.cook), [2, 1, 2]);
+
+        let phases = recipe.phases(&ActionVerbs::prep().with_verbs(["trim"]));
+        assert_eq!(numbers(&phases.prep), [1, 1]);
+        assert_eq!(numbers(&phases.cook), [2, 2]);
+    }
+}
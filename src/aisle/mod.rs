@@ -2,7 +2,10 @@
 //!
 //! This module is only available with the `aisle` [feaure](crate::_features).
 //!
-use std::{borrow::Cow, collections::HashMap};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+};
 
 use pest::Parser;
 use serde::{Deserialize, Serialize};
@@ -45,6 +48,12 @@ pub struct Category<'a> {
     pub name: &'a str,
     /// List of ingredients belonging to this category
     pub ingredients: Vec<Ingredient<'a>>,
+    /// Icon to display with the category, like an emoji
+    #[serde(borrow, default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<&'a str>,
+    /// Sort priority of the category
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub order: Option<i32>,
 }
 
 /// An ingredient belonging to a [`Category`]
@@ -55,6 +64,12 @@ pub struct Ingredient<'a> {
     pub names: Vec<&'a str>,
 }
 
+/// Name of the category with the pantry staples
+///
+/// Ingredients in this category are the ones usually at home, like salt or
+/// water. See [`IngredientList::categorize_without_staples`](crate::ingredient_list::IngredientList::categorize_without_staples).
+pub const PANTRY_CATEGORY: &str = "pantry";
+
 impl AisleConf<'_> {
     /// Returns a reversed configuration, where each key is an ingredient
     /// and the value is it's category.
@@ -70,10 +85,42 @@ impl AisleConf<'_> {
         self.len.set(map.len());
         map
     }
+
+    /// Names of the ingredients in the [`PANTRY_CATEGORY`], including
+    /// synonyms
+    ///
+    /// The category name is matched ignoring case.
+    pub fn pantry(&self) -> HashSet<&str> {
+        self.categories
+            .iter()
+            .filter(|cat| cat.name.eq_ignore_ascii_case(PANTRY_CATEGORY))
+            .flat_map(|cat| &cat.ingredients)
+            .flat_map(|igr| igr.names.iter().copied())
+            .collect()
+    }
 }
 
 /// Parse an [`AisleConf`] with the cooklang shopping list format
 pub fn parse(input: &str) -> Result<AisleConf, AisleConfError> {
+    parse_impl(input, None)
+}
+
+/// Same as [`parse`] but ingredients assigned to more than one category are
+/// not fatal
+///
+/// The ingredient stays in the first category it appears in and every other
+/// assignment is reported as a [`AisleConfError::DuplicateIngredient`] in the
+/// returned list.
+pub fn parse_lenient(input: &str) -> Result<(AisleConf<'_>, Vec<AisleConfError>), AisleConfError> {
+    let mut duplicates = Vec::new();
+    let conf = parse_impl(input, Some(&mut duplicates))?;
+    Ok((conf, duplicates))
+}
+
+fn parse_impl<'i>(
+    input: &'i str,
+    mut duplicates: Option<&mut Vec<AisleConfError>>,
+) -> Result<AisleConf<'i>, AisleConfError> {
     let pairs =
         AisleConfParser::parse(Rule::shopping_list, input).map_err(|e| AisleConfError::Parse {
             span: e.location.into(),
@@ -82,22 +129,52 @@ pub fn parse(input: &str) -> Result<AisleConf, AisleConfError> {
 
     let mut categories = Vec::new();
     let mut categories_span = HashMap::new();
-    let mut names_span = HashMap::new();
+    let mut names_span: HashMap<&str, (Span, &str)> = HashMap::new();
 
     for p in pairs.take_while(|p| p.as_rule() != Rule::EOI) {
-        let mut pairs = p.into_inner();
+        let mut pairs = p.into_inner().peekable();
         let name_pair = pairs.next().expect("name");
-        let name = name_pair.as_str().trim();
+        let category_name = name_pair.as_str().trim();
         let current_span = Span::from(name_pair.as_span());
 
-        if let Some(other) = categories_span.insert(name, current_span) {
+        if let Some(other) = categories_span.insert(category_name, current_span) {
             return Err(AisleConfError::DuplicateCategory {
-                name: name.to_string(),
+                name: category_name.to_string(),
                 first_span: other,
                 second_span: current_span,
             });
         }
 
+        let mut icon = None;
+        let mut order = None;
+        if let Some(meta) = pairs.next_if(|p| p.as_rule() == Rule::meta) {
+            for entry in meta.into_inner() {
+                let mut entry = entry.into_inner();
+                let key_pair = entry.next().expect("meta key");
+                let value_pair = entry.next().expect("meta value");
+                let value = value_pair.as_str().trim();
+                match key_pair.as_str() {
+                    "icon" => icon = Some(value),
+                    "order" => {
+                        let n = value.parse::<i32>().map_err(|_| {
+                            AisleConfError::InvalidMetadataValue {
+                                key: "order".to_string(),
+                                value: value.to_string(),
+                                span: value_pair.as_span().into(),
+                            }
+                        })?;
+                        order = Some(n);
+                    }
+                    key => {
+                        return Err(AisleConfError::UnknownMetadataKey {
+                            key: key.to_string(),
+                            span: key_pair.as_span().into(),
+                        })
+                    }
+                }
+            }
+        }
+
         let mut ingredients = Vec::new();
         for p in pairs {
             assert_eq!(p.as_rule(), Rule::ingredient, "expected ingredient");
@@ -106,18 +183,35 @@ pub fn parse(input: &str) -> Result<AisleConf, AisleConfError> {
                 assert_eq!(p.as_rule(), Rule::name, "expected name");
                 let name = p.as_str().trim();
                 let span = Span::from(p.as_span());
-                if let Some(other) = names_span.insert(name, span) {
-                    return Err(AisleConfError::DuplicateIngredient {
+                if let Some(&(other, other_category)) = names_span.get(name) {
+                    let err = AisleConfError::DuplicateIngredient {
                         name: name.to_string(),
+                        first_category: other_category.to_string(),
+                        second_category: category_name.to_string(),
                         first_span: other,
                         second_span: span,
-                    });
+                    };
+                    match duplicates.as_deref_mut() {
+                        Some(duplicates) => {
+                            duplicates.push(err);
+                            continue;
+                        }
+                        None => return Err(err),
+                    }
                 }
+                names_span.insert(name, (span, category_name));
                 names.push(name);
             }
-            ingredients.push(Ingredient { names });
+            if !names.is_empty() {
+                ingredients.push(Ingredient { names });
+            }
         }
-        let category = Category { name, ingredients };
+        let category = Category {
+            name: category_name,
+            ingredients,
+            icon,
+            order,
+        };
 
         categories.push(category);
     }
@@ -132,7 +226,18 @@ pub fn parse(input: &str) -> Result<AisleConf, AisleConfError> {
 pub fn write(conf: &AisleConf, mut write: impl std::io::Write) -> std::io::Result<()> {
     let w = &mut write;
     for category in &conf.categories {
-        writeln!(w, "[{}]", category.name)?;
+        write!(w, "[{}]", category.name)?;
+        let mut meta = Vec::new();
+        if let Some(icon) = category.icon {
+            meta.push(format!("icon: {icon}"));
+        }
+        if let Some(order) = category.order {
+            meta.push(format!("order: {order}"));
+        }
+        if !meta.is_empty() {
+            write!(w, " {{{}}}", meta.join(", "))?;
+        }
+        writeln!(w)?;
         for ingredient in &category.ingredients {
             if !ingredient.names.is_empty() {
                 let mut iter = ingredient.names.iter();
@@ -163,15 +268,35 @@ pub enum AisleConfError {
         /// The second location where the category was found
         second_span: Span,
     },
-    #[error("Duplicate ingredient: '{name}'")]
+    #[error("Duplicate ingredient: '{name}' is in '{first_category}' and '{second_category}'")]
     DuplicateIngredient {
         /// Duplicated ingredient name
         name: String,
+        /// The category where the ingredient was first found
+        first_category: String,
+        /// The category where the ingredient was found again
+        second_category: String,
         /// The first location where the ingredient was found
         first_span: Span,
         /// The second location where the ingredient was found
         second_span: Span,
     },
+    #[error("Unknown category metadata key: '{key}'")]
+    UnknownMetadataKey {
+        /// The unknown key
+        key: String,
+        /// Location of the key
+        span: Span,
+    },
+    #[error("Invalid value for category metadata '{key}': '{value}'")]
+    InvalidMetadataValue {
+        /// The key of the entry
+        key: String,
+        /// The invalid value
+        value: String,
+        /// Location of the value
+        span: Span,
+    },
 }
 
 impl RichError for AisleConfError {
@@ -179,6 +304,8 @@ impl RichError for AisleConfError {
         use crate::error::label;
         match self {
             AisleConfError::Parse { span, .. } => vec![label!(span)],
+            AisleConfError::UnknownMetadataKey { span, .. } => vec![label!(span)],
+            AisleConfError::InvalidMetadataValue { span, .. } => vec![label!(span)],
             AisleConfError::DuplicateCategory {
                 first_span,
                 second_span,
@@ -207,6 +334,12 @@ impl RichError for AisleConfError {
             AisleConfError::DuplicateIngredient { .. } => {
                 vec!["Remove the duplicate ingredient".into()]
             }
+            AisleConfError::UnknownMetadataKey { .. } => {
+                vec!["Supported keys are: icon, order".into()]
+            }
+            AisleConfError::InvalidMetadataValue { key, .. } if key == "order" => {
+                vec!["The order has to be an integer".into()]
+            }
             _ => {
                 vec![]
             }
@@ -254,12 +387,16 @@ butter
             vec![
                 Category {
                     name: "produce",
+                    icon: None,
+                    order: None,
                     ingredients: vec![Ingredient {
                         names: vec!["potatoes"]
                     }]
                 },
                 Category {
                     name: "dairy",
+                    icon: None,
+                    order: None,
                     ingredients: vec![
                         Ingredient {
                             names: vec!["milk"],
@@ -289,6 +426,8 @@ butter
             a.categories,
             vec![Category {
                 name: "empty",
+                icon: None,
+                order: None,
                 ingredients: vec![]
             }]
         )
@@ -308,12 +447,16 @@ milk
             vec![
                 Category {
                     name: "produce",
+                    icon: None,
+                    order: None,
                     ingredients: vec![Ingredient {
                         names: vec!["potatoes"]
                     }]
                 },
                 Category {
                     name: "dairy",
+                    icon: None,
+                    order: None,
                     ingredients: vec![Ingredient {
                         names: vec!["milk"],
                     }],
@@ -332,6 +475,8 @@ tuna|chicken of the sea
             a.categories,
             vec![Category {
                 name: "canned goods",
+                icon: None,
+                order: None,
                 ingredients: vec![Ingredient {
                     names: vec!["tuna", "chicken of the sea"]
                 }]
@@ -348,6 +493,8 @@ tuna|chicken of the sea
             e,
             AisleConfError::DuplicateIngredient {
                 name: "me".into(),
+                first_category: "first".into(),
+                second_category: "seconds".into(),
                 first_span: Span::new(8, 10),
                 second_span: Span::new(21, 23)
             }
@@ -393,12 +540,16 @@ tuna|chicken of the sea
         let expected = vec![
             Category {
                 name: "produce",
+                icon: None,
+                order: None,
                 ingredients: vec![Ingredient {
                     names: vec!["potatoes"],
                 }],
             },
             Category {
                 name: "dairy",
+                icon: None,
+                order: None,
                 ingredients: vec![
                     Ingredient {
                         names: vec!["milk"],
@@ -410,22 +561,30 @@ tuna|chicken of the sea
             },
             Category {
                 name: "deli",
+                icon: None,
+                order: None,
                 ingredients: vec![Ingredient {
                     names: vec!["chicken"],
                 }],
             },
             Category {
                 name: "canned goods",
+                icon: None,
+                order: None,
                 ingredients: vec![Ingredient {
                     names: vec!["tuna", "chicken of the sea"],
                 }],
             },
             Category {
                 name: "empty category",
+                icon: None,
+                order: None,
                 ingredients: vec![],
             },
             Category {
                 name: "another",
+                icon: None,
+                order: None,
                 ingredients: vec![],
             },
         ];
@@ -433,6 +592,19 @@ tuna|chicken of the sea
         assert_eq!(expected, got.categories);
     }
 
+    #[test]
+    fn pantry() {
+        let conf = parse("[Pantry]\nwater\nsalt|kosher salt\n\n[spices]\npepper").unwrap();
+        let pantry = conf.pantry();
+        assert_eq!(pantry.len(), 3);
+        assert!(pantry.contains("water"));
+        assert!(pantry.contains("kosher salt"));
+        assert!(!pantry.contains("pepper"));
+
+        let conf = parse("[spices]\npepper").unwrap();
+        assert!(conf.pantry().is_empty());
+    }
+
     #[test]
     fn conf_write() {
         let got = parse(CONF).unwrap();
@@ -442,4 +614,48 @@ tuna|chicken of the sea
         let got2 = parse(&serialized).unwrap();
         assert_eq!(got, got2);
     }
+
+    #[test]
+    fn category_metadata() {
+        let input = "[produce] {icon: 🥕, order: 2}\npotatoes\n[dairy]{ order: -1 }\nmilk\n";
+        let a = parse(input).unwrap();
+        assert_eq!(a.categories[0].icon, Some("🥕"));
+        assert_eq!(a.categories[0].order, Some(2));
+        assert_eq!(a.categories[1].icon, None);
+        assert_eq!(a.categories[1].order, Some(-1));
+
+        let mut buffer = Vec::new();
+        write(&a, &mut buffer).unwrap();
+        let serialized = String::from_utf8(buffer).unwrap();
+        assert!(serialized.starts_with("[produce] {icon: 🥕, order: 2}\n"));
+        assert_eq!(parse(&serialized).unwrap(), a);
+    }
+
+    #[test]
+    fn category_metadata_errors() {
+        let e = parse("[produce] {colour: red}\n").unwrap_err();
+        assert_eq!(
+            e,
+            AisleConfError::UnknownMetadataKey {
+                key: "colour".into(),
+                span: Span::new(11, 17)
+            }
+        );
+        let e = parse("[produce] {order: first}\n").unwrap_err();
+        assert!(matches!(e, AisleConfError::InvalidMetadataValue { .. }));
+    }
+
+    #[test]
+    fn lenient_duplicate_ingredient() {
+        let input = "[produce]\napple|apples\n[snacks]\napples\nchips|apple\n";
+        let (conf, duplicates) = parse_lenient(input).unwrap();
+        assert_eq!(conf.categories[1].ingredients.len(), 1);
+        assert_eq!(conf.categories[1].ingredients[0].names, vec!["chips"]);
+        assert_eq!(conf.reverse()["apple"], "produce");
+        assert_eq!(duplicates.len(), 2);
+        assert_eq!(
+            duplicates[0].to_string(),
+            "Duplicate ingredient: 'apples' is in 'produce' and 'snacks'"
+        );
+    }
 }
@@ -1,22 +1,23 @@
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
 
 use regex::Regex;
 
-use crate::convert::{Converter, PhysicalQuantity};
+use crate::convert::{Converter, PhysicalQuantity, System};
 use crate::error::{label, CowStr, PassResult, SourceDiag, SourceReport};
 use crate::located::Located;
-use crate::metadata::SpecialKey;
+use crate::metadata::{parse_density, SpecialKey, DENSITY_PREFIX};
 use crate::parser::{
     self, BlockKind, Event, IntermediateData, IntermediateRefMode, IntermediateTargetKind,
     Modifiers,
 };
-use crate::quantity::{Quantity, QuantityValue, ScalableValue, UnitInfo, Value};
+use crate::quantity::{Quantity, QuantityValue, ScalableValue, ScaledQuantity, UnitInfo, Value};
 use crate::span::Span;
-use crate::text::Text;
+use crate::text::{collapse_whitespace, Text};
 use crate::{model::*, Extensions, ParseOptions};
 
-use super::{AnalysisResult, DefineMode, DuplicateMode};
+use super::{AnalysisResult, DefineMode, DuplicateMode, Lints};
 
 macro_rules! error {
     ($msg:expr, $label:expr $(,)?) => {
@@ -46,18 +47,21 @@ pub fn parse_events<'i, 'c>(
     let mut ctx = SourceReport::empty();
     let temperature_regex = extensions
         .contains(Extensions::TEMPERATURE)
-        .then(|| match converter.temperature_regex() {
-            Ok(re) => Some(re),
-            Err(err) => {
-                ctx.warn(
-                    SourceDiag::unlabeled(
-                        "An error ocurred searching temperature values",
-                        crate::error::Severity::Error,
-                        crate::error::Stage::Analysis,
-                    )
-                    .set_source(err),
-                );
-                None
+        .then(|| {
+            let ranges = extensions.contains(Extensions::RANGE_VALUES);
+            match converter.temperature_regex(ranges) {
+                Ok(re) => Some(re),
+                Err(err) => {
+                    ctx.warn(
+                        SourceDiag::unlabeled(
+                            "An error ocurred searching temperature values",
+                            crate::error::Severity::Error,
+                            crate::error::Stage::Analysis,
+                        )
+                        .set_source(err),
+                    );
+                    None
+                }
             }
         })
         .flatten();
@@ -83,6 +87,8 @@ pub fn parse_events<'i, 'c>(
         define_mode: DefineMode::All,
         duplicate_mode: DuplicateMode::New,
         auto_scale_ingredients: false,
+        auto_scale_markers: 0,
+        unmarked_quantities: 0,
         ctx,
 
         locations: Default::default(),
@@ -104,6 +110,10 @@ struct RecipeCollector<'i, 'c> {
     define_mode: DefineMode,
     duplicate_mode: DuplicateMode,
     auto_scale_ingredients: bool,
+    /// Ingredient quantities with the auto scale marker
+    auto_scale_markers: usize,
+    /// Ingredient quantities that could have the auto scale marker but don't
+    unmarked_quantities: usize,
     ctx: SourceReport,
 
     locations: Locations<'i>,
@@ -115,8 +125,13 @@ struct Locations<'i> {
     ingredients: Vec<Located<parser::Ingredient<'i>>>,
     cookware: Vec<Located<parser::Cookware<'i>>>,
     metadata: HashMap<SpecialKey, (Text<'i>, Text<'i>)>,
+    #[cfg(feature = "metadata_schema")]
+    metadata_keys: HashMap<String, Span>,
 }
 
+/// Known config keys with the [`MODES`](Extensions::MODES) extension
+const CONFIG_KEYS: &[&str] = &["define", "mode", "duplicate", "auto scale", "auto_scale"];
+
 const IMPLICIT_REF_WARN: &str = "The reference (&) is implicit";
 
 impl<'i, 'c> RecipeCollector<'i, 'c> {
@@ -126,6 +141,8 @@ impl<'i, 'c> RecipeCollector<'i, 'c> {
             Text(String),
         }
         let mut current_block = None;
+        let mut block_span: Option<Span> = None;
+        let mut step_note: Option<String> = None;
 
         let events = events.by_ref();
         while let Some(event) = events.next() {
@@ -139,6 +156,9 @@ impl<'i, 'c> RecipeCollector<'i, 'c> {
                     self.current_section =
                         Section::new(name.map(|t| t.text_trimmed().into_owned()));
                 }
+                Event::SectionYield(quantity) => {
+                    self.current_section.yields = self.section_yield(quantity);
+                }
                 Event::Start(kind) => {
                     let buffer = if self.define_mode == DefineMode::Text {
                         BlockBuffer::Text(String::new())
@@ -148,22 +168,41 @@ impl<'i, 'c> RecipeCollector<'i, 'c> {
                             BlockKind::Text => BlockBuffer::Text(String::new()),
                         }
                     };
-                    current_block = Some(buffer)
+                    current_block = Some(buffer);
+                    block_span = None;
+                    step_note = None;
                 }
                 Event::End(kind) => {
                     let new_content = match current_block {
-                        Some(BlockBuffer::Step(items)) => {
+                        Some(BlockBuffer::Step(mut items)) => {
                             assert_eq!(kind, BlockKind::Step);
+                            if self.parse_options.collapse_whitespace {
+                                for item in &mut items {
+                                    if let Item::Text { value } = item {
+                                        *value = collapse_whitespace(value);
+                                    }
+                                }
+                            }
+                            if let Some(span) = block_span {
+                                if self.parse_options.lints.contains(Lints::LONG_STEPS) {
+                                    self.long_step_check(&items, span);
+                                }
+                            }
                             Content::Step(Step {
                                 items,
                                 number: self.step_counter,
+                                note: step_note.take(),
                             })
                         }
                         Some(BlockBuffer::Text(text)) => {
                             assert!(
                                 kind == BlockKind::Text || self.define_mode == DefineMode::Text,
                             );
-                            Content::Text(text)
+                            if self.parse_options.collapse_whitespace {
+                                Content::Text(collapse_whitespace(&text))
+                            } else {
+                                Content::Text(text)
+                            }
                         }
                         None => panic!("End event without Start"),
                     };
@@ -183,9 +222,39 @@ impl<'i, 'c> RecipeCollector<'i, 'c> {
                 item @ (Event::Text(_)
                 | Event::Ingredient(_)
                 | Event::Cookware(_)
-                | Event::Timer(_)) => match &mut current_block {
-                    Some(BlockBuffer::Step(items)) => self.in_step(item, items),
-                    Some(BlockBuffer::Text(text)) => self.in_text(item, text),
+                | Event::Timer(_)) => {
+                    let span = match &item {
+                        Event::Text(t) => t.span(),
+                        Event::Ingredient(c) => c.span(),
+                        Event::Cookware(c) => c.span(),
+                        Event::Timer(c) => c.span(),
+                        _ => unreachable!(),
+                    };
+                    block_span = Some(match block_span {
+                        Some(s) => Span::new(s.start(), span.end()),
+                        None => span,
+                    });
+                    match &mut current_block {
+                        Some(BlockBuffer::Step(items)) => self.in_step(item, items),
+                        Some(BlockBuffer::Text(text)) => self.in_text(item, text),
+                        None => panic!("Content outside block"),
+                    }
+                }
+                Event::StepNote(note) => match &mut current_block {
+                    Some(BlockBuffer::Step(items)) => {
+                        // the space before the `>` is not part of the step
+                        if let Some(Item::Text { value }) = items.last_mut() {
+                            value.truncate(value.trim_end().len());
+                            if value.is_empty() {
+                                items.pop();
+                            }
+                        }
+                        step_note = Some(note.text_trimmed().into_owned());
+                    }
+                    Some(BlockBuffer::Text(text)) => {
+                        text.push(' ');
+                        text.push_str(&note.text_trimmed());
+                    }
                     None => panic!("Content outside block"),
                 },
 
@@ -206,7 +275,32 @@ impl<'i, 'c> RecipeCollector<'i, 'c> {
             }
         }
         if !self.current_section.is_empty() {
-            self.content.sections.push(self.current_section);
+            let section = std::mem::take(&mut self.current_section);
+            self.content.sections.push(section);
+        }
+        if self
+            .parse_options
+            .lints
+            .contains(Lints::USED_BEFORE_DEFINED)
+        {
+            self.used_before_defined_check();
+        }
+        if self.parse_options.lints.contains(Lints::AUTO_SCALE_MARKERS) {
+            self.auto_scale_markers_check();
+        }
+        if self
+            .parse_options
+            .lints
+            .contains(Lints::REFERENCE_QUANTITIES)
+        {
+            self.reference_quantities_check();
+        }
+        if self.parse_options.lints.contains(Lints::MIXED_UNIT_SYSTEMS) {
+            self.mixed_unit_systems_check();
+        }
+        #[cfg(feature = "metadata_schema")]
+        if let Some(schema) = self.parse_options.metadata_schema {
+            self.metadata_schema_check(schema);
         }
         PassResult::new(Some(self.content), self.ctx)
     }
@@ -228,6 +322,12 @@ impl<'i, 'c> RecipeCollector<'i, 'c> {
             && key_t.ends_with(']')
         {
             let config_key = &key_t[1..key_t.len() - 1];
+            if CONFIG_KEYS.contains(&config_key) {
+                self.content
+                    .metadata
+                    .config
+                    .insert(key_t.to_string(), value_t.to_string());
+            }
             match config_key {
                 "define" | "mode" => match value_t.as_ref() {
                     "all" | "default" => self.define_mode = DefineMode::All,
@@ -249,19 +349,26 @@ impl<'i, 'c> RecipeCollector<'i, 'c> {
                     _ => self.ctx.error(invalid_value(vec!["true", "false"])),
                 },
                 _ => {
-                    self.ctx.warn(
-                        warning!(
-                            format!("Unknown config metadata key: {key_t}"),
-                            label!(key.span())
-                        )
-                        .hint(
-                            "Possible config keys are '[mode]', '[duplicate]' and '[auto scale]'",
-                        ),
+                    let message = format!("Unknown config metadata key: {key_t}");
+                    let mut diag = if self.parse_options.strict_config_keys {
+                        error!(message, label!(key.span()))
+                    } else {
+                        warning!(message, label!(key.span()))
+                    };
+                    if let Some(similar) = similar_config_key(config_key) {
+                        diag.add_hint(format!("Did you mean '[{similar}]'?"));
+                    }
+                    diag.add_hint(
+                        "Possible config keys are '[mode]', '[duplicate]' and '[auto scale]'",
                     );
-                    self.content
-                        .metadata
-                        .map
-                        .insert(key_t.into_owned(), value_t.into_owned());
+                    let strict = self.parse_options.strict_config_keys;
+                    self.ctx.push(diag);
+                    if !strict {
+                        self.content
+                            .metadata
+                            .map
+                            .insert(key_t.into_owned(), value_t.into_owned());
+                    }
                 }
             }
             return;
@@ -280,16 +387,62 @@ impl<'i, 'c> RecipeCollector<'i, 'c> {
             }
         }
 
+        // run the handler for this key if any
+        let mut value_t = value_t;
+        if let Some(handler) = self.parse_options.metadata_handlers.get_mut(key_t.as_ref()) {
+            let (res, new_value) = handler(&value_t);
+            if let Some(mut diag) =
+                res.into_source_diag(|| format!("Invalid value for metadata key '{key_t}'"))
+            {
+                diag.add_label(label!(value.span()));
+                self.ctx.push(diag);
+            }
+            let Some(new_value) = new_value else {
+                return;
+            };
+            value_t = new_value.into();
+        }
+
+        #[cfg(feature = "metadata_schema")]
+        self.locations
+            .metadata_keys
+            .insert(key_t.to_string(), key.span());
+
         // insert the value into the map
         self.content
             .metadata
             .map
             .insert(key_t.to_string(), value_t.to_string());
 
+        // an empty converter has no units to write a density
+        if self.extensions.contains(Extensions::SPECIAL_METADATA) && self.converter.unit_count() > 0
+        {
+            if let Some(ingredient) = key_t.strip_prefix(DENSITY_PREFIX) {
+                if let Err(err) = parse_density(&value_t, self.converter) {
+                    self.ctx.warn(
+                        warning!(
+                            format!("Invalid density for '{}'", ingredient.trim()),
+                            label!(value.span(), "this value"),
+                        )
+                        .hint("Write it as a mass over a volume, like `0.53 g/ml`")
+                        .set_source(err),
+                    );
+                }
+            }
+        }
+
         // check if it's a special key
         if let Ok(sp_key) = SpecialKey::from_str(&key_t) {
-            // always parse servings
-            if sp_key != SpecialKey::Servings
+            if self
+                .parse_options
+                .lints
+                .contains(Lints::STRUCTURED_METADATA)
+            {
+                self.structured_metadata_check(sp_key, &key, &value);
+            }
+
+            // always parse the keys used for scaling
+            if !matches!(sp_key, SpecialKey::Servings | SpecialKey::DefaultScale)
                 && !self.extensions.contains(Extensions::SPECIAL_METADATA)
             {
                 return;
@@ -301,18 +454,23 @@ impl<'i, 'c> RecipeCollector<'i, 'c> {
                     .metadata
                     .insert_special(sp_key, value_t.to_string(), self.converter);
             if let Err(err) = res {
-                self.ctx.warn(
-                    warning!(
-                        format!(
-                            "Unsupported value for special key: '{}'",
-                            key.text_trimmed()
-                        ),
-                        label!(value.span(), "this value"),
-                    )
-                    .label(label!(key.span(), "this key does not support"))
-                    .hint("It will be a regular metadata entry")
-                    .set_source(err),
+                let message = format!(
+                    "Unsupported value for special key: '{}'",
+                    key.text_trimmed()
                 );
+                // a scale that is not a number can't be used at all, but
+                // without the extension the key is not special for the user
+                let diag = if sp_key == SpecialKey::DefaultScale
+                    && self.extensions.contains(Extensions::SPECIAL_METADATA)
+                {
+                    error!(message, label!(value.span(), "this value"))
+                        .hint("Use a positive number, like '2'")
+                } else {
+                    warning!(message, label!(value.span(), "this value"))
+                        .label(label!(key.span(), "this key does not support"))
+                        .hint("It will be a regular metadata entry")
+                };
+                self.ctx.push(diag.set_source(err));
                 return;
             }
             // store it's location if it was inserted
@@ -329,6 +487,28 @@ impl<'i, 'c> RecipeCollector<'i, 'c> {
         }
     }
 
+    fn structured_metadata_check(&mut self, sp_key: SpecialKey, key: &Text, value: &Text) {
+        let value_t = value.text_outer_trimmed();
+        let Some(suggestion) =
+            crate::metadata::structured_suggestion(sp_key, &value_t, self.converter)
+        else {
+            return;
+        };
+        self.ctx.warn(
+            warning!(
+                format!(
+                    "Metadata value should be structured: '{}'",
+                    key.text_trimmed()
+                ),
+                label!(value.span(), "this value"),
+            )
+            .hint(format!(
+                "Write it as '{}: {suggestion}'",
+                key.text_trimmed()
+            )),
+        );
+    }
+
     fn time_override_check(&mut self, new: SpecialKey) {
         let locs = |keys: &[SpecialKey]| {
             assert!(!keys.is_empty());
@@ -378,7 +558,7 @@ impl<'i, 'c> RecipeCollector<'i, 'c> {
     fn in_step(&mut self, item: Event<'i>, items: &mut Vec<Item>) {
         match item {
             Event::Text(text) => {
-                let t = text.text();
+                let t = self.block_text(&text);
                 if self.define_mode == DefineMode::Components {
                     // only issue warnings for alphanumeric characters
                     // so that the user can format the text with spaces,
@@ -392,34 +572,20 @@ impl<'i, 'c> RecipeCollector<'i, 'c> {
                     return; // ignore text
                 }
 
-                // it's only some if the extension is enabled
-                if let Some(re) = &self.temperature_regex {
-                    debug_assert!(self.extensions.contains(Extensions::TEMPERATURE));
-
+                if self.parse_options.detect_links {
                     let mut haystack = t.as_ref();
-                    while let Some((before, temperature, after)) = find_temperature(haystack, re) {
+                    while let Some((before, link, after)) = find_link(haystack) {
                         if !before.is_empty() {
-                            items.push(Item::Text {
-                                value: before.to_string(),
-                            });
+                            self.step_text(before, items);
                         }
-
-                        items.push(Item::InlineQuantity {
-                            index: self.content.inline_quantities.len(),
-                        });
-                        self.content.inline_quantities.push(temperature);
-
+                        items.push(link);
                         haystack = after;
                     }
                     if !haystack.is_empty() {
-                        items.push(Item::Text {
-                            value: haystack.to_string(),
-                        });
+                        self.step_text(haystack, items);
                     }
                 } else {
-                    items.push(Item::Text {
-                        value: t.into_owned(),
-                    });
+                    self.step_text(&t, items);
                 }
             }
 
@@ -437,9 +603,45 @@ impl<'i, 'c> RecipeCollector<'i, 'c> {
         };
     }
 
+    /// Text of a step or a text paragraph, with the line breaks if they are
+    /// kept
+    fn block_text<'t>(&self, text: &Text<'t>) -> Cow<'t, str> {
+        if self.parse_options.collapse_whitespace {
+            text.text_with_breaks()
+        } else {
+            text.text()
+        }
+    }
+
+    fn step_text(&mut self, text: &str, items: &mut Vec<Item>) {
+        // it's only some if the extension is enabled
+        if let Some(re) = &self.temperature_regex {
+            debug_assert!(self.extensions.contains(Extensions::TEMPERATURE));
+
+            let mut haystack = text;
+            while let Some((before, temperature, after)) = find_temperature(haystack, re) {
+                if !before.is_empty() {
+                    push_text(items, before);
+                }
+
+                items.push(Item::InlineQuantity {
+                    index: self.content.inline_quantities.len(),
+                });
+                self.content.inline_quantities.push(temperature);
+
+                haystack = after;
+            }
+            if !haystack.is_empty() {
+                push_text(items, haystack);
+            }
+        } else {
+            push_text(items, text);
+        }
+    }
+
     fn in_text(&mut self, ev: Event<'i>, s: &mut String) {
         match ev {
-            Event::Text(t) => s.push_str(t.text().as_ref()),
+            Event::Text(t) => s.push_str(&self.block_text(&t)),
             Event::Ingredient(_) | Event::Cookware(_) | Event::Timer(_) => {
                 assert_eq!(
                     self.define_mode,
@@ -471,8 +673,9 @@ impl<'i, 'c> RecipeCollector<'i, 'c> {
         let mut new_igr = Ingredient {
             name: name.into_owned(),
             alias: ingredient.alias.map(|t| t.text_trimmed().into_owned()),
-            quantity: ingredient.quantity.clone().map(|q| self.quantity(q, true)),
+            quantity: ingredient.quantity.map(|q| self.quantity(q, true)),
             note: ingredient.note.map(|n| n.text_trimmed().into_owned()),
+            prep_order: ingredient.prep_order.map(Located::into_inner),
             modifiers: ingredient.modifiers.into_inner(),
             relation: IngredientRelation::definition(
                 Vec::new(),
@@ -480,6 +683,12 @@ impl<'i, 'c> RecipeCollector<'i, 'c> {
             ),
         };
 
+        if self.extensions.contains(Extensions::RELATIVE_QUANTITIES) {
+            if let Some(quantity) = &located_ingredient.quantity {
+                self.resolve_relative_quantity(&mut new_igr, quantity.span());
+            }
+        }
+
         if let Some(inter_data) = ingredient.intermediate_data {
             assert!(new_igr.modifiers().contains(Modifiers::REF));
             let invalid_modifiers = Modifiers::RECIPE | Modifiers::HIDDEN | Modifiers::NEW;
@@ -595,7 +804,7 @@ impl<'i, 'c> RecipeCollector<'i, 'c> {
                     .expect("definition")
             {
                 self.ctx.error(conflicting_reference_quantity_error(
-                    ingredient.quantity.unwrap().span(),
+                    located_ingredient.quantity.as_ref().unwrap().span(),
                     definition_location.span(),
                     implicit,
                 ));
@@ -629,6 +838,16 @@ impl<'i, 'c> RecipeCollector<'i, 'c> {
             Ingredient::set_referenced_from(&mut self.content.ingredients, references_to);
         }
 
+        if self
+            .parse_options
+            .lints
+            .contains(Lints::DUPLICATE_DEFINITIONS)
+            && new_igr.relation.is_definition()
+            && !new_igr.modifiers.contains(Modifiers::NEW)
+        {
+            self.duplicate_definition_check(&new_igr.name, location);
+        }
+
         if new_igr.modifiers.contains(Modifiers::RECIPE)
             && !new_igr.modifiers.contains(Modifiers::REF)
         {
@@ -648,6 +867,289 @@ impl<'i, 'c> RecipeCollector<'i, 'c> {
         self.content.ingredients.len() - 1
     }
 
+    fn duplicate_definition_check(&mut self, name: &str, location: Span) {
+        let name = unicase::UniCase::new(name);
+        let Some(first) = self.content.ingredients.iter().position(|igr| {
+            igr.relation.is_definition() && unicase::UniCase::new(igr.name.as_str()) == name
+        }) else {
+            return;
+        };
+
+        let mut w = warning!(
+            format!("Ingredient defined more than once: {name}"),
+            label!(location, "defined again here")
+        )
+        .label(label!(
+            self.locations.ingredients[first].span(),
+            "first defined here"
+        ));
+        if self.extensions.contains(Extensions::COMPONENT_MODIFIERS) {
+            w.add_hint("Mark the ingredient as a reference with '&' to add to the first one");
+        }
+        if self.extensions.contains(Extensions::MODES) {
+            w.add_hint("Use '>> [duplicate]: reference' to make repeated ingredients references");
+        }
+        self.ctx.warn(w);
+    }
+
+    #[cfg(feature = "metadata_schema")]
+    fn metadata_schema_check(&mut self, schema: &super::MetadataSchema) {
+        for violation in schema.violations(&self.content.metadata.map) {
+            let span = violation
+                .key
+                .as_ref()
+                .and_then(|key| self.locations.metadata_keys.get(key));
+            let message = match &violation.key {
+                Some(key) => format!("Metadata does not match the schema: '{key}'"),
+                None => "Metadata does not match the schema".to_string(),
+            };
+            let mut diag = match span {
+                Some(span) => error!(message, label!(span)),
+                None => SourceDiag::unlabeled(
+                    message,
+                    crate::error::Severity::Error,
+                    crate::error::Stage::Analysis,
+                ),
+            };
+            diag.add_hint(violation.message);
+            self.ctx.push(diag);
+        }
+    }
+
+    fn long_step_check(&mut self, items: &[Item], span: Span) {
+        let max = self
+            .parse_options
+            .max_step_length
+            .unwrap_or(super::DEFAULT_MAX_STEP_LENGTH);
+        let len = items
+            .iter()
+            .map(|item| match item {
+                Item::Text { value } => value.chars().count(),
+                Item::Ingredient { index } => self.content.ingredients[*index]
+                    .display_name()
+                    .chars()
+                    .count(),
+                Item::Cookware { index } => {
+                    self.content.cookware[*index].display_name().chars().count()
+                }
+                Item::Timer { index } => {
+                    let timer = &self.content.timers[*index];
+                    match (&timer.quantity, &timer.name) {
+                        (Some(quantity), _) => quantity.to_string().chars().count(),
+                        (None, Some(name)) => name.chars().count(),
+                        (None, None) => 0,
+                    }
+                }
+                Item::InlineQuantity { index } => self.content.inline_quantities[*index]
+                    .to_string()
+                    .chars()
+                    .count(),
+                Item::Link { text, .. } => text.chars().count(),
+            })
+            .sum::<usize>();
+        if len > max {
+            self.ctx.warn(
+                warning!(
+                    format!("Step is too long: {len} characters"),
+                    label!(span, "this step")
+                )
+                .hint(format!(
+                    "Consider splitting it into smaller steps of at most {max} characters"
+                )),
+            );
+        }
+    }
+
+    fn auto_scale_markers_check(&mut self) {
+        if !self.extensions.contains(Extensions::MODES)
+            || self.auto_scale_ingredients
+            || self.unmarked_quantities > 0
+            || self.auto_scale_markers < 2
+        {
+            return;
+        }
+        let mut w = SourceDiag::unlabeled(
+            "Every ingredient quantity has the auto scale marker",
+            crate::error::Severity::Warning,
+            crate::error::Stage::Analysis,
+        );
+        w.add_hint("Use '>> [auto scale]: true' to scale every ingredient and remove the markers");
+        self.ctx.warn(w);
+    }
+
+    fn used_before_defined_check(&mut self) {
+        let is_definition = |igr: &Ingredient<ScalableValue>| {
+            igr.relation.is_definition() && !igr.modifiers().contains(Modifiers::RECIPE)
+        };
+
+        let mut reported = HashSet::new();
+        for (index, igr) in self.content.ingredients.iter().enumerate() {
+            if !is_definition(igr) || igr.quantity.is_some() {
+                continue;
+            }
+            let name = unicase::UniCase::new(igr.name.as_str());
+            if reported.contains(&name) {
+                continue;
+            }
+            let Some(later) = self.content.ingredients[index + 1..]
+                .iter()
+                .position(|other| {
+                    is_definition(other)
+                        && other.quantity.is_some()
+                        && unicase::UniCase::new(other.name.as_str()) == name
+                })
+                .map(|pos| index + 1 + pos)
+            else {
+                continue;
+            };
+            reported.insert(name);
+
+            let mut w = warning!(
+                format!(
+                    "Ingredient used before its quantity is defined: {}",
+                    igr.name
+                ),
+                label!(
+                    self.locations.ingredients[index].span(),
+                    "used here without a quantity"
+                )
+            )
+            .label(label!(
+                self.locations.ingredients[later].span(),
+                "defined later with a quantity"
+            ))
+            .hint("Move the quantity to the first time the ingredient is used");
+            if self.extensions.contains(Extensions::COMPONENT_MODIFIERS) {
+                w.add_hint("Then mark the later one as a reference with '&'");
+            }
+            if self
+                .extensions
+                .contains(Extensions::INTERMEDIATE_PREPARATIONS)
+            {
+                w.add_hint(
+                    "If it's prepared in another step, move that step before and use an intermediate preparation reference",
+                );
+            }
+            self.ctx.warn(w);
+        }
+    }
+
+    fn reference_quantities_check(&mut self) {
+        for (index, igr) in self.content.ingredients.iter().enumerate() {
+            if !igr.relation.is_definition() || igr.relation.referenced_from().is_empty() {
+                continue;
+            }
+            let Some((kind, defined)) = igr
+                .quantity
+                .as_ref()
+                .and_then(|q| self.comparable_amount(q))
+            else {
+                continue;
+            };
+
+            let mut used = 0.0;
+            let mut references = Vec::new();
+            let comparable = igr.relation.referenced_from().iter().all(|&r| {
+                let Some(quantity) = &self.content.ingredients[r].quantity else {
+                    return true;
+                };
+                match self.comparable_amount(quantity) {
+                    Some((k, amount)) if k == kind => {
+                        used += amount;
+                        references.push(r);
+                        true
+                    }
+                    _ => false,
+                }
+            });
+            if !comparable || used <= defined * (1.0 + f64::EPSILON) {
+                continue;
+            }
+
+            let mut w = warning!(
+                format!("References use more than the definition: {}", igr.name),
+                label!(
+                    self.locations.ingredients[index].span(),
+                    "defined here with less"
+                )
+            )
+            .hint("The quantity of the definition should be the total used by the references");
+            for r in references {
+                w.add_label(label!(self.locations.ingredients[r].span(), "used here"));
+            }
+            self.ctx.warn(w);
+        }
+    }
+
+    fn mixed_unit_systems_check(&mut self) {
+        let mut metric = Vec::new();
+        let mut imperial = Vec::new();
+        let mut labels = Vec::new();
+        for (index, igr) in self.content.ingredients.iter().enumerate() {
+            let Some(UnitInfo::Known(unit)) = igr
+                .quantity
+                .as_ref()
+                .and_then(|q| q.unit())
+                .map(|u| u.unit_info_or_parse(self.converter))
+            else {
+                continue;
+            };
+            let (units, name) = match unit.system {
+                Some(System::Metric) => (&mut metric, "metric"),
+                Some(System::Imperial) => (&mut imperial, "imperial"),
+                None => continue,
+            };
+            let symbol = unit.symbol().to_string();
+            if !units.contains(&symbol) {
+                units.push(symbol);
+                labels.push(label!(self.locations.ingredients[index].span(), name));
+            }
+        }
+        if metric.is_empty() || imperial.is_empty() {
+            return;
+        }
+
+        let mut w = SourceDiag::unlabeled(
+            format!(
+                "Ingredient units mix metric ({}) and imperial ({})",
+                metric.join(", "),
+                imperial.join(", ")
+            ),
+            crate::error::Severity::Warning,
+            crate::error::Stage::Analysis,
+        );
+        for label in labels {
+            w.add_label(label);
+        }
+        w.add_hint("Use the units of one system, the recipe can be converted to the other");
+        self.ctx.warn(w);
+    }
+
+    /// Number of a quantity to compare it with others, in the base unit of its
+    /// physical quantity or [`None`] if it has no unit
+    fn comparable_amount(
+        &self,
+        quantity: &Quantity<ScalableValue>,
+    ) -> Option<(Option<PhysicalQuantity>, f64)> {
+        let value = match &quantity.value {
+            ScalableValue::Fixed(Value::Number(n)) | ScalableValue::Linear(Value::Number(n)) => {
+                n.value()
+            }
+            _ => return None,
+        };
+        match quantity
+            .unit()
+            .map(|u| u.unit_info_or_parse(self.converter))
+        {
+            None => Some((None, value)),
+            Some(UnitInfo::Known(unit)) => Some((
+                Some(unit.physical_quantity),
+                (value + unit.difference) * unit.ratio,
+            )),
+            Some(UnitInfo::Unknown) => None,
+        }
+    }
+
     fn resolve_intermediate_ref(
         &mut self,
         inter_data: Located<IntermediateData>,
@@ -675,6 +1177,7 @@ impl<'i, 'c> RecipeCollector<'i, 'c> {
                     )
                     .hint("Relative reference value has to be greater than 0"));
                 }
+                Mode::Name => {}
             }
         }
 
@@ -696,6 +1199,11 @@ impl<'i, 'c> RecipeCollector<'i, 'c> {
                     .filter_map(|(i, c)| c.is_step().then_some(i))
                     .nth((val - 1) as usize);
 
+                if index.is_none() && val == self.step_counter {
+                    return bounds(format!(
+                        "Step {val} is the current step, only previous steps can be referenced"
+                    ));
+                }
                 if index.is_none() {
                     return bounds(format!(
                         "The value has to be a previous step number: {}",
@@ -733,6 +1241,13 @@ impl<'i, 'c> RecipeCollector<'i, 'c> {
             (Kind::Section, Mode::Number) => {
                 let index = (val - 1) as usize; // direct index, but make it 0 indexed
 
+                // the current section is not in content.sections until the
+                // next one starts, so its index is the length
+                if index == self.content.sections.len() {
+                    return bounds(format!(
+                        "Section {val} is the current section, only previous sections can be referenced"
+                    ));
+                }
                 if index >= self.content.sections.len() {
                     return bounds(format!(
                         "The value has to be a previous section number: {}",
@@ -746,6 +1261,18 @@ impl<'i, 'c> RecipeCollector<'i, 'c> {
 
                 IngredientRelation::reference(index, IngredientReferenceTarget::Section)
             }
+            (Kind::Section, Mode::Name) => {
+                let span = inter_data.span();
+                let name_span = inter_data.name.expect("no name in name reference");
+                let name = &self.input[name_span.range()];
+                self.resolve_section_name(name, span)?
+            }
+            (Kind::Step, Mode::Name) => {
+                return Err(error!(
+                    format!("{INVALID}: only sections can be referenced by name"),
+                    label!(inter_data.span())
+                ));
+            }
             (Kind::Section, Mode::Relative) => {
                 let val = val as usize; // number of sections to go back
 
@@ -770,6 +1297,129 @@ impl<'i, 'c> RecipeCollector<'i, 'c> {
         Ok(relation)
     }
 
+    /// Replaces a quantity like `=flour` with the quantity of the last
+    /// definition of that ingredient
+    fn resolve_relative_quantity(&mut self, igr: &mut Ingredient<ScalableValue>, span: Span) {
+        let Some(
+            ScalableValue::Fixed(Value::Text(text)) | ScalableValue::Linear(Value::Text(text)),
+        ) = igr.quantity.as_ref().map(|q| &q.value)
+        else {
+            return;
+        };
+        let Some(target) = text.strip_prefix('=').map(str::trim) else {
+            return;
+        };
+        if igr.quantity.as_ref().is_some_and(|q| q.unit().is_some()) {
+            self.ctx.error(
+                error!("Relative quantity with a unit", label!(span))
+                    .hint(format!("The unit is the one of '{target}', remove it")),
+            );
+            return;
+        }
+
+        let name = unicase::UniCase::new(target);
+        let Some(index) = self.content.ingredients.iter().rposition(|i| {
+            i.relation.is_definition() && unicase::UniCase::new(i.name.as_str()) == name
+        }) else {
+            self.ctx.error(
+                error!(
+                    format!("Relative quantity target not found: {target}"),
+                    label!(span)
+                )
+                .hint("The ingredient has to be defined before"),
+            );
+            return;
+        };
+
+        let numeric = self.content.ingredients[index]
+            .quantity
+            .as_ref()
+            .filter(|q| !q.value.is_text());
+        match numeric {
+            Some(quantity) => igr.quantity = Some(quantity.clone()),
+            None => {
+                let definition = self.locations.ingredients[index].span();
+                self.ctx.error(
+                    error!(
+                        format!("Relative quantity target has no numeric quantity: {target}"),
+                        label!(span)
+                    )
+                    .label(label!(definition, "defined here")),
+                );
+            }
+        }
+    }
+
+    fn resolve_section_name(
+        &self,
+        name: &str,
+        span: Span,
+    ) -> Result<IngredientRelation, SourceDiag> {
+        const INVALID: &str = "Invalid intermediate preparation reference";
+
+        let target = unicase::UniCase::new(name);
+        let matches = self
+            .content
+            .sections
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| s.name.as_deref().map(unicase::UniCase::new) == Some(target))
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+
+        match matches.as_slice() {
+            [index] => Ok(IngredientRelation::reference(
+                *index,
+                IngredientReferenceTarget::Section,
+            )),
+            [] => {
+                let mut err = error!(
+                    format!("{INVALID}: section not found: {name}"),
+                    label!(span)
+                );
+                if self
+                    .current_section
+                    .name
+                    .as_deref()
+                    .map(unicase::UniCase::new)
+                    == Some(target)
+                {
+                    err = err.hint(
+                        "This is the current section, only previous sections can be referenced",
+                    );
+                } else {
+                    let names = self
+                        .content
+                        .sections
+                        .iter()
+                        .filter_map(|s| s.name.as_deref())
+                        .map(|n| format!("`{n}`"))
+                        .collect::<Vec<_>>();
+                    if names.is_empty() {
+                        err = err.hint("There are no named sections before this one");
+                    } else {
+                        err = err.hint(format!("Previous sections are: {}", names.join(", ")));
+                    }
+                }
+                Err(err)
+            }
+            many => {
+                let numbers = many
+                    .iter()
+                    .map(|index| (index + 1).to_string())
+                    .collect::<Vec<_>>();
+                Err(error!(
+                    format!("{INVALID}: ambiguous section name: {name}"),
+                    label!(span)
+                )
+                .hint(format!(
+                    "There are many sections with this name, use the number instead: {}",
+                    numbers.join(", ")
+                )))
+            }
+        }
+    }
+
     fn cookware(&mut self, cookware: Located<parser::Cookware<'i>>) -> usize {
         let located_cookware = cookware.clone();
         let (cookware, location) = cookware.take_pair();
@@ -850,12 +1500,15 @@ impl<'i, 'c> RecipeCollector<'i, 'c> {
     }
 
     fn timer(&mut self, timer: Located<parser::Timer<'i>>) -> usize {
-        let located_timer = timer.clone();
         let (timer, _span) = timer.take_pair();
         let quantity = timer.quantity.map(|q| {
+            // the location is only needed for the checks
+            let located_quantity = self
+                .extensions
+                .contains(Extensions::ADVANCED_UNITS)
+                .then(|| q.clone());
             let quantity = self.quantity(q, false);
-            if self.extensions.contains(Extensions::ADVANCED_UNITS) {
-                let located_quantity = located_timer.quantity.as_ref().unwrap();
+            if let Some(located_quantity) = located_quantity {
                 if quantity.value.is_text() {
                     self.ctx.error(error!(
                         format!("Timer value is text: {}", quantity.value),
@@ -901,14 +1554,60 @@ impl<'i, 'c> RecipeCollector<'i, 'c> {
         quantity: Located<parser::Quantity<'i>>,
         is_ingredient: bool,
     ) -> Quantity<ScalableValue> {
-        let parser::Quantity { value, unit, .. } = quantity.into_inner();
-        Quantity::new(
-            self.value(value, is_ingredient),
-            unit.map(|t| t.text_trimmed().into_owned()),
-        )
+        let parser::Quantity {
+            value,
+            unit,
+            approximate,
+            unit_locked,
+        } = quantity.into_inner();
+        Quantity {
+            approximate: approximate.is_some(),
+            unit_locked: unit_locked.is_some(),
+            ..Quantity::new(
+                self.value(value, is_ingredient),
+                unit.map(|t| t.text_trimmed().into_owned()),
+            )
+        }
+    }
+
+    fn section_yield(&mut self, quantity: Located<parser::Quantity<'i>>) -> Option<ScaledQuantity> {
+        let span = quantity.span();
+        let quantity = self.quantity(quantity, false);
+        let value = match quantity.value {
+            ScalableValue::Fixed(value) | ScalableValue::Linear(value) => value,
+            ScalableValue::ByServings(_) => {
+                self.ctx.error(
+                    error!(
+                        "A section yield can't have a value for each serving",
+                        label!(span)
+                    )
+                    .hint("Use a single value, it scales with the recipe"),
+                );
+                return None;
+            }
+        };
+        Some(ScaledQuantity {
+            value,
+            unit: quantity.unit,
+            approximate: quantity.approximate,
+            unit_locked: quantity.unit_locked,
+        })
     }
 
-    fn value(&mut self, value: parser::QuantityValue, is_ingredient: bool) -> ScalableValue {
+    fn value(&mut self, mut value: parser::QuantityValue, is_ingredient: bool) -> ScalableValue {
+        if self.parse_options.number_words {
+            let values = match &mut value {
+                parser::QuantityValue::Single { value, .. } => std::slice::from_mut(value),
+                parser::QuantityValue::Many(v) => v.as_mut_slice(),
+            };
+            for v in values {
+                if let Value::Text(text) = &**v {
+                    if let Some(number) = Value::from_number_word(text) {
+                        **v = number;
+                    }
+                }
+            }
+        }
         let mut marker_span = None;
         match &value {
             parser::QuantityValue::Single {
@@ -960,6 +1659,15 @@ impl<'i, 'c> RecipeCollector<'i, 'c> {
             }
             _ => {}
         }
+        if is_ingredient {
+            if marker_span.is_some() {
+                self.auto_scale_markers += 1;
+            } else if matches!(&value, parser::QuantityValue::Single { value, .. } if !value.is_text())
+                && !self.auto_scale_ingredients
+            {
+                self.unmarked_quantities += 1;
+            }
+        }
         let mut v = ScalableValue::from_ast(value);
 
         if is_ingredient && self.auto_scale_ingredients {
@@ -987,6 +1695,15 @@ impl<'i, 'c> RecipeCollector<'i, 'c> {
         location: Span,
         modifiers_location: Span,
     ) -> Option<(usize, bool)> {
+        // without modifiers or modes there are no references, every component
+        // is a definition
+        if !self
+            .extensions
+            .intersects(Extensions::COMPONENT_MODIFIERS | Extensions::MODES)
+        {
+            return None;
+        }
+
         let new_name = unicase::UniCase::new(new.name());
 
         let all = C::all(&self.content);
@@ -1121,20 +1838,24 @@ impl<'i, 'c> RecipeCollector<'i, 'c> {
             // extra reference checks
             Some((references_to, implicit))
         } else {
-            self.ctx.error({
-                let mut e = error!(
-                    format!("Reference not found: {}", new.name()),
-                    label!(location)
-                )
-                .hint(format!(
-                    "A non reference {} with the same name defined BEFORE cannot be found",
-                    C::container()
-                ));
-                if implicit {
-                    e.add_hint(IMPLICIT_REF_WARN);
-                }
-                e
-            });
+            let message = format!("Reference not found: {}", new.name());
+            let lenient = self.parse_options.lenient_references;
+            let mut diag = if lenient {
+                warning!(message, label!(location))
+            } else {
+                error!(message, label!(location))
+            };
+            diag.add_hint(format!(
+                "A non reference {} with the same name defined BEFORE cannot be found",
+                C::container()
+            ));
+            if implicit {
+                diag.add_hint(IMPLICIT_REF_WARN);
+            }
+            self.ctx.push(diag);
+            if lenient {
+                new.modifiers_mut().remove(Modifiers::REF);
+            }
             None
         }
     }
@@ -1250,12 +1971,81 @@ impl RefComponent for Cookware<ScalableValue> {
     }
 }
 
+/// Adds text to the step, merging it with the previous item if it's also text
+fn push_text(items: &mut Vec<Item>, text: &str) {
+    if let Some(Item::Text { value }) = items.last_mut() {
+        value.push_str(text);
+    } else {
+        items.push(Item::Text {
+            value: text.to_string(),
+        });
+    }
+}
+
+/// Finds the first URL in the text, like `https://cooklang.org` or
+/// `www.cooklang.org`
+///
+/// Punctuation at the end, like the dot in `see https://cooklang.org.`, is
+/// not part of the URL, and neither is a closing parenthesis without an
+/// opening one in the URL.
+fn find_link(text: &str) -> Option<(&str, Item, &str)> {
+    let start = ["https://", "http://", "www."]
+        .iter()
+        .filter_map(|prefix| {
+            text.match_indices(prefix)
+                .find(|(i, _)| {
+                    text[..*i]
+                        .chars()
+                        .next_back()
+                        .is_none_or(|c| c.is_whitespace() || "([<\"'".contains(c))
+                })
+                .map(|(i, _)| i)
+        })
+        .min()?;
+    let rest = &text[start..];
+    let mut end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+    loop {
+        let url = &rest[..end];
+        let Some(last) = url.chars().next_back() else {
+            break;
+        };
+        let unbalanced =
+            |open, close| last == close && url.matches(open).count() < url.matches(close).count();
+        if ".,;:!?'\"".contains(last) || unbalanced('(', ')') || unbalanced('[', ']') {
+            end -= last.len_utf8();
+        } else {
+            break;
+        }
+    }
+    let url = &rest[..end];
+    // only the prefix
+    if url.ends_with("//") || url == "www." {
+        return None;
+    }
+    let link = Item::Link {
+        url: if url.starts_with("www.") {
+            format!("https://{url}")
+        } else {
+            url.to_string()
+        },
+        text: url.to_string(),
+    };
+    Some((&text[..start], link, &rest[end..]))
+}
+
 fn find_temperature<'a>(text: &'a str, re: &Regex) -> Option<(&'a str, Quantity<Value>, &'a str)> {
     let caps = re.captures(text)?;
-    let value = caps[1].replace(',', ".").parse::<f64>().ok()?;
-    let unit = caps.get(3).unwrap().range();
-    let unit_text = text[unit].to_string();
-    let temperature = Quantity::new(Value::Number(value.into()), Some(unit_text));
+    let number = |n: &str| n.replace(',', ".").parse::<f64>().ok();
+    let start = number(&caps["start"])?;
+    let value = match caps.name("end") {
+        Some(end) => Value::Range {
+            start: start.into(),
+            end: number(end.as_str())?.into(),
+        },
+        None => Value::Number(start.into()),
+    };
+    let unit_text = caps["unit"].to_string();
+    let temperature = Quantity::new(value, Some(unit_text));
 
     let range = caps.get(0).unwrap().range();
     let (before, after) = (&text[..range.start], &text[range.end..]);
@@ -1295,6 +2085,32 @@ fn conflicting_reference_quantity_error(
     e
 }
 
+/// The known config key closest to an unknown one, if it's probably a typo
+fn similar_config_key(key: &str) -> Option<&'static str> {
+    CONFIG_KEYS
+        .iter()
+        .map(|&known| (known, edit_distance(key, known)))
+        .filter(|&(_, distance)| distance <= 2)
+        .min_by_key(|&(_, distance)| distance)
+        .map(|(known, _)| known)
+}
+
+/// Number of single char insertions, deletions or substitutions to go from
+/// one string to the other
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut prev = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut row = vec![i + 1];
+        for (j, &cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != cb);
+            row.push((prev[j] + cost).min(prev[j + 1] + 1).min(row[j] + 1));
+        }
+        prev = row;
+    }
+    prev[b.len()]
+}
+
 fn text_val_in_ref_warn(
     text_quantity_span: Span,
     number_quantity_span: Span,
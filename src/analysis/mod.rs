@@ -3,12 +3,20 @@
 //! This is just if for some reason you want to split the parsing from the
 //! analysis.
 
+use std::collections::HashMap;
+
+use bitflags::bitflags;
+
 use crate::error::{CowStr, PassResult, SourceDiag};
 use crate::ScalableRecipe;
 
 mod event_consumer;
+#[cfg(feature = "metadata_schema")]
+mod schema;
 
 pub use event_consumer::parse_events;
+#[cfg(feature = "metadata_schema")]
+pub use schema::{MetadataSchema, MetadataSchemaError};
 
 pub type AnalysisResult = PassResult<ScalableRecipe>;
 
@@ -39,6 +47,107 @@ pub struct ParseOptions<'a> {
     /// The boolean returned indicates if the value should be included in the
     /// recipe.
     pub metadata_validator: Option<MetadataValidator<'a>>,
+    /// Handlers for specific metadata keys
+    ///
+    /// The handler of a key receives the value and can validate or transform
+    /// it. It runs after the [`metadata_validator`](Self::metadata_validator)
+    /// and before the special keys are parsed, so a transformed value is used
+    /// for everything else. Keys without a handler work as usual.
+    pub metadata_handlers: HashMap<String, MetadataHandler<'a>>,
+    /// JSON Schema the metadata has to match
+    ///
+    /// Every violation is an error. The check runs after all the metadata is
+    /// collected.
+    #[cfg(feature = "metadata_schema")]
+    pub metadata_schema: Option<&'a MetadataSchema>,
+    /// Opt-in extra warnings
+    pub lints: Lints,
+    /// Max number of characters of a step for [`Lints::LONG_STEPS`]
+    ///
+    /// Components count with their display name. If [`None`],
+    /// [`DEFAULT_MAX_STEP_LENGTH`] is used.
+    pub max_step_length: Option<usize>,
+    /// Make unknown config keys an error
+    ///
+    /// With the [`MODES`](crate::Extensions::MODES) extension, an unknown
+    /// `[bracketed]` key like `[mdoe]` is a warning and it is stored as
+    /// regular metadata. With this, it is an error and it is not stored.
+    pub strict_config_keys: bool,
+    /// Make a reference to a component that is not found a warning
+    ///
+    /// By default, a reference like `@&flour{}` without a `flour` defined
+    /// before it is an error. With this, it is a warning and the component is
+    /// a definition, so a recipe that is still being written, like in a live
+    /// preview, has an output.
+    pub lenient_references: bool,
+    /// Collapse runs of whitespace in the text of steps and text paragraphs
+    ///
+    /// By default the text is kept as written, like the spaces used to align
+    /// the source. With this, every run of whitespace is a single space. The
+    /// lines of a step with [`MULTILINE_STEPS`](crate::Extensions::MULTILINE_STEPS)
+    /// and of a text paragraph are kept, separated by a single `\n`, instead
+    /// of joined with a space. Component names and notes are always trimmed.
+    pub collapse_whitespace: bool,
+    /// Find URLs in the text of steps, like `https://cooklang.org`, and make
+    /// them an [`Item::Link`](crate::Item::Link)
+    ///
+    /// URLs start with `http://`, `https://` or `www.` and end at a
+    /// whitespace. Punctuation at the end, like a full stop, is not part of
+    /// the URL.
+    ///
+    /// Links are found in the text between the components, so a URL with a
+    /// component marker, `@`, `#` or `~`, ends before it and the rest is
+    /// parsed as a component.
+    pub detect_links: bool,
+    /// Read quantity values written as English number words as numbers
+    ///
+    /// With this, `@eggs{two}` is the number 2 instead of the text `two`, so
+    /// it can be scaled and added. See [`Value::from_number_word`](crate::quantity::Value::from_number_word)
+    /// for the words. Any other text, like `some`, stays a text value.
+    pub number_words: bool,
+}
+
+/// Default for [`ParseOptions::max_step_length`]
+pub const DEFAULT_MAX_STEP_LENGTH: usize = 500;
+
+bitflags! {
+    /// Opt-in lints for [`ParseOptions`]
+    ///
+    /// These are warnings for valid recipes that are probably not what the
+    /// author meant. None are enabled by default.
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+    pub struct Lints: u32 {
+        /// Warn when an ingredient with the same name is defined more than
+        /// once, instead of being referenced
+        const DUPLICATE_DEFINITIONS = 1 << 0;
+        /// Warn when an ingredient first appears without a quantity and a
+        /// later definition with the same name has one
+        const USED_BEFORE_DEFINED = 1 << 1;
+        /// Warn when the value of a special metadata key is free text that
+        /// looks like it should be structured, like `servings: four` or
+        /// `time: 1h30`
+        const STRUCTURED_METADATA = 1 << 2;
+        /// Warn when every ingredient quantity has the auto scale marker
+        /// (`*`) but the `[auto scale]` config is not enabled
+        const AUTO_SCALE_MARKERS = 1 << 3;
+        /// Warn when the text of a step is longer than
+        /// [`ParseOptions::max_step_length`]
+        const LONG_STEPS = 1 << 4;
+        /// Warn when the quantities of the references to an ingredient add
+        /// up to more than its definition, for recipes where the definition
+        /// is the total that is used
+        ///
+        /// Only number quantities without a unit or with units of the same
+        /// physical quantity are compared.
+        const REFERENCE_QUANTITIES = 1 << 5;
+        /// Warn when the ingredients use both metric and imperial units,
+        /// like `cups` and `grams`
+        ///
+        /// The system of a unit is the one in the converter. Units without a
+        /// system are not checked. In the bundled units, spoons like `tsp`
+        /// are imperial.
+        const MIXED_UNIT_SYSTEMS = 1 << 6;
+    }
 }
 
 /// Return type for check functions in [`ParseOptions`]
@@ -74,3 +183,6 @@ impl CheckResult {
 
 pub type RecipeRefCheck<'a> = Box<dyn FnMut(&str) -> CheckResult + 'a>;
 pub type MetadataValidator<'a> = Box<dyn FnMut(&str, &str) -> (CheckResult, bool) + 'a>;
+/// Returns the check result and the value to store. If the value is [`None`],
+/// the entry is not included in the recipe.
+pub type MetadataHandler<'a> = Box<dyn FnMut(&str) -> (CheckResult, Option<String>) + 'a>;
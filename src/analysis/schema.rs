@@ -0,0 +1,85 @@
+//! Validate the metadata of a recipe with a JSON Schema
+
+use jsonschema::{error::ValidationErrorKind, paths::PathChunk, JSONSchema};
+use thiserror::Error;
+
+use crate::metadata::IndexMap;
+
+/// Compiled JSON Schema for the metadata of a recipe
+///
+/// The metadata is validated as a JSON object with every entry of
+/// [`Metadata::map`](crate::Metadata::map). All values are strings, so use
+/// keywords like `pattern` or `enum` to restrict them.
+///
+/// ```
+/// # use cooklang::{analysis::MetadataSchema, CooklangParser, ParseOptions};
+/// let schema = MetadataSchema::new(&serde_json::json!({
+///     "type": "object",
+///     "required": ["title"],
+///     "properties": {
+///         "difficulty": { "enum": ["easy", "medium", "hard"] }
+///     }
+/// }))
+/// .unwrap();
+/// let options = ParseOptions {
+///     metadata_schema: Some(&schema),
+///     ..Default::default()
+/// };
+/// let result = CooklangParser::extended()
+///     .parse_with_options(">> difficulty: extreme\nMix @flour.", options);
+/// assert_eq!(result.report().errors().count(), 2);
+/// ```
+pub struct MetadataSchema {
+    schema: JSONSchema,
+}
+
+/// Error compiling a [`MetadataSchema`]
+#[derive(Debug, Error)]
+#[error("Invalid metadata schema: {0}")]
+pub struct MetadataSchemaError(String);
+
+/// A metadata entry that doesn't match the schema
+pub(crate) struct SchemaViolation {
+    /// Key the violation is about, if any
+    pub key: Option<String>,
+    pub message: String,
+}
+
+impl MetadataSchema {
+    /// Compile a JSON Schema
+    pub fn new(schema: &serde_json::Value) -> Result<Self, MetadataSchemaError> {
+        let schema = JSONSchema::compile(schema).map_err(|e| MetadataSchemaError(e.to_string()))?;
+        Ok(Self { schema })
+    }
+
+    pub(crate) fn violations(&self, map: &IndexMap<String, String>) -> Vec<SchemaViolation> {
+        let instance = serde_json::Value::Object(
+            map.iter()
+                .map(|(k, v)| (k.clone(), serde_json::Value::String(v.clone())))
+                .collect(),
+        );
+        let Err(errors) = self.schema.validate(&instance) else {
+            return Vec::new();
+        };
+        errors
+            .map(|error| {
+                let key = match &error.kind {
+                    ValidationErrorKind::Required { property } => {
+                        property.as_str().map(String::from)
+                    }
+                    ValidationErrorKind::AdditionalProperties { unexpected } => {
+                        unexpected.first().cloned()
+                    }
+                    _ => error.instance_path.iter().find_map(|chunk| match chunk {
+                        PathChunk::Property(key) => Some(key.to_string()),
+                        _ => None,
+                    }),
+                };
+                SchemaViolation {
+                    key,
+                    message: error.to_string(),
+                }
+            })
+            .collect()
+    }
+}
@@ -29,18 +29,28 @@ pub struct Ast<'a> {
 pub fn build_ast<'i>(events: impl Iterator<Item = Event<'i>>) -> PassResult<Ast<'i>> {
     let mut blocks = Vec::new();
     let mut items = Vec::new();
+    let mut note = None;
     let mut ctx = SourceReport::empty();
     for event in events {
         match event {
             Event::Metadata { key, value } => blocks.push(Block::Metadata { key, value }),
-            Event::Section { name } => blocks.push(Block::Section { name }),
-            Event::Start(_kind) => items.clear(),
+            Event::Section { name } => blocks.push(Block::Section { name, yields: None }),
+            Event::SectionYield(q) => {
+                if let Some(Block::Section { yields, .. }) = blocks.last_mut() {
+                    *yields = Some(q);
+                }
+            }
+            Event::Start(_kind) => {
+                items.clear();
+                note = None;
+            }
             Event::End(kind) => {
                 match kind {
                     BlockKind::Step => {
                         if !items.is_empty() {
                             blocks.push(Block::Step {
                                 items: std::mem::take(&mut items),
+                                note: note.take(),
                             })
                         }
                     }
@@ -63,6 +73,7 @@ pub fn build_ast<'i>(events: impl Iterator<Item = Event<'i>>) -> PassResult<Ast<
             Event::Ingredient(c) => items.push(Item::Ingredient(Box::new(c))),
             Event::Cookware(c) => items.push(Item::Cookware(Box::new(c))),
             Event::Timer(c) => items.push(Item::Timer(Box::new(c))),
+            Event::StepNote(t) => note = Some(t),
             Event::Error(e) => ctx.push(e),
             Event::Warning(w) => ctx.push(w),
         }
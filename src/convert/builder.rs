@@ -215,7 +215,9 @@ impl ConverterBuilder {
             best,
             fractions,
             default_system: self.default_system,
+            preferred_system: None,
             temperature_regex: Default::default(),
+            temperature_range_regex: Default::default(),
         })
     }
 
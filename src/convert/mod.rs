@@ -15,7 +15,7 @@ use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use crate::{
-    quantity::{Number, Quantity, ScaledQuantity, Value},
+    quantity::{FormatOptions, Number, Quantity, ScaledQuantity, Value},
     ScaledRecipe, UnitInfo,
 };
 
@@ -42,8 +42,10 @@ pub struct Converter {
     best: EnumMap<PhysicalQuantity, BestConversionsStore>,
     fractions: Fractions,
     default_system: System,
+    preferred_system: Option<System>,
 
     temperature_regex: OnceCell<Regex>,
+    temperature_range_regex: OnceCell<Regex>,
 }
 
 impl Converter {
@@ -67,7 +69,9 @@ impl Converter {
             quantity_index: Default::default(),
             best: Default::default(),
             default_system: Default::default(),
+            preferred_system: None,
             temperature_regex: Default::default(),
+            temperature_range_regex: Default::default(),
             fractions: Default::default(),
         }
     }
@@ -95,6 +99,31 @@ impl Converter {
         self.default_system
     }
 
+    /// Get the preferred unit [System], if any
+    ///
+    /// See [`Self::set_preferred_system`].
+    pub fn preferred_system(&self) -> Option<System> {
+        self.preferred_system
+    }
+
+    /// Set the preferred unit [System]
+    ///
+    /// When set, [`Quantity::fit`] and [`ScaledRecipe::convert`] without an
+    /// explicit system convert to the best unit of this system instead of
+    /// keeping the original one. [`None`] by default.
+    ///
+    /// ```
+    /// # use cooklang::{Converter, convert::System, quantity::{Quantity, Value}};
+    /// let mut converter = Converter::bundled();
+    /// converter.set_preferred_system(Some(System::Metric));
+    /// let mut q = Quantity::new(Value::from(2.0), Some("cup".into()));
+    /// q.fit(&converter).unwrap();
+    /// assert_eq!(q.unit_text(), Some("ml"));
+    /// ```
+    pub fn set_preferred_system(&mut self, system: Option<System>) {
+        self.preferred_system = system;
+    }
+
     /// Get the total number of known units.
     ///
     /// This is **not** all the known unit names, just **different units**.
@@ -194,7 +223,8 @@ impl PartialEq for Converter {
             && self.quantity_index == other.quantity_index
             && self.best == other.best
             && self.default_system == other.default_system
-        // temperature_regex ignored, it should be the same if the rest is the
+            && self.preferred_system == other.preferred_system
+        // temperature regexes ignored, they should be the same if the rest is
         // the same
     }
 }
@@ -422,15 +452,32 @@ impl ScaledRecipe {
     /// Convert a [`ScaledRecipe`] to another [`System`] in place.
     ///
     /// When an error occurs, it is stored and the quantity stays the same.
+    /// Quantities with a [locked unit](crate::quantity::Quantity::unit_locked)
+    /// are not converted.
+    ///
+    /// Without a target system, the converter's
+    /// [preferred system](Converter::preferred_system) is used. If there is
+    /// none either, quantities stay in their own system and are only fitted to
+    /// the best unit.
     ///
     /// Returns all the errors while converting. These usually are missing units,
     /// unknown units or text values.
-    pub fn convert(&mut self, to: System, converter: &Converter) -> Vec<ConvertError> {
+    pub fn convert(
+        &mut self,
+        to: impl Into<Option<System>>,
+        converter: &Converter,
+    ) -> Vec<ConvertError> {
         let mut errors = Vec::new();
 
-        let to = ConvertTo::from(to);
+        let to = match to.into().or(converter.preferred_system()) {
+            Some(system) => ConvertTo::from(system),
+            None => ConvertTo::SameSystem,
+        };
 
         let mut conv = |q: &mut ScaledQuantity| {
+            if q.unit_locked {
+                return;
+            }
             if let Err(e) = q.convert(to, converter) {
                 errors.push(e)
             }
@@ -456,6 +503,31 @@ impl ScaledRecipe {
 
         errors
     }
+
+    /// Fit every quantity of the recipe with some [`DisplayPreferences`]
+    ///
+    /// Returns all the errors while converting, like in [`Self::convert`].
+    pub fn fit_with_preferences(
+        &mut self,
+        preferences: &DisplayPreferences,
+        converter: &Converter,
+    ) -> Vec<ConvertError> {
+        let mut errors = Vec::new();
+
+        let quantities = self
+            .ingredients
+            .iter_mut()
+            .filter_map(|i| i.quantity.as_mut())
+            .chain(self.timers.iter_mut().filter_map(|t| t.quantity.as_mut()))
+            .chain(self.inline_quantities.iter_mut());
+        for q in quantities {
+            if let Err(e) = q.fit_with_preferences(preferences, converter) {
+                errors.push(e);
+            }
+        }
+
+        errors
+    }
 }
 
 impl ScaledQuantity {
@@ -486,7 +558,11 @@ impl ScaledQuantity {
         let value = ConvertValue::try_from(&self.value)?;
 
         let (new_value, new_unit) = converter.convert(value, unit, to)?;
-        *self = Quantity::with_known_unit(new_value.into(), Arc::clone(&new_unit));
+        *self = Quantity {
+            approximate: self.approximate,
+            unit_locked: self.unit_locked,
+            ..Quantity::with_known_unit(new_value.into(), Arc::clone(&new_unit))
+        };
         match to {
             ConvertTo::Unit(_) => {
                 self.try_fraction(converter);
@@ -503,28 +579,100 @@ impl ScaledQuantity {
 
     /// Converts the unit to the best possible match in the same unit system.
     ///
-    /// For example, `1000 ml` would be converted to `1 l`.
+    /// For example, `1000 ml` would be converted to `1 l`. If the converter has
+    /// a [preferred system](Converter::preferred_system), the best match in
+    /// that system is used instead.
+    ///
+    /// A [locked unit](Quantity::unit_locked) is never changed.
     #[tracing::instrument(level = "trace", skip_all)]
     pub fn fit(&mut self, converter: &Converter) -> Result<(), ConvertError> {
+        if self.unit_locked {
+            return Ok(());
+        }
         // only known units can be fitted
         let Some(UnitInfo::Known(unit)) = self.unit().map(|u| u.unit_info_or_parse(converter))
         else {
             return Ok(());
         };
 
+        let preferred = converter.preferred_system();
+
         // If configured, try fitting as a fraction
         if converter.should_fit_fraction(&unit)
-            && self.fit_fraction(&unit, unit.system, converter)?
+            && self.fit_fraction(&unit, preferred.or(unit.system), converter)?
         {
             return Ok(());
         }
 
-        // convert to the best in the same system
-        self.convert(ConvertTo::SameSystem, converter)?;
+        // convert to the best in the preferred or the same system
+        match preferred {
+            Some(system) => self.convert(system, converter)?,
+            None => self.convert(ConvertTo::SameSystem, converter)?,
+        }
 
         Ok(())
     }
 
+    /// Same as [`Self::fit`], but quantities with a preferred unit in
+    /// `preferences` are always converted to it.
+    ///
+    /// For example, with `ml` as the preferred volume unit, `1.5 l` becomes
+    /// `1500 ml` instead of staying in litres.
+    pub fn fit_with_preferences(
+        &mut self,
+        preferences: &DisplayPreferences,
+        converter: &Converter,
+    ) -> Result<(), ConvertError> {
+        if self.unit_locked {
+            return Ok(());
+        }
+        let Some(UnitInfo::Known(unit)) = self.unit().map(|u| u.unit_info_or_parse(converter))
+        else {
+            return Ok(());
+        };
+
+        match preferences.unit(unit.physical_quantity) {
+            Some(preferred) => {
+                self.convert(preferred, converter)?;
+                self.try_fraction(converter);
+                Ok(())
+            }
+            None => self.fit(converter),
+        }
+    }
+
+    /// Formats the quantity with [`FormatOptions`] after fitting it with
+    /// [`Self::fit_with_preferences`]
+    ///
+    /// If the quantity can't be fitted, it is formatted as it is.
+    ///
+    /// ```
+    /// # use cooklang::{Converter, Quantity, Value};
+    /// # use cooklang::convert::{DisplayPreferences, PhysicalQuantity};
+    /// # use cooklang::quantity::{FormatOptions, NumberLocale};
+    /// let converter = Converter::bundled();
+    /// let preferences = DisplayPreferences::default().with_unit(PhysicalQuantity::Volume, "ml");
+    /// let options = FormatOptions {
+    ///     locale: NumberLocale::FR,
+    ///     ..Default::default()
+    /// };
+    /// let q = Quantity::new(Value::from(1.5), Some("l".to_string()));
+    /// let formatted = q.format_with_preferences(&options, &preferences, &converter);
+    /// assert_eq!(formatted, "1 500 ml");
+    /// ```
+    pub fn format_with_preferences(
+        &self,
+        options: &FormatOptions,
+        preferences: &DisplayPreferences,
+        converter: &Converter,
+    ) -> String {
+        let mut fitted = self.clone();
+        match fitted.fit_with_preferences(preferences, converter) {
+            Ok(()) => fitted.display_with(options).to_string(),
+            Err(_) => self.display_with(options).to_string(),
+        }
+    }
+
     /// Fits the quantity as an approximation.
     ///
     /// - Finds all the conversions where an approximation is possible
@@ -600,10 +748,34 @@ impl ScaledQuantity {
             }
             Value::Text(_) => unreachable!(),
         };
-        *self = Quantity::with_known_unit(new_value, Arc::clone(new_unit));
+        *self = Quantity {
+            approximate: self.approximate,
+            unit_locked: self.unit_locked,
+            ..Quantity::with_known_unit(new_value, Arc::clone(new_unit))
+        };
         Ok(true)
     }
 
+    /// Converts the quantity to the base unit of its physical quantity
+    ///
+    /// The base unit is the one every other unit is defined relative to. With
+    /// the bundled units these are grams, litres, metres, seconds and kelvin.
+    /// Use it to compare or sort quantities of the same [`PhysicalQuantity`].
+    ///
+    /// Returns [`None`] for text values and for quantities without a unit or
+    /// with an unknown unit. For ranges, the start of the range is used.
+    pub fn to_base(&self, converter: &Converter) -> Option<f64> {
+        let Some(UnitInfo::Known(unit)) = self.unit().map(|u| u.unit_info_or_parse(converter))
+        else {
+            return None;
+        };
+        let value = match ConvertValue::try_from(&self.value).ok()? {
+            ConvertValue::Number(n) => n,
+            ConvertValue::Range(r) => *r.start(),
+        };
+        Some((value + unit.difference) * unit.ratio)
+    }
+
     /// Tries to convert the value to a fraction, keeping the same unit
     ///
     /// It respects the converter configuration for the unit.
@@ -729,6 +901,45 @@ pub(crate) fn convert_f64(value: f64, from: &Unit, to: &Unit) -> f64 {
     (norm / to.ratio) - to.difference
 }
 
+/// Preferred units to display each [`PhysicalQuantity`]
+///
+/// Used in [`ScaledQuantity::fit_with_preferences`],
+/// [`ScaledQuantity::format_with_preferences`] and
+/// [`ScaledRecipe::fit_with_preferences`]. Physical quantities without a
+/// preference are fitted as usual.
+///
+/// ```
+/// # use cooklang::convert::{DisplayPreferences, PhysicalQuantity};
+/// let preferences = DisplayPreferences::default()
+///     .with_unit(PhysicalQuantity::Volume, "ml");
+/// assert_eq!(preferences.unit(PhysicalQuantity::Volume), Some("ml"));
+/// assert_eq!(preferences.unit(PhysicalQuantity::Mass), None);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DisplayPreferences {
+    units: EnumMap<PhysicalQuantity, Option<String>>,
+}
+
+impl DisplayPreferences {
+    /// Set the preferred unit for a physical quantity
+    ///
+    /// The unit can be any name, symbol or alias known by the converter.
+    pub fn with_unit(mut self, quantity: PhysicalQuantity, unit: impl Into<String>) -> Self {
+        self.set_unit(quantity, unit);
+        self
+    }
+
+    /// Same as [`Self::with_unit`] but in place
+    pub fn set_unit(&mut self, quantity: PhysicalQuantity, unit: impl Into<String>) {
+        self.units[quantity] = Some(unit.into());
+    }
+
+    /// Get the preferred unit for a physical quantity, if any
+    pub fn unit(&self, quantity: PhysicalQuantity) -> Option<&str> {
+        self.units[quantity].as_deref()
+    }
+}
+
 /// Error when try to convert an unknown unit
 #[derive(Debug, Error)]
 #[error("Unknown unit: '{0}'")]
@@ -902,9 +1113,19 @@ impl Converter {
             .map(|&id| self.all_units[id].as_ref())
     }
 
-    pub(crate) fn temperature_regex(&self) -> Result<&Regex, regex::Error> {
-        self.temperature_regex.get_or_try_init(|| {
-            let _guard = tracing::trace_span!("temp_regex").entered();
+    /// Regex to find temperatures in text, with the `start`, `end` and
+    /// `unit` groups
+    ///
+    /// With `ranges`, it also finds temperatures like `180-200ºC` and `end` is
+    /// set for them.
+    pub(crate) fn temperature_regex(&self, ranges: bool) -> Result<&Regex, regex::Error> {
+        let cell = if ranges {
+            &self.temperature_range_regex
+        } else {
+            &self.temperature_regex
+        };
+        cell.get_or_try_init(|| {
+            let _guard = tracing::trace_span!("temp_regex", ranges).entered();
             let symbols = self
                 .quantity_units(crate::convert::PhysicalQuantity::Temperature)
                 .flat_map(|unit| unit.symbols.iter())
@@ -912,9 +1133,32 @@ impl Converter {
                 .collect::<Vec<_>>()
                 .join("|");
             let float = r"[+-]?\d+([.,]\d+)?";
-            RegexBuilder::new(&format!(r"({float})\s*({symbols})"))
+            let range = if ranges {
+                format!(r"(\s*[-–]\s*(?P<end>{float}))?")
+            } else {
+                String::new()
+            };
+            RegexBuilder::new(&format!(r"(?P<start>{float}){range}\s*(?P<unit>{symbols})"))
                 .size_limit(500_000)
                 .build()
         })
     }
+
+    /// Checks if a text has a temperature, like `220°C`
+    ///
+    /// Temperatures are found like with the
+    /// [`TEMPERATURE`](crate::Extensions::TEMPERATURE) extension, with the
+    /// temperature units of this converter. The regex is built on the first
+    /// call and reused, so keep the converter around to check many texts.
+    ///
+    /// ```
+    /// # use cooklang::Converter;
+    /// let converter = Converter::bundled();
+    /// assert!(converter.has_temperature("Bake at 220°C for 30 min"));
+    /// assert!(!converter.has_temperature("Bake for 30 min"));
+    /// ```
+    pub fn has_temperature(&self, text: &str) -> bool {
+        self.temperature_regex(false)
+            .is_ok_and(|re| re.is_match(text))
+    }
 }
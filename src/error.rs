@@ -182,6 +182,46 @@ pub enum Stage {
     Analysis,
 }
 
+/// Number of diagnostics in a [`SourceReport`] by severity
+///
+/// Created with [`SourceReport::summary`]. The display impl is a short text
+/// like `3 errors, 5 warnings`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReportSummary {
+    pub errors: usize,
+    pub warnings: usize,
+    /// Hints of the errors
+    pub error_hints: usize,
+    /// Hints of the warnings
+    pub warning_hints: usize,
+}
+
+impl ReportSummary {
+    /// Errors plus warnings
+    pub fn total(&self) -> usize {
+        self.errors + self.warnings
+    }
+
+    /// Hints of all the errors and warnings
+    pub fn hints(&self) -> usize {
+        self.error_hints + self.warning_hints
+    }
+}
+
+impl std::fmt::Display for ReportSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let plural = |n: usize| if n == 1 { "" } else { "s" };
+        write!(
+            f,
+            "{} error{}, {} warning{}",
+            self.errors,
+            plural(self.errors),
+            self.warnings,
+            plural(self.warnings)
+        )
+    }
+}
+
 /// Errors and warnings container with fancy formatting
 ///
 /// The [`Display`](std::fmt::Display) implementation is not fancy formatting,
@@ -295,6 +335,44 @@ impl SourceReport {
         self.buf.retain(SourceDiag::is_error)
     }
 
+    /// Counts the errors, the warnings and their hints
+    ///
+    /// To go through the diagnostics of each severity, use [`Self::errors`]
+    /// and [`Self::warnings`].
+    ///
+    /// ```
+    /// let result = cooklang::parse("Add @water{1%l} and @&water{a lot}.");
+    /// let summary = result.report().summary();
+    /// assert_eq!(summary.errors, 0);
+    /// assert_eq!(summary.warnings, 2);
+    /// assert_eq!(summary.warning_hints, 1);
+    /// assert_eq!(summary.hints(), 1);
+    /// assert_eq!(summary.to_string(), "0 errors, 2 warnings");
+    ///
+    /// let result = cooklang::parse("Wait ~{5}.");
+    /// let summary = result.report().summary();
+    /// assert_eq!(summary.to_string(), "1 error, 0 warnings");
+    /// assert_eq!(summary.error_hints, 1);
+    /// assert_eq!(summary.total(), 1);
+    /// ```
+    pub fn summary(&self) -> ReportSummary {
+        let mut summary = ReportSummary::default();
+        for diag in &self.buf {
+            let hints = diag.hints.len();
+            match diag.severity {
+                Severity::Error => {
+                    summary.errors += 1;
+                    summary.error_hints += hints;
+                }
+                Severity::Warning => {
+                    summary.warnings += 1;
+                    summary.warning_hints += hints;
+                }
+            }
+        }
+        summary
+    }
+
     /// Consumes the report and returns [`Vec`] of [`SourceDiag`]
     pub fn into_vec(self) -> Vec<SourceDiag> {
         self.buf
@@ -0,0 +1,141 @@
+//! Find the cookware that involves heat
+//!
+//! This is a heuristic based on the cookware names, useful to show safety
+//! reminders like "oven used". Only names with a word in the
+//! [`HeatKeywords`] are considered to involve heat.
+
+use std::collections::HashSet;
+
+use crate::{ingredient_list::GroupedCookware, ScaledRecipe};
+
+/// Common words for cookware that heats in English
+const DEFAULT_KEYWORDS: &[&str] = &[
+    "barbecue",
+    "bbq",
+    "broiler",
+    "burner",
+    "cooker",
+    "fryer",
+    "griddle",
+    "grill",
+    "hob",
+    "kettle",
+    "microwave",
+    "oven",
+    "pan",
+    "pot",
+    "saucepan",
+    "skillet",
+    "smoker",
+    "steamer",
+    "stove",
+    "stovetop",
+    "toaster",
+    "torch",
+    "wok",
+];
+
+/// Set of words that mark cookware as involving heat
+///
+/// The [`Default`] contains common cookware in English. Matching is case
+/// insensitive and done word by word, so `pan` matches "frying pan" and "pans"
+/// but not "panettone tin".
+///
+/// ```
+/// # use cooklang::heat::HeatKeywords;
+/// let keywords = HeatKeywords::default().with_keywords(["tandoor"]);
+/// assert!(keywords.matches("Dutch oven"));
+/// assert!(keywords.matches("tandoor"));
+/// assert!(!keywords.matches("bowl"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct HeatKeywords {
+    keywords: HashSet<String>,
+}
+
+impl HeatKeywords {
+    /// Create a new set with only the given keywords
+    pub fn new<S: AsRef<str>>(keywords: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            keywords: keywords
+                .into_iter()
+                .map(|k| k.as_ref().to_lowercase())
+                .collect(),
+        }
+    }
+
+    /// Add more keywords to the set
+    pub fn with_keywords<S: AsRef<str>>(mut self, keywords: impl IntoIterator<Item = S>) -> Self {
+        self.keywords
+            .extend(keywords.into_iter().map(|k| k.as_ref().to_lowercase()));
+        self
+    }
+
+    /// Check if a cookware name involves heat
+    pub fn matches(&self, name: &str) -> bool {
+        name.split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .map(str::to_lowercase)
+            .any(|word| {
+                self.keywords.contains(&word)
+                    || word
+                        .strip_suffix('s')
+                        .is_some_and(|w| self.keywords.contains(w))
+            })
+    }
+}
+
+impl Default for HeatKeywords {
+    fn default() -> Self {
+        Self::new(DEFAULT_KEYWORDS)
+    }
+}
+
+impl ScaledRecipe {
+    /// Cookware **definitions** that involve heat
+    ///
+    /// This is [`group_cookware`](Self::group_cookware) filtered by the
+    /// [`display_name`](crate::Cookware::display_name) of each item. Items
+    /// defined more than once with the same name are only included the first
+    /// time.
+    ///
+    /// ```
+    /// # use cooklang::heat::HeatKeywords;
+    /// let recipe = cooklang::parse("Preheat the #oven. Mix in a #bowl and pour into a #pan. Put the #pan in the #oven.")
+    ///     .into_output()
+    ///     .unwrap()
+    ///     .default_scale();
+    /// let heat = recipe.heat_cookware(&HeatKeywords::default());
+    /// let names = heat.iter().map(|c| c.cookware.name.as_str()).collect::<Vec<_>>();
+    /// assert_eq!(names, ["oven", "pan"]);
+    /// ```
+    pub fn heat_cookware(&self, keywords: &HeatKeywords) -> Vec<GroupedCookware<'_>> {
+        let mut seen = HashSet::new();
+        self.group_cookware()
+            .into_iter()
+            .filter(|c| {
+                let name = c.cookware.display_name();
+                keywords.matches(name) && seen.insert(name.to_lowercase())
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keywords() {
+        let keywords = HeatKeywords::default();
+        assert!(keywords.matches("frying pan"));
+        assert!(keywords.matches("Pots"));
+        assert!(keywords.matches("air-fryer"));
+        assert!(!keywords.matches("panettone tin"));
+        assert!(!keywords.matches("baking sheet"));
+
+        let keywords = HeatKeywords::new(["horno"]);
+        assert!(keywords.matches("Horno"));
+        assert!(!keywords.matches("oven"));
+    }
+}
@@ -1,16 +1,19 @@
 //! Generate ingredients lists from recipes
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 use serde::Serialize;
 
 use crate::{
     aisle::AisleConf,
-    convert::Converter,
+    convert::{Converter, PhysicalQuantity},
+    error::SourceReport,
     model::Ingredient,
-    quantity::{GroupedQuantity, GroupedValue},
+    quantity::{
+        AggregateMode, GroupedQuantity, GroupedValue, QuantityValue, ScaledQuantity, UnitInfo,
+    },
     scale::ScaleOutcome,
-    Cookware, ScaledRecipe, Value,
+    CooklangParser, Cookware, Item, Quantity, ScaledRecipe, Value,
 };
 
 /// Ingredient with all quantities from it's references and itself grouped.
@@ -31,6 +34,30 @@ pub struct GroupedIngredient<'a> {
     ///
     /// If any scaling outcome was [`ScaleOutcome::Fixed`], this will be the fixed.
     pub outcome: Option<ScaleOutcome>,
+    /// Index of the quantities in [`Recipe::ingredients`](crate::model::Recipe::ingredients)
+    /// that were grouped with an inferred unit
+    ///
+    /// Always empty unless [`GroupOptions::infer_units`] is enabled.
+    pub inferred_units: Vec<usize>,
+}
+
+/// Options for [`ScaledRecipe::group_ingredients_with_options`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GroupOptions {
+    /// Group a number written without a unit with the unit of a previous
+    /// quantity of an ingredient with the same name
+    ///
+    /// With `@flour{200%g}` and later `@flour{100}`, the `100` is grouped as
+    /// `100 g`, so the total is `300 g` instead of `200 g, 100`. Names are
+    /// compared ignoring case and the last unit before the quantity is used.
+    /// Text values are never inferred.
+    pub infer_units: bool,
+    /// List the references to other recipes, like `@./sauces/pesto{}`, with
+    /// the name of the recipe, `pesto`, see [`Ingredient::referenced_recipe`]
+    ///
+    /// Only used by [`IngredientList::add_recipe_with_options`]. Without this,
+    /// they are listed with their [`display_name`](Ingredient::display_name).
+    pub recipe_refs_as_ingredients: bool,
 }
 
 /// Cookware item with all amounts from it's references and itself grouped.
@@ -46,6 +73,52 @@ pub struct GroupedCookware<'a> {
     pub amount: GroupedValue,
 }
 
+/// Share of the total mass of each ingredient
+///
+/// Created from [`ScaledRecipe::ingredient_percentages`].
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct IngredientPercentages {
+    /// Index of the ingredient definition in the [`Recipe::ingredients`](crate::model::Recipe::ingredients)
+    /// and its percentage, from 0 to 100
+    pub percentages: Vec<(usize, f64)>,
+    /// Index of the ingredient definitions that are not included because
+    /// some of their quantities are missing, text or not a mass
+    pub excluded: Vec<usize>,
+}
+
+/// One ingredient of a recipe as a flat record, like a row of a table
+///
+/// Created from [`ScaledRecipe::ingredient_rows`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct IngredientRow<'a> {
+    /// Index of the ingredient in the [`Recipe::ingredients`](crate::model::Recipe::ingredients)
+    pub index: usize,
+    /// Name of the ingredient, the same for its references
+    pub name: &'a str,
+    /// Number of the quantity, [`None`] if there is no quantity or it is text
+    ///
+    /// For ranges, the start of the range is used.
+    pub value: Option<f64>,
+    /// Unit as written in the recipe
+    pub unit: Option<&'a str>,
+    /// The value in the base unit of the physical quantity, like grams for a
+    /// mass, see [`Quantity::to_base`]
+    ///
+    /// [`None`] if the unit is not known by the converter.
+    pub base_value: Option<f64>,
+    /// Physical quantity of the unit, [`None`] if the unit is not known
+    pub physical_quantity: Option<PhysicalQuantity>,
+    /// Marked as optional with `@?`
+    pub optional: bool,
+    /// Marked as hidden with `@-`
+    pub hidden: bool,
+    /// Step where the ingredient is, numbered from 1 across all the sections
+    /// like in [`Recipe::ingredient_steps`](crate::model::Recipe::ingredient_steps)
+    ///
+    /// [`None`] if it's not in a step, like in the components define mode.
+    pub step: Option<u32>,
+}
+
 impl ScaledRecipe {
     /// List of ingredient **definitions** with quantities of all of it
     /// references grouped.
@@ -75,13 +148,69 @@ impl ScaledRecipe {
     /// assert!(water.quantity.is_empty());
     /// ```
     pub fn group_ingredients<'a>(&'a self, converter: &Converter) -> Vec<GroupedIngredient<'a>> {
+        self.group_ingredients_with_options(converter, GroupOptions::default())
+    }
+
+    /// Same as [`Self::group_ingredients`] but with [`GroupOptions`]
+    ///
+    /// Each inferred unit is logged with [tracing] at the debug level and
+    /// listed in [`GroupedIngredient::inferred_units`].
+    ///
+    /// ```
+    /// # use cooklang::{Converter, ingredient_list::GroupOptions};
+    /// let recipe = cooklang::parse("Mix @flour{200%g}.\n\nDust with @&flour{100}.")
+    ///     .into_output()
+    ///     .unwrap()
+    ///     .default_scale();
+    /// let converter = Converter::bundled();
+    /// let grouped = recipe.group_ingredients(&converter);
+    /// assert_eq!(grouped[0].quantity.to_string(), "200 g, 100");
+    ///
+    /// let options = GroupOptions {
+    ///     infer_units: true,
+    ///     ..Default::default()
+    /// };
+    /// let grouped = recipe.group_ingredients_with_options(&converter, options);
+    /// assert_eq!(grouped[0].quantity.to_string(), "300 g");
+    /// assert_eq!(grouped[0].inferred_units, [1]);
+    /// ```
+    pub fn group_ingredients_with_options<'a>(
+        &'a self,
+        converter: &Converter,
+        options: GroupOptions,
+    ) -> Vec<GroupedIngredient<'a>> {
+        let inferred = if options.infer_units {
+            self.inferred_unit_quantities(converter)
+        } else {
+            HashMap::new()
+        };
         let mut list = Vec::new();
         let data = self.scaled_data();
         for (index, ingredient) in self.ingredients.iter().enumerate() {
             if !ingredient.relation.is_definition() {
                 continue;
             }
-            let grouped = ingredient.group_quantities(&self.ingredients, converter);
+            let all_indices = std::iter::once(index)
+                .chain(ingredient.relation.referenced_from().iter().copied())
+                .collect::<Vec<_>>();
+            let inferred_units = all_indices
+                .iter()
+                .copied()
+                .filter(|i| inferred.contains_key(i))
+                .collect::<Vec<_>>();
+            let grouped = if inferred_units.is_empty() {
+                ingredient.group_quantities(&self.ingredients, converter)
+            } else {
+                let mut grouped = GroupedQuantity::default();
+                for i in &all_indices {
+                    let quantity = inferred.get(i).or(self.ingredients[*i].quantity.as_ref());
+                    if let Some(q) = quantity {
+                        grouped.add(q, converter);
+                    }
+                }
+                let _ = grouped.fit(converter);
+                grouped
+            };
             let outcome: Option<ScaleOutcome> = data
                 .as_ref()
                 .map(|data| {
@@ -103,11 +232,44 @@ impl ScaledRecipe {
                 ingredient,
                 quantity: grouped,
                 outcome,
+                inferred_units,
             });
         }
         list
     }
 
+    /// Quantities without a unit with the unit of a previous quantity of an
+    /// ingredient with the same name, by index
+    fn inferred_unit_quantities(&self, converter: &Converter) -> HashMap<usize, ScaledQuantity> {
+        let mut last_unit: HashMap<String, &str> = HashMap::new();
+        let mut inferred = HashMap::new();
+        for (index, ingredient) in self.ingredients.iter().enumerate() {
+            let Some(quantity) = &ingredient.quantity else {
+                continue;
+            };
+            let name = ingredient.name.to_lowercase();
+            if let Some(unit) = quantity.unit_text() {
+                last_unit.insert(name, unit);
+                continue;
+            }
+            let Some(&unit) = last_unit.get(&name) else {
+                continue;
+            };
+            if quantity.value.is_text() {
+                continue;
+            }
+            tracing::debug!(
+                "Inferred unit '{unit}' for '{}' from a previous quantity",
+                ingredient.name
+            );
+            let mut q =
+                Quantity::new_and_parse(quantity.value.clone(), Some(unit.to_string()), converter);
+            q.approximate = quantity.approximate;
+            inferred.insert(index, q);
+        }
+        inferred
+    }
+
     /// List of cookware **definitions** with amount of all of it
     /// references grouped.
     ///
@@ -127,6 +289,220 @@ impl ScaledRecipe {
         }
         list
     }
+
+    /// Share of the total mass of each ingredient **definition**
+    ///
+    /// Quantities of references are added to their definition and converted
+    /// with [`to_base`](crate::quantity::Quantity::to_base), so different mass
+    /// units can be mixed. Only ingredients where all quantities are a mass, or
+    /// a volume with a density in the metadata, are included. The rest are in
+    /// [`IngredientPercentages::excluded`].
+    ///
+    /// Order is the recipe order.
+    ///
+    /// ```
+    /// # use cooklang::{CooklangParser, Extensions, Converter};
+    /// let parser = CooklangParser::new(Extensions::all(), Converter::bundled());
+    /// let recipe = parser
+    ///     .parse("@flour{1%kg} @water{600%g} @salt{20%g} @yeast{1%pinch} @&water{100%g}")
+    ///     .into_output()
+    ///     .unwrap()
+    ///     .default_scale();
+    /// let result = recipe.ingredient_percentages(parser.converter());
+    ///
+    /// let names = result
+    ///     .percentages
+    ///     .iter()
+    ///     .map(|&(i, p)| (recipe.ingredients[i].name.as_str(), p.round()))
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(names, [("flour", 58.0), ("water", 41.0), ("salt", 1.0)]);
+    /// // pinch is not a mass
+    /// assert_eq!(result.excluded, [3]);
+    /// ```
+    pub fn ingredient_percentages(&self, converter: &Converter) -> IngredientPercentages {
+        let mut result = IngredientPercentages::default();
+        let mut masses = Vec::new();
+        for entry in self.group_ingredients(converter) {
+            match self.grams(&entry, converter) {
+                Some(mass) => masses.push((entry.index, mass)),
+                None => result.excluded.push(entry.index),
+            }
+        }
+
+        let total: f64 = masses.iter().map(|(_, m)| m).sum();
+        if total > 0.0 {
+            result.percentages = masses
+                .into_iter()
+                .map(|(index, mass)| (index, mass / total * 100.0))
+                .collect();
+        } else {
+            result.excluded.extend(masses.into_iter().map(|(i, _)| i));
+            result.excluded.sort_unstable();
+        }
+        result
+    }
+
+    /// Mass in grams of each ingredient **definition**, for things like
+    /// nutrition calculators
+    ///
+    /// Like [`ingredient_percentages`](Self::ingredient_percentages), the
+    /// quantities of the references are added to their definition. The mass
+    /// is [`None`] if the ingredient has no quantity or any of its quantities
+    /// is not a mass, like a count or text. Volumes are converted with the
+    /// density of the ingredient in the metadata, see
+    /// [`Metadata::density`](crate::metadata::Metadata::density), or the mass
+    /// is [`None`] too. The mass is in the base unit, which is grams with the
+    /// bundled units.
+    ///
+    /// Order is the recipe order.
+    ///
+    /// ```
+    /// # use cooklang::{CooklangParser, Extensions, Converter};
+    /// let parser = CooklangParser::new(Extensions::all(), Converter::bundled());
+    /// let recipe = parser
+    ///     .parse("@flour{1%kg} @milk{200%ml} @sugar{50%g} @&flour{20%g} @egg{2}")
+    ///     .into_output()
+    ///     .unwrap()
+    ///     .default_scale();
+    /// assert_eq!(
+    ///     recipe.ingredient_grams(parser.converter()),
+    ///     [("flour", Some(1020.0)), ("milk", None), ("sugar", Some(50.0)), ("egg", None)]
+    /// );
+    /// ```
+    pub fn ingredient_grams(&self, converter: &Converter) -> Vec<(&str, Option<f64>)> {
+        self.group_ingredients(converter)
+            .into_iter()
+            .map(|entry| {
+                (
+                    entry.ingredient.name.as_str(),
+                    self.grams(&entry, converter),
+                )
+            })
+            .collect()
+    }
+
+    /// Total of the ingredient quantities of each physical quantity, like the
+    /// total mass and the total volume, for things like packaging estimates
+    ///
+    /// Every quantity is added, references included. See [`quantity_totals`].
+    ///
+    /// ```
+    /// # use cooklang::{CooklangParser, convert::PhysicalQuantity};
+    /// let parser = CooklangParser::extended();
+    /// let recipe = parser
+    ///     .parse("Mix @flour{1%kg}, @sugar{200%g}, @milk{1/4%l} and @eggs{2}.")
+    ///     .into_output()
+    ///     .unwrap()
+    ///     .default_scale();
+    /// let totals = recipe.totals_by_category(parser.converter());
+    /// assert_eq!(totals[&PhysicalQuantity::Mass], 1200.0);
+    /// assert_eq!(totals[&PhysicalQuantity::Volume], 0.25);
+    /// assert_eq!(totals.len(), 2);
+    /// ```
+    pub fn totals_by_category(&self, converter: &Converter) -> HashMap<PhysicalQuantity, f64> {
+        quantity_totals(
+            self.ingredients.iter().filter_map(|i| i.quantity.as_ref()),
+            converter,
+        )
+    }
+
+    /// Every ingredient, references included, as an [`IngredientRow`]
+    ///
+    /// This is a flat view of the ingredients for data pipelines, like
+    /// loading them in a database. Unlike [`Self::group_ingredients`], nothing
+    /// is added up, there is one row for each ingredient in
+    /// [`Recipe::ingredients`](crate::model::Recipe::ingredients), in the same
+    /// order.
+    ///
+    /// ```
+    /// # use cooklang::{CooklangParser, Converter, convert::PhysicalQuantity};
+    /// let parser = CooklangParser::extended();
+    /// let recipe = parser
+    ///     .parse("Mix @flour{1%kg} and @?salt{a pinch}.\n\nAdd @&flour{2-3%tbsp}.")
+    ///     .into_output()
+    ///     .unwrap()
+    ///     .default_scale();
+    /// let rows = recipe.ingredient_rows(parser.converter());
+    /// assert_eq!(rows.len(), 3);
+    ///
+    /// assert_eq!(rows[0].value, Some(1.0));
+    /// assert_eq!(rows[0].unit, Some("kg"));
+    /// assert_eq!(rows[0].base_value, Some(1000.0));
+    /// assert_eq!(rows[0].physical_quantity, Some(PhysicalQuantity::Mass));
+    ///
+    /// assert_eq!(rows[1].name, "salt");
+    /// assert_eq!(rows[1].value, None);
+    /// assert!(rows[1].optional);
+    ///
+    /// assert_eq!(rows[2].name, "flour");
+    /// assert_eq!(rows[2].value, Some(2.0));
+    /// assert_eq!(rows[2].physical_quantity, Some(PhysicalQuantity::Volume));
+    /// assert_eq!(rows[2].step, Some(2));
+    /// ```
+    pub fn ingredient_rows(&self, converter: &Converter) -> Vec<IngredientRow<'_>> {
+        let mut steps = vec![None; self.ingredients.len()];
+        for (number, step) in self.numbered_steps() {
+            for item in &step.items {
+                if let Item::Ingredient { index } = item {
+                    steps[*index] = Some(number);
+                }
+            }
+        }
+
+        self.ingredients
+            .iter()
+            .zip(steps)
+            .enumerate()
+            .map(|(index, (ingredient, step))| {
+                let quantity = ingredient.quantity.as_ref();
+                let physical_quantity = match quantity
+                    .and_then(|q| q.unit())
+                    .map(|u| u.unit_info_or_parse(converter))
+                {
+                    Some(UnitInfo::Known(unit)) => Some(unit.physical_quantity),
+                    _ => None,
+                };
+                let value = quantity.and_then(|q| match &q.value {
+                    Value::Number(n) => Some(n.value()),
+                    Value::Range { start, .. } => Some(start.value()),
+                    Value::Text(_) => None,
+                });
+                IngredientRow {
+                    index,
+                    name: &ingredient.name,
+                    value,
+                    unit: quantity.and_then(|q| q.unit_text()),
+                    base_value: quantity.and_then(|q| q.to_base(converter)),
+                    physical_quantity,
+                    optional: ingredient.modifiers().is_optional(),
+                    hidden: ingredient.modifiers().is_hidden(),
+                    step,
+                }
+            })
+            .collect()
+    }
+
+    /// Sum of the quantities in grams, if all of them are a mass or a volume
+    /// with a density
+    fn grams(&self, entry: &GroupedIngredient, converter: &Converter) -> Option<f64> {
+        if entry.quantity.is_empty() {
+            return None;
+        }
+        let density = self.metadata.density(&entry.ingredient.name, converter);
+        let mut mass = 0.0;
+        for q in entry.quantity.iter() {
+            let Some(UnitInfo::Known(unit)) = q.unit().map(|u| u.unit_info_or_parse(converter))
+            else {
+                return None;
+            };
+            mass += match (unit.physical_quantity, density) {
+                (PhysicalQuantity::Mass, _) => q.to_base(converter)?,
+                (PhysicalQuantity::Volume, Some(density)) => q.to_base(converter)? * density,
+                _ => return None,
+            };
+        }
+        Some(mass)
+    }
 }
 
 /// List of ingredients with quantities.
@@ -134,8 +510,15 @@ impl ScaledRecipe {
 /// This will only store the ingredient name and quantity. Sorted by name. This
 /// is used to combine multiple recipes into a single list. For ingredients of a
 /// single recipe, check [`ScaledRecipe::group_ingredients`].
-#[derive(Debug, Default)]
-pub struct IngredientList(BTreeMap<String, GroupedQuantity>);
+///
+/// The order in which the ingredients were first added is also kept, see
+/// [`IngredientList::iter_in_order`].
+#[derive(Debug, Default, Clone)]
+pub struct IngredientList {
+    ingredients: BTreeMap<String, GroupedQuantity>,
+    /// Names in the order they were first added
+    order: Vec<String>,
+}
 
 impl IngredientList {
     /// Empty list
@@ -163,7 +546,18 @@ impl IngredientList {
     ///
     /// Ingredients are listed based on their [`display_name`](crate::model::Ingredient::display_name).
     pub fn add_recipe(&mut self, recipe: &ScaledRecipe, converter: &Converter) {
-        for entry in recipe.group_ingredients(converter) {
+        self.add_recipe_with_options(recipe, GroupOptions::default(), converter)
+    }
+
+    /// Same as [`IngredientList::add_recipe`] but grouping the ingredients of
+    /// the recipe with [`GroupOptions`]
+    pub fn add_recipe_with_options(
+        &mut self,
+        recipe: &ScaledRecipe,
+        options: GroupOptions,
+        converter: &Converter,
+    ) {
+        for entry in recipe.group_ingredients_with_options(converter, options) {
             let GroupedIngredient {
                 ingredient,
                 quantity,
@@ -179,7 +573,11 @@ impl IngredientList {
                 tracing::error!("Error scaling ingredient: {err}");
             }
 
-            self.add_ingredient(ingredient.display_name().into_owned(), &quantity, converter);
+            let name = match ingredient.referenced_recipe() {
+                Some(recipe) if options.recipe_refs_as_ingredients => recipe.to_string(),
+                _ => ingredient.display_name().into_owned(),
+            };
+            self.add_ingredient(name, &quantity, converter);
         }
     }
 
@@ -192,12 +590,31 @@ impl IngredientList {
         quantity: &GroupedQuantity,
         converter: &Converter,
     ) {
-        self.0.entry(name).or_default().merge(quantity, converter)
+        self.add_ingredient_with_mode(name, quantity, AggregateMode::Sum, converter)
+    }
+
+    /// Same as [`IngredientList::add_ingredient`] but choosing how the
+    /// quantities are combined
+    pub fn add_ingredient_with_mode(
+        &mut self,
+        name: String,
+        quantity: &GroupedQuantity,
+        mode: AggregateMode,
+        converter: &Converter,
+    ) {
+        self.entry(name).merge_with_mode(quantity, converter, mode)
+    }
+
+    fn entry(&mut self, name: String) -> &mut GroupedQuantity {
+        if !self.ingredients.contains_key(&name) {
+            self.order.push(name.clone());
+        }
+        self.ingredients.entry(name).or_default()
     }
 
     /// Cheks if the list is empty
     pub fn is_empty(&self) -> bool {
-        self.0.is_empty()
+        self.ingredients.is_empty()
     }
 
     /// Split this list into different categories.
@@ -206,25 +623,290 @@ impl IngredientList {
     pub fn categorize(self, aisle: &AisleConf) -> CategorizedIngredientList {
         let aisle = aisle.reverse();
         let mut categorized = CategorizedIngredientList::default();
-        for (name, quantity) in self.0 {
-            if let Some(cat) = aisle.get(name.as_str()) {
-                categorized
-                    .categories
-                    .entry(cat.to_string())
-                    .or_default()
-                    .0
-                    .insert(name, quantity);
-            } else {
-                categorized.other.0.insert(name, quantity);
-            }
+        for (name, quantity) in self.into_iter_in_order() {
+            let list = match aisle.get(name.as_str()) {
+                Some(cat) => categorized.categories.entry(cat.to_string()).or_default(),
+                None => &mut categorized.other,
+            };
+            *list.entry(name) = quantity;
         }
         categorized
     }
 
+    /// Like [`categorize`](Self::categorize), but leaving out the pantry
+    /// staples
+    ///
+    /// Staples are the ingredients in the [`PANTRY_CATEGORY`](crate::aisle::PANTRY_CATEGORY)
+    /// of the aisle configuration and the ones in `extra`. They are removed
+    /// from the list, so they don't appear in any category or in `"other"`.
+    ///
+    /// ```
+    /// # use cooklang::{Converter, ingredient_list::IngredientList};
+    /// let recipe = cooklang::parse("Mix @flour{200%g}, @water{120%ml}, @salt and @pepper.")
+    ///     .into_output()
+    ///     .unwrap()
+    ///     .default_scale();
+    /// let list = IngredientList::from_recipe(&recipe, &Converter::empty());
+    /// let aisle = cooklang::aisle::parse("[baking]\nflour\n[pantry]\nwater\nsalt").unwrap();
+    /// let categorized = list.categorize_without_staples(&aisle, &["pepper"]);
+    /// let names = categorized
+    ///     .iter()
+    ///     .flat_map(|(_, list)| list.iter().map(|(name, _)| name.as_str()))
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(names, ["flour"]);
+    /// ```
+    pub fn categorize_without_staples(
+        mut self,
+        aisle: &AisleConf,
+        extra: &[&str],
+    ) -> CategorizedIngredientList {
+        let pantry = aisle.pantry();
+        let is_staple =
+            |name: &String| pantry.contains(name.as_str()) || extra.contains(&name.as_str());
+        self.ingredients.retain(|name, _| !is_staple(name));
+        self.order.retain(|name| !is_staple(name));
+        self.categorize(aisle)
+    }
+
+    /// Names of the ingredients without a category in the aisle configuration
+    ///
+    /// These are the ingredients that [`categorize`](Self::categorize) would
+    /// place in `"other"`, sorted by name.
+    ///
+    /// ```
+    /// # use cooklang::{Converter, ingredient_list::IngredientList};
+    /// let recipe = cooklang::parse("Mix @flour{200%g}, @salt and @sumac.")
+    ///     .into_output()
+    ///     .unwrap()
+    ///     .default_scale();
+    /// let list = IngredientList::from_recipe(&recipe, &Converter::empty());
+    /// let aisle = cooklang::aisle::parse("[baking]\nflour\n[spices]\nsalt").unwrap();
+    /// assert_eq!(list.uncategorized(&aisle), ["sumac"]);
+    /// ```
+    pub fn uncategorized(&self, aisle: &AisleConf) -> Vec<&str> {
+        let aisle = aisle.reverse();
+        self.ingredients
+            .keys()
+            .filter(|name| !aisle.contains_key(name.as_str()))
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// Names of the ingredients in both lists
+    ///
+    /// See [`shared_ingredients`].
+    pub fn shared_with(&self, other: &IngredientList) -> Vec<String> {
+        shared_ingredients(self.ingredients.keys(), other.ingredients.keys())
+    }
+
     /// Iterate over all ingredients sorted by name
     pub fn iter(&self) -> impl Iterator<Item = (&String, &GroupedQuantity)> {
-        self.0.iter()
+        self.ingredients.iter()
     }
+
+    /// Iterate over all ingredients in the order they were first added
+    ///
+    /// For a list made from a recipe, this is the order in which the
+    /// ingredients first appear in it. When more recipes are added, their
+    /// new ingredients go after the ones already in the list.
+    ///
+    /// ```
+    /// # use cooklang::{Converter, ingredient_list::IngredientList};
+    /// let recipe = cooklang::parse("Mix @zucchini{1}, @apple{2} and @zucchini{1}.")
+    ///     .into_output()
+    ///     .unwrap()
+    ///     .default_scale();
+    /// let list = IngredientList::from_recipe(&recipe, &Converter::empty());
+    /// let names = list.iter_in_order().map(|(name, _)| name.as_str()).collect::<Vec<_>>();
+    /// assert_eq!(names, ["zucchini", "apple"]);
+    /// ```
+    pub fn iter_in_order(&self) -> impl Iterator<Item = (&String, &GroupedQuantity)> {
+        self.order
+            .iter()
+            .map(|name| (name, &self.ingredients[name]))
+    }
+
+    fn into_iter_in_order(self) -> impl Iterator<Item = (String, GroupedQuantity)> {
+        let Self {
+            mut ingredients,
+            order,
+        } = self;
+        order.into_iter().map(move |name| {
+            let quantity = ingredients.remove(&name).expect("name in the list");
+            (name, quantity)
+        })
+    }
+}
+
+/// Total of the quantities of each physical quantity
+///
+/// Each total is in the base unit, see [`Quantity::to_base`]. Quantities
+/// without a unit, with an unknown unit or with a text value are skipped.
+/// Temperatures are skipped too, because adding them makes no sense.
+pub fn quantity_totals<'a>(
+    quantities: impl IntoIterator<Item = &'a ScaledQuantity>,
+    converter: &Converter,
+) -> HashMap<PhysicalQuantity, f64> {
+    let mut totals = HashMap::new();
+    for quantity in quantities {
+        let Some(UnitInfo::Known(unit)) = quantity.unit().map(|u| u.unit_info_or_parse(converter))
+        else {
+            continue;
+        };
+        if unit.physical_quantity == PhysicalQuantity::Temperature {
+            continue;
+        }
+        let Some(base) = quantity.to_base(converter) else {
+            continue;
+        };
+        *totals.entry(unit.physical_quantity).or_insert(0.0) += base;
+    }
+    totals
+}
+
+/// Ingredient names present in both `a` and `b`
+///
+/// Names are compared normalized: trimmed, lowercase and with whitespace
+/// collapsed. The normalized names are returned in the order of `a`, without
+/// duplicates.
+///
+/// ```
+/// # use cooklang::ingredient_list::shared_ingredients;
+/// let shared = shared_ingredients(["Olive oil", "garlic", "pasta"], ["rice", "olive  oil", "Garlic"]);
+/// assert_eq!(shared, ["olive oil", "garlic"]);
+/// ```
+pub fn shared_ingredients<A, B>(
+    a: impl IntoIterator<Item = A>,
+    b: impl IntoIterator<Item = B>,
+) -> Vec<String>
+where
+    A: AsRef<str>,
+    B: AsRef<str>,
+{
+    let b = b
+        .into_iter()
+        .map(|name| normalize_name(name.as_ref()))
+        .collect::<HashSet<_>>();
+    let mut shared = Vec::new();
+    for name in a {
+        let name = normalize_name(name.as_ref());
+        if b.contains(&name) && !shared.contains(&name) {
+            shared.push(name);
+        }
+    }
+    shared
+}
+
+/// Combines many ingredient lists into one
+///
+/// With [`AggregateMode::Sum`], the quantities of each ingredient are added,
+/// like adding the recipes to the same [`IngredientList`]. With
+/// [`AggregateMode::Max`], only the largest quantity of each ingredient is
+/// kept, converting between units of the same physical quantity to compare
+/// them. Quantities that can't be compared, like text, are kept apart like
+/// when adding them.
+///
+/// ```
+/// # use cooklang::{Converter, ingredient_list::{combine_ingredients, IngredientList}};
+/// # use cooklang::quantity::AggregateMode;
+/// let converter = Converter::bundled();
+/// let list = |input: &str| {
+///     let recipe = cooklang::parse(input).into_output().unwrap().default_scale();
+///     IngredientList::from_recipe(&recipe, &converter)
+/// };
+/// let lists = [list("Add @butter{50%g}."), list("Add @butter{0.25%kg}.")];
+///
+/// let sum = combine_ingredients(lists.clone(), AggregateMode::Sum, &converter);
+/// assert_eq!(sum.iter().next().unwrap().1.to_string(), "300 g");
+/// let max = combine_ingredients(lists, AggregateMode::Max, &converter);
+/// assert_eq!(max.iter().next().unwrap().1.to_string(), "250 g");
+/// ```
+pub fn combine_ingredients(
+    lists: impl IntoIterator<Item = IngredientList>,
+    mode: AggregateMode,
+    converter: &Converter,
+) -> IngredientList {
+    let mut combined = IngredientList::new();
+    for list in lists {
+        for (name, quantity) in list.into_iter_in_order() {
+            combined.add_ingredient_with_mode(name, &quantity, mode, converter);
+        }
+    }
+    combined
+}
+
+/// Shopping list of a meal plan
+///
+/// Created from [`shopping_list_for_plan`].
+#[derive(Debug, Default)]
+pub struct PlanShoppingList {
+    /// Ingredients of all the recipes that were parsed, by category
+    pub list: CategorizedIngredientList,
+    /// Recipes of the plan that were left out because they have errors, with
+    /// their index in the plan and the report
+    pub failed: Vec<(usize, SourceReport)>,
+}
+
+/// Builds the shopping list for a meal plan
+///
+/// Each entry of `plan` is the text of a recipe and the servings to make.
+/// Every recipe is parsed, scaled to its servings and added to one
+/// [`IngredientList`], where the quantities of the same ingredient are added
+/// and fit to the best unit. Then, the list is split in categories with the
+/// aisle configuration.
+///
+/// A recipe without servings in the metadata is scaled as if it was written
+/// for 1, like with [`ScalableRecipe::scale`](crate::ScalableRecipe::scale).
+/// A recipe with errors is left out of the list and its report is in
+/// [`PlanShoppingList::failed`], so one broken recipe doesn't break the whole
+/// plan. Warnings are ignored.
+///
+/// ```
+/// # use cooklang::{CooklangParser, ingredient_list::shopping_list_for_plan};
+/// let parser = CooklangParser::default();
+/// let aisle = cooklang::aisle::parse("[baking]\nflour\n").unwrap();
+/// let plan = [
+///     (">> servings: 2\nMix @flour{500*%g} and @water{300*%ml}.", 4),
+///     ("Knead @flour{200%g}.", 1),
+///     ("Add the @&sauce{}.", 1),
+/// ];
+/// let shopping = shopping_list_for_plan(&parser, &plan, &aisle);
+/// let flour = shopping.list.categories["baking"].iter().next().unwrap();
+/// assert_eq!(flour.1.to_string(), "1.2 kg");
+/// assert_eq!(shopping.failed.len(), 1);
+/// assert_eq!(shopping.failed[0].0, 2);
+/// ```
+pub fn shopping_list_for_plan(
+    parser: &CooklangParser,
+    plan: &[(&str, u32)],
+    aisle: &AisleConf,
+) -> PlanShoppingList {
+    let converter = parser.converter();
+    let mut list = IngredientList::new();
+    let mut failed = Vec::new();
+    for (index, &(input, servings)) in plan.iter().enumerate() {
+        match parser.parse(input).into_result() {
+            Ok((recipe, _)) => {
+                let recipe = recipe.scale(servings, converter);
+                list.add_recipe(&recipe, converter);
+            }
+            Err(report) => failed.push((index, report)),
+        }
+    }
+    for quantity in list.ingredients.values_mut() {
+        let _ = quantity.fit(converter);
+    }
+    PlanShoppingList {
+        list: list.categorize(aisle),
+        failed,
+    }
+}
+
+fn normalize_name(name: &str) -> String {
+    name.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
 }
 
 impl IntoIterator for IngredientList {
@@ -234,7 +916,7 @@ impl IntoIterator for IngredientList {
 
     /// Iterate over all ingrediends sorted by name
     fn into_iter(self) -> Self::IntoIter {
-        self.0.into_iter()
+        self.ingredients.into_iter()
     }
 }
 
@@ -0,0 +1,166 @@
+//! Detect the language a recipe is written in
+//!
+//! The preferred source is the `language` (or `lang`) metadata key. If it's
+//! not present, [`guess_language`] looks for very common words in the text. It
+//! only knows a few languages and gives up when the text is not clear enough.
+
+use crate::{
+    model::{Content, Item},
+    quantity::QuantityValue,
+    Recipe,
+};
+
+/// Common words that are very rare in other languages, by ISO 639-1 code
+const STOPWORDS: &[(&str, &[&str])] = &[
+    (
+        "en",
+        &[
+            "the", "and", "with", "into", "until", "then", "of", "for", "from", "over",
+        ],
+    ),
+    (
+        "es",
+        &[
+            "el", "los", "las", "y", "hasta", "del", "por", "al", "luego",
+        ],
+    ),
+    (
+        "fr",
+        &[
+            "le", "les", "et", "avec", "dans", "jusqu", "du", "des", "puis", "au",
+        ],
+    ),
+    (
+        "de",
+        &[
+            "und", "mit", "der", "die", "das", "den", "bis", "ein", "eine", "auf", "zu", "dem",
+        ],
+    ),
+    (
+        "it",
+        &[
+            "il", "gli", "nel", "della", "fino", "di", "per", "poi", "nella", "alla",
+        ],
+    ),
+    (
+        "pt",
+        &[
+            "o", "os", "com", "até", "em", "do", "da", "depois", "no", "na",
+        ],
+    ),
+    ("nl", &["het", "en", "met", "tot", "een", "van", "je", "op"]),
+];
+
+/// Minimum number of common words found to make a guess
+const MIN_HITS: usize = 3;
+
+/// Guess the language of some text
+///
+/// Returns the ISO 639-1 code of the language. This is a conservative
+/// heuristic, so [`None`] is returned if there is not enough text or the top
+/// language is not at least twice as likely as the next one.
+///
+/// ```
+/// # use cooklang::language::guess_language;
+/// assert_eq!(
+///     guess_language(["Mix the flour with the water until smooth."]),
+///     Some("en")
+/// );
+/// assert_eq!(guess_language(["Mix."]), None);
+/// ```
+pub fn guess_language<'a>(text: impl IntoIterator<Item = &'a str>) -> Option<&'static str> {
+    let mut hits = [0usize; STOPWORDS.len()];
+    for fragment in text {
+        for word in fragment
+            .split(|c: char| !c.is_alphabetic())
+            .filter(|w| !w.is_empty())
+        {
+            let word = word.to_lowercase();
+            for (i, (_, words)) in STOPWORDS.iter().enumerate() {
+                if words.contains(&word.as_str()) {
+                    hits[i] += 1;
+                }
+            }
+        }
+    }
+
+    let mut ranked = hits.iter().copied().enumerate().collect::<Vec<_>>();
+    ranked.sort_unstable_by_key(|r| std::cmp::Reverse(r.1));
+    let (best, best_hits) = ranked[0];
+    let second_hits = ranked.get(1).map(|r| r.1).unwrap_or(0);
+    if best_hits < MIN_HITS || best_hits < second_hits * 2 {
+        return None;
+    }
+    Some(STOPWORDS[best].0)
+}
+
+impl<D, V: QuantityValue> Recipe<D, V> {
+    /// Detect the language of the recipe
+    ///
+    /// Uses the [`language`](crate::Metadata::language) metadata key if
+    /// present. Otherwise, it tries to [guess](guess_language) it from the
+    /// text of the steps.
+    pub fn detect_language(&self) -> Option<String> {
+        if let Some(lang) = self.metadata.language() {
+            return Some(lang.to_string());
+        }
+
+        let text =
+            self.sections
+                .iter()
+                .flat_map(|s| &s.content)
+                .flat_map(|content| match content {
+                    Content::Step(step) => step
+                        .items
+                        .iter()
+                        .filter_map(|item| match item {
+                            Item::Text { value } => Some(value.as_str()),
+                            _ => None,
+                        })
+                        .collect::<Vec<_>>(),
+                    Content::Text(text) => vec![text.as_str()],
+                });
+        guess_language(text).map(String::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn guess() {
+        assert_eq!(
+            guess_language([
+                "Mezclar la harina con el agua y dejar reposar hasta el día siguiente."
+            ]),
+            Some("es")
+        );
+        assert_eq!(
+            guess_language(["Mélanger la farine et le sucre dans un bol, puis ajouter les œufs."]),
+            Some("fr")
+        );
+        assert_eq!(
+            guess_language([
+                "Die Butter mit dem Zucker schaumig schlagen und bis zum Rand füllen."
+            ]),
+            Some("de")
+        );
+        // not enough text
+        assert_eq!(guess_language(["Bake."]), None);
+        assert_eq!(guess_language([]), None);
+    }
+
+    #[test]
+    fn metadata_first() {
+        let recipe = crate::parse(">> lang: it\nMix the flour with the water until smooth.")
+            .into_output()
+            .unwrap();
+        assert_eq!(recipe.detect_language().as_deref(), Some("it"));
+
+        let recipe = crate::parse("Mix the flour with the water until smooth.")
+            .into_output()
+            .unwrap();
+        assert_eq!(recipe.detect_language().as_deref(), Some("en"));
+    }
+}
@@ -1,11 +1,14 @@
 use std::str::Chars;
 
+use crate::parser::ComponentMarkers;
+
 /// Peekable iterator from a &str
 ///
 /// This was adapted from <https://github.com/rust-lang/rust/blob/2d429f3064cb67710fe64dee293329089871d92b/compiler/rustc_lexer/src/cursor.rs>
 pub struct Cursor<'a> {
     len_remaining: usize,
     chars: Chars<'a>,
+    pub(crate) markers: ComponentMarkers,
     #[cfg(debug_assertions)]
     prev: char,
 }
@@ -14,9 +17,14 @@ pub(crate) const EOF_CHAR: char = '\0';
 
 impl<'a> Cursor<'a> {
     pub fn new(input: &'a str) -> Self {
+        Self::with_markers(input, ComponentMarkers::default())
+    }
+
+    pub fn with_markers(input: &'a str, markers: ComponentMarkers) -> Self {
         Self {
             len_remaining: input.len(),
             chars: input.chars(),
+            markers,
             #[cfg(debug_assertions)]
             prev: EOF_CHAR,
         }
@@ -132,10 +132,14 @@ impl Cursor<'_> {
             }
             c @ '0'..='9' => self.number(c),
 
+            c if c == self.markers.ingredient() => TokenKind::At,
+            c if c == self.markers.cookware() => TokenKind::Hash,
+            c if c == self.markers.timer() => TokenKind::Tilde,
+            c if Some(c) == self.markers.unit_separator() => TokenKind::Percent,
+            // standard markers that are not in use
+            '@' | '#' | '~' => TokenKind::Punctuation,
+
             ':' => TokenKind::Colon,
-            '@' => TokenKind::At,
-            '#' => TokenKind::Hash,
-            '~' => TokenKind::Tilde,
             '?' => TokenKind::Question,
             '+' => TokenKind::Plus,
             '/' => TokenKind::Slash,
@@ -186,7 +190,12 @@ impl Cursor<'_> {
 
     fn word(&mut self) -> TokenKind {
         debug_assert!(self.pos_within_token() > 0); // at least one char
-        self.eat_while(is_word_char);
+        if self.markers.is_default() {
+            self.eat_while(is_word_char);
+        } else {
+            let markers = self.markers;
+            self.eat_while(|c| is_word_char(c) && !markers.contains(c));
+        }
         TokenKind::Word
     }
 
@@ -402,6 +411,34 @@ mod tests {
         );
     }
 
+    #[test]
+    fn custom_markers() {
+        let markers = crate::parser::ComponentMarkers::new('$', '^', '!').unwrap();
+        let tokenize = |input| {
+            let mut cursor = Cursor::with_markers(input, markers);
+            std::iter::from_fn(move || {
+                let token = cursor.advance_token();
+                (token.kind != TokenKind::Eof).then_some(token.kind)
+            })
+            .collect::<Vec<_>>()
+        };
+        assert_eq!(tokenize("$basic"), vec![At, Word]);
+        assert_eq!(tokenize("a^b!c"), vec![Word, Hash, Word, Tilde, Word]);
+        assert_eq!(tokenize("@#~"), vec![Punctuation, Punctuation, Punctuation]);
+        assert_eq!(tokenize("\\$"), vec![Escaped]);
+
+        let markers = crate::parser::ComponentMarkers::default()
+            .with_unit_separator('^')
+            .unwrap();
+        let mut cursor = Cursor::with_markers("1^g%", markers);
+        let kinds = std::iter::from_fn(move || {
+            let token = cursor.advance_token();
+            (token.kind != TokenKind::Eof).then_some(token.kind)
+        })
+        .collect::<Vec<_>>();
+        assert_eq!(kinds, vec![Int, Percent, Word, Percent]);
+    }
+
     #[test]
     fn test_component() {
         t!("@basic", vec![At, Word]);
@@ -24,7 +24,7 @@
 //! # use cooklang::{CooklangParser, Converter, Extensions};
 //! // Create a parser
 //! // (this is the default configuration)
-//! let parser = CooklangParser::new(Extensions::all(), Converter::default());
+//! let parser = CooklangParser::new(Extensions::default(), Converter::default());
 //! # assert_eq!(parser, CooklangParser::default());
 //! ```
 //!
@@ -56,7 +56,7 @@ pub mod _extensions {
 
 #[cfg(doc)]
 pub mod _features {
-    //! This lib has 2 features, both enabled by default:
+    //! This lib has 4 features, the first 2 enabled by default:
     //! - `bundled_units`. Includes a units file with the most common units for
     //!   recipes in English. These units are available to load when you want
     //!   without the need to read a file. The default
@@ -64,15 +64,26 @@ pub mod _features {
     //!   enabled. [This is the bundled file](https://github.com/cooklang/cooklang-rs/blob/main/units.toml)
     //!
     //! - `aisle`. Enables the [`aisle`](crate::aisle) module.
+    //!
+    //! - `actions`. Enables the [`actions`](crate::actions) module, to extract
+    //!   the cooking verbs of the steps.
+    //!
+    //! - `metadata_schema`. Enables
+    //!   [`ParseOptions::metadata_schema`](crate::ParseOptions) to validate
+    //!   the metadata with a JSON Schema.
 }
 
+#[cfg(feature = "actions")]
+pub mod actions;
 #[cfg(feature = "aisle")]
 pub mod aisle;
 pub mod analysis;
 pub mod ast;
 pub mod convert;
 pub mod error;
+pub mod heat;
 pub mod ingredient_list;
+pub mod language;
 pub mod located;
 pub mod metadata;
 pub mod model;
@@ -81,6 +92,7 @@ pub mod quantity;
 pub mod scale;
 pub mod span;
 pub mod text;
+pub mod timers;
 
 mod lexer;
 
@@ -108,7 +120,8 @@ bitflags! {
     /// This allows to enable or disable the extensions. See [extensions](_extensions)
     /// for a detailed explanation of all of them.
     ///
-    /// [`Extensions::default`] enables all extensions.
+    /// [`Extensions::default`] enables all extensions except a few that
+    /// are opt-in.
     #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
     pub struct Extensions: u32 {
         /// Steps separation is a blank line, not a line break. This may break
@@ -140,12 +153,42 @@ bitflags! {
         const INTERMEDIATE_PREPARATIONS = 1 << 11 | Self::COMPONENT_MODIFIERS.bits();
         /// Enables special metadata key parsing
         const SPECIAL_METADATA = 1 << 12;
+        /// Prep order hints for ingredients with `@igr{}[#2]`
+        const PREP_ORDER = 1 << 13;
+        /// Numbers with thousands separators like `@flour{1,000%g}` or
+        /// `@flour{1 000%g}`. The decimal separator is a point, use
+        /// [`Self::DECIMAL_COMMA`] to change it.
+        const GROUP_SEPARATORS = 1 << 14;
+        /// Count multipliers with an `x` before the number, like `@egg{x3}`
+        const MULTIPLIERS = 1 << 15;
+        /// Notes in steps after a `>`, like `Knead. > Wet your hands first`
+        const STEP_NOTES = 1 << 16;
+        /// Ingredient quantities with the same amount as another ingredient,
+        /// like `@water{=flour}`
+        const RELATIVE_QUANTITIES = 1 << 17;
+        /// How much a section makes, like `== Meatballs {12%meatballs} ==`
+        const SECTION_YIELDS = 1 << 18;
+        /// Approximate quantities with a `~` before the value, like
+        /// `@flour{~200%g}`
+        const APPROXIMATE_QUANTITIES = 1 << 19;
+        /// Units that are never converted with a `!` at the end, like
+        /// `@milk{250%ml!}`
+        const LOCKED_UNITS = 1 << 20;
+        /// Numbers with a decimal comma and points to separate groups, like
+        /// `@milk{1,5%l}` or `@flour{1.000%g}`.
+        ///
+        /// This extensions also enables [`Self::GROUP_SEPARATORS`].
+        const DECIMAL_COMMA = 1 << 21 | Self::GROUP_SEPARATORS.bits();
 
         /// Enables a subset of extensions to maximize compatibility with other
         /// cooklang parsers.
         ///
         /// Currently it enables all the extensions except
-        /// [`Self::MULTILINE_STEPS`] and [`Self::TIMER_REQUIRES_TIME`].
+        /// [`Self::MULTILINE_STEPS`], [`Self::TIMER_REQUIRES_TIME`],
+        /// [`Self::PREP_ORDER`], [`Self::GROUP_SEPARATORS`], [`Self::MULTIPLIERS`],
+        /// [`Self::STEP_NOTES`], [`Self::RELATIVE_QUANTITIES`],
+        /// [`Self::SECTION_YIELDS`], [`Self::APPROXIMATE_QUANTITIES`],
+        /// [`Self::LOCKED_UNITS`] and [`Self::DECIMAL_COMMA`].
         ///
         /// **ADDITIONS TO THE EXTENSIONS THIS ENABLES WILL NOT BE CONSIDERED A BREAKING CHANGE**
         const COMPAT = Self::COMPONENT_MODIFIERS.bits()
@@ -163,9 +206,27 @@ bitflags! {
 }
 
 impl Default for Extensions {
-    /// Enables all extensions
+    /// Enables all extensions except [`Self::PREP_ORDER`],
+    /// [`Self::GROUP_SEPARATORS`], [`Self::DECIMAL_COMMA`],
+    /// [`Self::MULTIPLIERS`], [`Self::STEP_NOTES`],
+    /// [`Self::RELATIVE_QUANTITIES`], [`Self::SECTION_YIELDS`],
+    /// [`Self::APPROXIMATE_QUANTITIES`] and [`Self::LOCKED_UNITS`]
+    ///
+    /// Each of the ones left out gives a meaning to text that is valid
+    /// without it, like the `[#2]` after an ingredient or the `!` after a
+    /// unit, so they have to be enabled explicitly.
     fn default() -> Self {
-        Self::all()
+        Self::all().difference(
+            Self::PREP_ORDER
+                | Self::GROUP_SEPARATORS
+                | Self::DECIMAL_COMMA
+                | Self::MULTIPLIERS
+                | Self::STEP_NOTES
+                | Self::RELATIVE_QUANTITIES
+                | Self::SECTION_YIELDS
+                | Self::APPROXIMATE_QUANTITIES
+                | Self::LOCKED_UNITS,
+        )
     }
 }
 
@@ -174,7 +235,7 @@ impl Default for Extensions {
 /// Instantiating this takes time and the first parse may take longer. So
 /// you may want to create only one and reuse it.
 ///
-/// The default parser enables all extensions.
+/// The default parser enables the [default](Extensions::default) extensions.
 ///
 /// The 2 main methods are [`CooklangParser::parse`] and [`CooklangParser::parse_metadata`].
 ///
@@ -183,6 +244,7 @@ impl Default for Extensions {
 pub struct CooklangParser {
     extensions: Extensions,
     converter: Converter,
+    markers: parser::ComponentMarkers,
 }
 
 pub type RecipeResult = PassResult<ScalableRecipe>;
@@ -196,15 +258,17 @@ impl CooklangParser {
         Self {
             extensions,
             converter,
+            markers: Default::default(),
         }
     }
 
     /// Creates a new extended parser
     ///
-    /// This enables all extensions and uses the bunlded units.
+    /// This enables the [default](Extensions::default) extensions and uses
+    /// the bunlded units.
     /// It is encouraged to reuse the parser and not rebuild it every time.
     pub fn extended() -> Self {
-        Self::new(Extensions::all(), Converter::bundled())
+        Self::new(Extensions::default(), Converter::bundled())
     }
 
     /// Creates a new canonical parser
@@ -216,6 +280,14 @@ impl CooklangParser {
         Self::new(Extensions::empty(), Converter::empty())
     }
 
+    /// Use other characters to mark the components
+    ///
+    /// See [`parser::ComponentMarkers`].
+    pub fn with_markers(mut self, markers: parser::ComponentMarkers) -> Self {
+        self.markers = markers;
+        self
+    }
+
     /// Get the parser inner converter
     pub fn converter(&self) -> &Converter {
         &self.converter
@@ -234,7 +306,7 @@ impl CooklangParser {
     /// Same as [`Self::parse`] but with aditional options
     #[tracing::instrument(level = "debug", name = "parse", skip_all, fields(len = input.len()))]
     pub fn parse_with_options(&self, input: &str, options: ParseOptions) -> RecipeResult {
-        let mut parser = parser::PullParser::new(input, self.extensions);
+        let mut parser = parser::PullParser::with_markers(input, self.extensions, self.markers);
         analysis::parse_events(
             &mut parser,
             input,
@@ -258,7 +330,7 @@ impl CooklangParser {
         input: &str,
         options: ParseOptions,
     ) -> MetadataResult {
-        let parser = parser::PullParser::new(input, self.extensions);
+        let parser = parser::PullParser::with_markers(input, self.extensions, self.markers);
         let meta_events = parser.into_meta_iter();
         analysis::parse_events(
             meta_events,
@@ -273,7 +345,7 @@ impl CooklangParser {
 
 /// Parse a recipe with a default [`CooklangParser`]. Avoid calling this in a loop.
 ///
-/// The default parser enables all extensions.
+/// The default parser enables the [default](Extensions::default) extensions.
 ///
 /// **IMPORTANT:** If you are going to parse more than one recipe you may want
 /// to only create one [`CooklangParser`] and reuse it. Every time this function
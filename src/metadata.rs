@@ -22,6 +22,7 @@ pub(crate) use regex;
 
 use crate::{
     convert::{ConvertError, ConvertTo, ConvertUnit, ConvertValue, PhysicalQuantity, UnknownUnit},
+    quantity::Value,
     Converter,
 };
 
@@ -39,6 +40,9 @@ pub struct Metadata {
     special: HashMap<SpecialKey, SpecialValue>,
     /// All the raw key/value pairs from the recipe
     pub map: IndexMap<String, String>,
+    /// Config keys like `[mode]` that are not in the map
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub(crate) config: IndexMap<String, String>,
 }
 
 #[derive(
@@ -60,6 +64,8 @@ pub(crate) enum SpecialKey {
     Description,
     #[strum(serialize = "tag", to_string = "tags")]
     Tags,
+    #[strum(serialize = "image", to_string = "images")]
+    Images,
     Emoji,
     Author,
     Source,
@@ -69,16 +75,20 @@ pub(crate) enum SpecialKey {
     #[strum(serialize = "cook_time", to_string = "cook time")]
     CookTime,
     Servings,
+    #[strum(serialize = "default_scale", to_string = "default scale")]
+    DefaultScale,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(untagged)]
 enum SpecialValue {
-    Tags(Vec<String>),
+    List(Vec<String>),
     NameAndUrl(NameAndUrl),
     Time(RecipeTime),
     Servings(Vec<u32>),
+    ServingsRange(ServingsRange),
     String(String),
+    Number(f64),
 }
 
 macro_rules! unwrap_value {
@@ -96,6 +106,49 @@ macro_rules! unwrap_value {
 }
 
 impl Metadata {
+    /// All the keys written in the recipe, with their original case
+    ///
+    /// These are the keys of [`map`](Self::map) and, with the
+    /// [`MODES`](crate::Extensions::MODES) extension, the config keys like
+    /// `[mode]`, which are not stored in the map. Config keys go first and
+    /// then the rest in the order they are written.
+    ///
+    /// ```
+    /// let recipe = cooklang::parse(">> Title: Soup\n>> [mode]: default\n>> servings: 2\nBoil @water.")
+    ///     .into_output()
+    ///     .unwrap();
+    /// assert_eq!(recipe.metadata.keys(), ["[mode]", "Title", "servings"]);
+    /// ```
+    pub fn keys(&self) -> Vec<&str> {
+        self.config
+            .keys()
+            .chain(self.map.keys())
+            .map(|k| k.as_str())
+            .collect()
+    }
+
+    /// Config keys like `[mode]` with their values, as written
+    ///
+    /// With the [`MODES`](crate::Extensions::MODES) extension, these keys
+    /// change how the recipe is parsed and are not stored in
+    /// [`map`](Self::map). This is for tools that want to show or write them
+    /// back, like editors. Keys keep their brackets and are in the order they
+    /// are written. Unknown config keys are not here, they are a warning and
+    /// go to the map.
+    ///
+    /// ```
+    /// let recipe = cooklang::parse(">> [mode]: components\n>> [auto scale]: true\n>> servings: 2\n@water{1%l}")
+    ///     .into_output()
+    ///     .unwrap();
+    /// let directives = recipe.metadata.config_directives();
+    /// assert_eq!(directives["[mode]"], "components");
+    /// assert_eq!(directives["[auto scale]"], "true");
+    /// assert!(!recipe.metadata.map.contains_key("[mode]"));
+    /// ```
+    pub fn config_directives(&self) -> &IndexMap<String, String> {
+        &self.config
+    }
+
     /// Description of the recipe
     pub fn description(&self) -> Option<&str> {
         self.map
@@ -103,6 +156,18 @@ impl Metadata {
             .map(|s| s.as_str())
     }
 
+    /// Language of the recipe
+    ///
+    /// From the `language` or `lang` keys. To also guess it from the steps
+    /// see [`Recipe::detect_language`](crate::Recipe::detect_language).
+    pub fn language(&self) -> Option<&str> {
+        ["language", "lang"]
+            .iter()
+            .filter_map(|key| self.map.get(*key))
+            .map(|s| s.trim())
+            .find(|s| !s.is_empty())
+    }
+
     /// Emoji for the recipe
     pub fn emoji(&self) -> Option<&str> {
         self.special
@@ -114,7 +179,23 @@ impl Metadata {
     pub fn tags(&self) -> Option<&[String]> {
         self.special
             .get(&SpecialKey::Tags)
-            .map(|v| unwrap_value!(Tags, v).as_slice())
+            .map(|v| unwrap_value!(List, v).as_slice())
+    }
+
+    /// List of image paths or URLs
+    ///
+    /// The first one is the primary image of the recipe.
+    pub fn images(&self) -> Option<&[String]> {
+        self.special
+            .get(&SpecialKey::Images)
+            .map(|v| unwrap_value!(List, v).as_slice())
+    }
+
+    /// Primary image of the recipe, the first of [`Self::images`]
+    ///
+    /// This is the one to show in a list of recipes.
+    pub fn thumbnail(&self) -> Option<&str> {
+        self.images()?.first().map(String::as_str)
     }
 
     /// Author
@@ -143,10 +224,155 @@ impl Metadata {
     }
 
     /// Servings the recipe is made for
+    ///
+    /// This is [`None`] if the servings are a range, see
+    /// [`servings_range`](Self::servings_range).
     pub fn servings(&self) -> Option<&[u32]> {
+        match self.special.get(&SpecialKey::Servings)? {
+            SpecialValue::ServingsRange(_) => None,
+            v => Some(unwrap_value!(Servings, v).as_slice()),
+        }
+    }
+
+    /// Range of servings the recipe is made for, like `servings: 4-6`
+    pub fn servings_range(&self) -> Option<ServingsRange> {
+        match self.special.get(&SpecialKey::Servings)? {
+            SpecialValue::ServingsRange(range) => Some(*range),
+            _ => None,
+        }
+    }
+
+    /// Factor the recipe is usually scaled by, like `default scale: 2`
+    ///
+    /// The value is a positive number. See
+    /// [`ScalableRecipe::apply_default_scale`](crate::ScalableRecipe::apply_default_scale)
+    /// to scale the recipe with it.
+    pub fn default_scale(&self) -> Option<f64> {
         self.special
-            .get(&SpecialKey::Servings)
-            .map(|v| unwrap_value!(Servings, v).as_slice())
+            .get(&SpecialKey::DefaultScale)
+            .map(|v| *unwrap_value!(Number, v))
+    }
+
+    /// Density of an ingredient, from a `density.<ingredient>` key
+    ///
+    /// The value is a mass over a volume, like `0.53 g/ml` or `120 g/cup`. A
+    /// number alone is in `g/ml`. The ingredient name is matched ignoring
+    /// case.
+    ///
+    /// The density is returned in the base units of the converter, so a
+    /// volume converted with [`to_base`](crate::quantity::Quantity::to_base)
+    /// times the density is the mass in the base unit. With the bundled units
+    /// that's grams per litre. Returns [`None`] if the key is missing or the
+    /// value is not valid.
+    ///
+    /// ```
+    /// # use cooklang::Converter;
+    /// let recipe = cooklang::parse(">> density.flour: 0.53 g/ml\nMix @flour{1%cup}.")
+    ///     .into_output()
+    ///     .unwrap();
+    /// let converter = Converter::bundled();
+    /// assert_eq!(recipe.metadata.density("Flour", &converter), Some(530.0));
+    /// assert_eq!(recipe.metadata.density("sugar", &converter), None);
+    /// ```
+    pub fn density(&self, ingredient: &str, converter: &Converter) -> Option<f64> {
+        let ingredient = unicase::UniCase::new(ingredient);
+        self.map
+            .iter()
+            .find(|(key, _)| {
+                key.strip_prefix(DENSITY_PREFIX)
+                    .is_some_and(|name| unicase::UniCase::new(name.trim()) == ingredient)
+            })
+            .and_then(|(_, value)| parse_density(value, converter).ok())
+    }
+}
+
+/// Prefix of the metadata keys with the density of an ingredient
+pub(crate) const DENSITY_PREFIX: &str = "density.";
+
+/// Parses a density like `0.53 g/ml` into base mass units per base volume
+/// units
+pub(crate) fn parse_density(value: &str, converter: &Converter) -> Result<f64, DensityError> {
+    let value = value.trim();
+    let (amount, volume) = value.split_once('/').unwrap_or((value, "ml"));
+    let split = amount
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(amount.len());
+    let (number, mass) = amount.split_at(split);
+    let number = number.parse::<f64>()?;
+    let mass = match mass.trim() {
+        "" => "g",
+        unit => unit,
+    };
+
+    let unit = |name: &str, physical_quantity: PhysicalQuantity| {
+        let unit = converter
+            .find_unit(name)
+            .ok_or_else(|| DensityError::UnknownUnit(name.to_string()))?;
+        if unit.physical_quantity != physical_quantity {
+            return Err(DensityError::WrongUnit {
+                unit: name.to_string(),
+                expected: physical_quantity,
+            });
+        }
+        Ok(unit.ratio)
+    };
+    let mass = unit(mass, PhysicalQuantity::Mass)?;
+    let volume = unit(volume.trim(), PhysicalQuantity::Volume)?;
+    Ok(number * mass / volume)
+}
+
+#[derive(Debug, Error)]
+pub(crate) enum DensityError {
+    #[error(transparent)]
+    ParseFloatError(#[from] ParseFloatError),
+    #[error("Unknown unit: {0}")]
+    UnknownUnit(String),
+    #[error("Unit '{unit}' is not a {expected}")]
+    WrongUnit {
+        unit: String,
+        expected: PhysicalQuantity,
+    },
+}
+
+/// Range of servings, like `4-6`
+///
+/// `start` is always less than `end`.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+#[serde(deny_unknown_fields)]
+pub struct ServingsRange {
+    pub start: u32,
+    pub end: u32,
+}
+
+/// Which point of a [`ServingsRange`] to use when a single value is needed,
+/// like when scaling
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum ServingsPoint {
+    /// The start of the range
+    #[default]
+    Min,
+    /// The end of the range
+    Max,
+    /// The middle of the range, rounded down
+    Mid,
+}
+
+impl ServingsRange {
+    /// Get a single value from the range
+    ///
+    /// ```
+    /// # use cooklang::metadata::{ServingsPoint, ServingsRange};
+    /// let range = ServingsRange { start: 4, end: 7 };
+    /// assert_eq!(range.point(ServingsPoint::Min), 4);
+    /// assert_eq!(range.point(ServingsPoint::Max), 7);
+    /// assert_eq!(range.point(ServingsPoint::Mid), 5);
+    /// ```
+    pub fn point(&self, point: ServingsPoint) -> u32 {
+        match point {
+            ServingsPoint::Min => self.start,
+            ServingsPoint::Max => self.end,
+            ServingsPoint::Mid => self.start + (self.end - self.start) / 2,
+        }
     }
 }
 
@@ -193,7 +419,7 @@ impl Metadata {
             SpecialKey::Tags => {
                 // take current
                 let mut tags = if let Some(entry) = self.special.remove(&key) {
-                    unwrap_value!(Tags, entry)
+                    unwrap_value!(List, entry)
                 } else {
                     Vec::new()
                 };
@@ -211,7 +437,26 @@ impl Metadata {
                     tags.push(tag);
                 }
                 // add to the map
-                self.special.insert(key, SpecialValue::Tags(tags));
+                self.special.insert(key, SpecialValue::List(tags));
+            }
+            SpecialKey::Images => {
+                let new_images = value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|image| !image.is_empty());
+                if let Some(path) = new_images.clone().find(|p| escapes_recipe_dir(p)) {
+                    return Err(MetadataError::ImageOutsideRecipeDir {
+                        path: path.to_string(),
+                    });
+                }
+                // take current
+                let mut images = if let Some(entry) = self.special.remove(&key) {
+                    unwrap_value!(List, entry)
+                } else {
+                    Vec::new()
+                };
+                images.extend(new_images.map(String::from));
+                self.special.insert(key, SpecialValue::List(images));
             }
             SpecialKey::Emoji => {
                 let emoji = if value.starts_with(':') && value.ends_with(':') {
@@ -259,6 +504,18 @@ impl Metadata {
                     .insert(SpecialKey::Time, SpecialValue::Time(time));
             }
             SpecialKey::Servings => {
+                if let Some((start, end)) = value.split_once('-') {
+                    let start = start.trim().parse()?;
+                    let end = end.trim().parse()?;
+                    if start >= end {
+                        return Err(MetadataError::InvalidServingsRange { start, end });
+                    }
+                    self.special.insert(
+                        SpecialKey::Servings,
+                        SpecialValue::ServingsRange(ServingsRange { start, end }),
+                    );
+                    return Ok(());
+                }
                 let servings = value
                     .split('|')
                     .map(str::trim)
@@ -277,6 +534,14 @@ impl Metadata {
                 self.special
                     .insert(SpecialKey::Servings, SpecialValue::Servings(servings));
             }
+            SpecialKey::DefaultScale => {
+                let scale = value
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|s| s.is_finite() && *s > 0.0)
+                    .ok_or(MetadataError::InvalidScale { value })?;
+                self.special.insert(key, SpecialValue::Number(scale));
+            }
         }
         Ok(())
     }
@@ -317,6 +582,29 @@ impl Metadata {
     }
 }
 
+/// Checks if a relative image path goes above the directory of the recipe
+///
+/// URLs and absolute paths are not checked.
+fn escapes_recipe_dir(path: &str) -> bool {
+    if Url::parse(path).is_ok() || path.starts_with('/') {
+        return false;
+    }
+    let mut depth = 0i32;
+    for component in path.split(['/', '\\']) {
+        match component {
+            "" | "." => {}
+            ".." => {
+                depth -= 1;
+                if depth < 0 {
+                    return true;
+                }
+            }
+            _ => depth += 1,
+        }
+    }
+    false
+}
+
 /// Returns minutes
 fn parse_time(s: &str, converter: &Converter) -> Result<u32, ParseTimeError> {
     if s.is_empty() {
@@ -374,6 +662,72 @@ fn dynamic_time_units(
     }
 }
 
+/// Suggests the structured form of a special key value that can't be parsed
+/// but looks like it means something valid, like `servings: four` or
+/// `time: 1h30`
+///
+/// Returns [`None`] if the value is already valid or no suggestion is known.
+pub(crate) fn structured_suggestion(
+    key: SpecialKey,
+    value: &str,
+    converter: &Converter,
+) -> Option<String> {
+    let value = value.trim();
+    match key {
+        SpecialKey::Servings => {
+            if value.split('|').all(|s| s.trim().parse::<u32>().is_ok())
+                || value.split_once('-').is_some_and(|(a, b)| {
+                    a.trim().parse::<u32>().is_ok() && b.trim().parse::<u32>().is_ok()
+                })
+            {
+                return None;
+            }
+            let count = |words: &str| match Value::from_number_word(words)? {
+                Value::Number(n) if n.value().fract() == 0.0 => Some(n.value() as u32),
+                _ => None,
+            };
+            if let Some(n) = count(value) {
+                return Some(n.to_string());
+            }
+            let numbers = value
+                .split(|c: char| !c.is_alphanumeric())
+                // an article alone is not a count, like in `a family of four`
+                .filter(|w| !w.eq_ignore_ascii_case("a") && !w.eq_ignore_ascii_case("an"))
+                .filter_map(|w| w.parse::<u32>().ok().or_else(|| count(w)))
+                .map(|n| n.to_string())
+                .collect::<Vec<_>>();
+            (!numbers.is_empty()).then(|| numbers.join("|"))
+        }
+        SpecialKey::Time | SpecialKey::PrepTime | SpecialKey::CookTime => {
+            if parse_time(value, converter).is_ok() {
+                return None;
+            }
+            let caps = regex!(r"^(\d+)\s*(?:h|hrs?|hours?)\s*(\d+)\s*(?:m|mins?|minutes?)?$")
+                .captures(value)
+                .or_else(|| regex!(r"^(\d+):(\d{2})$").captures(value))?;
+            let hours = caps[1].parse::<u32>().ok()?;
+            let minutes = caps[2].parse::<u32>().ok()?;
+            match (hours, minutes) {
+                (0, m) => Some(format!("{m} min")),
+                (h, 0) => Some(format!("{h} h")),
+                (h, m) => Some(format!("{h} h {m} min")),
+            }
+        }
+        SpecialKey::Tags => {
+            if value.contains(',') || !value.contains(['#', ';']) {
+                return None;
+            }
+            let tags = value
+                .split(['#', ';'])
+                .map(str::trim)
+                .filter(|t| !t.is_empty())
+                .collect::<Vec<_>>();
+            (!tags.is_empty()).then(|| tags.join(", "))
+        }
+        _ => None,
+    }
+}
+
 fn hard_coded_time_units(value: f64, unit: &str) -> Result<f64, ParseTimeError> {
     let minutes = match unit {
         "s" | "sec" | "secs" | "second" | "seconds" => value / 60.0,
@@ -486,8 +840,14 @@ pub(crate) enum MetadataError {
     NotEmoji { value: String },
     #[error(transparent)]
     ParseIntError(#[from] std::num::ParseIntError),
+    #[error("Image path is outside the recipe directory: {path}")]
+    ImageOutsideRecipeDir { path: String },
     #[error("Duplicate servings: {servings:?}")]
     DuplicateServings { servings: Vec<u32> },
+    #[error("Invalid servings range: {start}-{end}")]
+    InvalidServingsRange { start: u32, end: u32 },
+    #[error("Scale is not a positive number: {value}")]
+    InvalidScale { value: String },
     #[error(transparent)]
     ParseTimeError(#[from] ParseTimeError),
 }
@@ -531,6 +891,8 @@ mod tests {
         t("description", SpecialKey::Description);
         t("tags", SpecialKey::Tags);
         t_alias("tag", SpecialKey::Tags);
+        t("images", SpecialKey::Images);
+        t_alias("image", SpecialKey::Images);
         t("emoji", SpecialKey::Emoji);
         t("author", SpecialKey::Author);
         t("source", SpecialKey::Source);
@@ -658,6 +1020,9 @@ mod tests {
         let _ = insert!(m, converter, SpecialKey::Tags, "t1, t2");
         assert!(matches!(m.tags(), Some(_)));
 
+        let _ = insert!(m, converter, SpecialKey::Images, "a.jpg, b.jpg");
+        assert!(matches!(m.images(), Some(_)));
+
         let _ = insert!(m, converter, SpecialKey::Emoji, "⛄");
         assert!(matches!(m.emoji(), Some(_)));
 
@@ -683,6 +1048,27 @@ mod tests {
         assert!(matches!(m.servings(), Some(_)));
     }
 
+    #[test]
+    fn servings_range() {
+        let converter = Converter::empty();
+
+        let mut m = Metadata::default();
+        let r = insert!(m, converter, SpecialKey::Servings, "4 - 6");
+        assert!(r.is_ok());
+        assert_eq!(m.servings_range(), Some(ServingsRange { start: 4, end: 6 }));
+        assert_eq!(m.servings(), None);
+
+        let mut m = Metadata::default();
+        let r = insert!(m, converter, SpecialKey::Servings, "6-4");
+        assert!(r.is_err());
+        assert_eq!(m.servings_range(), None);
+
+        let mut m = Metadata::default();
+        let r = insert!(m, converter, SpecialKey::Servings, "2|4");
+        assert!(r.is_ok());
+        assert_eq!(m.servings_range(), None);
+    }
+
     #[test]
     fn shortcode_emoji() {
         let converter = Converter::empty();
@@ -701,4 +1087,42 @@ mod tests {
         assert!(r.is_ok());
         assert_eq!(m.emoji(), Some("🌮"));
     }
+
+    #[test]
+    fn images() {
+        let converter = Converter::empty();
+
+        let mut m = Metadata::default();
+        let r = insert!(m, converter, SpecialKey::Images, "cover.jpg");
+        assert!(r.is_ok());
+        assert_eq!(m.images(), Some(["cover.jpg".to_string()].as_slice()));
+        assert_eq!(m.thumbnail(), Some("cover.jpg"));
+
+        let mut m = Metadata::default();
+        let r = insert!(
+            m,
+            converter,
+            SpecialKey::Images,
+            "img/cover.jpg, ,https://example.com/step.png,  img/../photos/x.jpg"
+        );
+        assert!(r.is_ok());
+        assert_eq!(
+            m.images().unwrap(),
+            [
+                "img/cover.jpg",
+                "https://example.com/step.png",
+                "img/../photos/x.jpg"
+            ]
+        );
+        assert_eq!(m.thumbnail(), Some("img/cover.jpg"));
+
+        let mut m = Metadata::default();
+        let r = insert!(m, converter, SpecialKey::Images, "a/../../secret.jpg");
+        assert!(matches!(
+            r,
+            Err(MetadataError::ImageOutsideRecipeDir { .. })
+        ));
+        assert_eq!(m.images(), None);
+        assert_eq!(m.thumbnail(), None);
+    }
 }
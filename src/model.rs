@@ -1,6 +1,6 @@
 //! Recipe representation
 
-use std::borrow::Cow;
+use std::{borrow::Cow, collections::HashMap};
 
 use serde::{Deserialize, Serialize};
 
@@ -55,6 +55,343 @@ pub type ScalableRecipe = Recipe<(), ScalableValue>;
 /// scaled once.
 pub type ScaledRecipe = Recipe<crate::scale::Scaled, Value>;
 
+impl<D, V: QuantityValue> Recipe<D, V> {
+    /// Finds the intermediate preparation references that don't point to a
+    /// valid target
+    ///
+    /// A recipe from the parser is always valid, because these are errors
+    /// while parsing. This is for recipes that have been built or modified
+    /// in some other way, like after removing a section.
+    ///
+    /// A reference to a step is valid if the step is before the ingredient in
+    /// the same section. A reference to a section is valid if the section is
+    /// before the one of the ingredient.
+    pub fn dangling_intermediate_refs(&self) -> Vec<DanglingReference> {
+        let mut locations = vec![None; self.ingredients.len()];
+        for (section_index, section) in self.sections.iter().enumerate() {
+            for (content_index, content) in section.content.iter().enumerate() {
+                let Content::Step(step) = content else {
+                    continue;
+                };
+                for item in &step.items {
+                    if let Item::Ingredient { index } = item {
+                        if let Some(location) = locations.get_mut(*index) {
+                            *location = Some((section_index, content_index));
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut dangling = Vec::new();
+        for (index, igr) in self.ingredients.iter().enumerate() {
+            let Some((target_index, target)) = igr.relation.references_to() else {
+                continue;
+            };
+            let location = locations[index];
+            let valid = match (target, location) {
+                (IngredientReferenceTarget::Ingredient, _) => continue,
+                (IngredientReferenceTarget::Step, Some((section, content))) => {
+                    target_index < content && self.sections[section].content[target_index].is_step()
+                }
+                (IngredientReferenceTarget::Section, Some((section, _))) => target_index < section,
+                (_, None) => false,
+            };
+            if !valid {
+                dangling.push(DanglingReference {
+                    ingredient: index,
+                    target,
+                    target_index,
+                    location,
+                });
+            }
+        }
+        dangling
+    }
+
+    /// Finds the cookware references, like `#&pan`, without a definition
+    ///
+    /// Returns the indices in [`Recipe::cookware`]. While parsing these are
+    /// "reference not found" errors, but the recipe is still in the output
+    /// of the [`PassResult`](crate::error::PassResult), so this is a way for
+    /// tools to list them.
+    pub fn undefined_cookware_refs(&self) -> Vec<usize> {
+        self.cookware
+            .iter()
+            .enumerate()
+            .filter(|(_, cw)| cw.modifiers.is_reference() && cw.relation.is_definition())
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Compares the ingredient list with the ingredients of the steps
+    ///
+    /// For recipes that list the ingredients first, with the `[mode]:
+    /// ingredients` config, and then write the steps. The ingredients of the
+    /// list are the definitions outside of a step. This finds the listed
+    /// ingredients that no step uses, and the ingredients in the steps that
+    /// are not a reference to the list. Both are indices in
+    /// [`Recipe::ingredients`].
+    ///
+    /// Returns [`None`] if the recipe has no ingredient list, because then
+    /// every ingredient would be reported.
+    ///
+    /// ```
+    /// let input = ">> [mode]: ingredients\n@flour{200%g}\n@yeast{5%g}\n\n\
+    ///     >> [mode]: steps\nMix the @flour with @+water{}.";
+    /// let recipe = cooklang::parse(input).into_output().unwrap();
+    /// let audit = recipe.ingredient_list_audit().unwrap();
+    /// assert_eq!(recipe.ingredients[audit.unused[0]].name, "yeast");
+    /// assert_eq!(recipe.ingredients[audit.unlisted[0]].name, "water");
+    ///
+    /// let recipe = cooklang::parse("Mix @flour{200%g}.").into_output().unwrap();
+    /// assert!(recipe.ingredient_list_audit().is_none());
+    /// ```
+    pub fn ingredient_list_audit(&self) -> Option<IngredientListAudit> {
+        if !self
+            .ingredients
+            .iter()
+            .any(|igr| igr.relation.is_defined_in_step() == Some(false))
+        {
+            return None;
+        }
+
+        let unused = self
+            .ingredients
+            .iter()
+            .enumerate()
+            .filter(|(_, igr)| {
+                igr.relation.is_defined_in_step() == Some(false)
+                    && igr.relation.referenced_from().is_empty()
+            })
+            .map(|(index, _)| index)
+            .collect();
+
+        let mut unlisted = Vec::new();
+        for (_, step) in self.numbered_steps() {
+            for item in &step.items {
+                if let Item::Ingredient { index } = item {
+                    if self.ingredients[*index].relation.is_definition() {
+                        unlisted.push(*index);
+                    }
+                }
+            }
+        }
+
+        Some(IngredientListAudit { unused, unlisted })
+    }
+
+    /// Total number of steps in all the sections
+    ///
+    /// Text paragraphs are not counted, like they don't get a
+    /// [`Step::number`].
+    ///
+    /// ```
+    /// let recipe = cooklang::parse("Mix @flour.\n\n> Let it rest.\n\n= Bake\nBake it.")
+    ///     .into_output()
+    ///     .unwrap();
+    /// assert_eq!(recipe.step_count(), 2);
+    /// ```
+    pub fn step_count(&self) -> usize {
+        self.sections
+            .iter()
+            .flat_map(|s| &s.content)
+            .filter(|c| c.is_step())
+            .count()
+    }
+
+    /// The steps where each ingredient is used
+    ///
+    /// The key is the name of the ingredient, and references count for the
+    /// ingredient they point to. Steps are numbered from 1 across all the
+    /// sections, so with more than one section the numbers are not the
+    /// [`Step::number`]. Text paragraphs are not steps, like in
+    /// [`Self::step_count`].
+    ///
+    /// ```
+    /// let recipe = cooklang::parse("Mix @flour and @water.\n\n= Bake\nDust with @&flour{}.")
+    ///     .into_output()
+    ///     .unwrap();
+    /// let steps = recipe.ingredient_steps();
+    /// assert_eq!(steps["flour"], [1, 2]);
+    /// assert_eq!(steps["water"], [1]);
+    /// ```
+    pub fn ingredient_steps(&self) -> HashMap<&str, Vec<u32>> {
+        let mut steps: HashMap<&str, Vec<u32>> = HashMap::new();
+        for (number, step) in self.numbered_steps() {
+            for item in &step.items {
+                let Item::Ingredient { index } = item else {
+                    continue;
+                };
+                let mut ingredient = &self.ingredients[*index];
+                if let Some((target, IngredientReferenceTarget::Ingredient)) =
+                    ingredient.relation.references_to()
+                {
+                    ingredient = &self.ingredients[target];
+                }
+                let numbers = steps.entry(ingredient.name.as_str()).or_default();
+                if numbers.last() != Some(&number) {
+                    numbers.push(number);
+                }
+            }
+        }
+        steps
+    }
+
+    /// The steps where each cookware item is used
+    ///
+    /// Like [`Self::ingredient_steps`] but for the cookware. References count
+    /// for the item they point to.
+    ///
+    /// ```
+    /// let recipe = cooklang::parse("Blend in a #blender.\n\nBoil in a #pot.\n\n= Serve\nClean the #&blender.")
+    ///     .into_output()
+    ///     .unwrap();
+    /// let steps = recipe.cookware_steps();
+    /// assert_eq!(steps["blender"], [1, 3]);
+    /// assert_eq!(steps["pot"], [2]);
+    /// ```
+    pub fn cookware_steps(&self) -> HashMap<&str, Vec<u32>> {
+        let mut steps: HashMap<&str, Vec<u32>> = HashMap::new();
+        for (number, step) in self.numbered_steps() {
+            for item in &step.items {
+                let Item::Cookware { index } = item else {
+                    continue;
+                };
+                let mut cookware = &self.cookware[*index];
+                if let Some(target) = cookware.relation.references_to() {
+                    cookware = &self.cookware[target];
+                }
+                let numbers = steps.entry(cookware.name.as_str()).or_default();
+                if numbers.last() != Some(&number) {
+                    numbers.push(number);
+                }
+            }
+        }
+        steps
+    }
+
+    /// All the steps numbered from 1 across the sections
+    pub(crate) fn numbered_steps(&self) -> impl Iterator<Item = (u32, &Step)> {
+        let all_steps = self
+            .sections
+            .iter()
+            .flat_map(|s| &s.content)
+            .filter_map(|c| match c {
+                Content::Step(step) => Some(step),
+                Content::Text(_) => None,
+            });
+        (1..).zip(all_steps)
+    }
+
+    /// The whole method as plain text, for showing it as one block
+    ///
+    /// Each step and text paragraph is a line, and sections are separated by
+    /// a blank line. Section names and step notes are not included.
+    ///
+    /// With `components`, ingredients and cookware are written with their
+    /// display name and timers with their quantity, or their name if they
+    /// have none. Without it they are left out. Inline quantities, like
+    /// temperatures, are always written.
+    ///
+    /// ```
+    /// let recipe = cooklang::parse("Mix @flour{200%g} in a #bowl.\n\nBake for ~{20%min}.")
+    ///     .into_output()
+    ///     .unwrap();
+    /// assert_eq!(recipe.method_prose(true), "Mix flour in a bowl.\nBake for 20 min.");
+    /// assert_eq!(recipe.method_prose(false), "Mix in a.\nBake for.");
+    /// ```
+    pub fn method_prose(&self, components: bool) -> String {
+        let mut sections = Vec::new();
+        for section in &self.sections {
+            let mut lines = Vec::new();
+            for content in &section.content {
+                let line = match content {
+                    Content::Text(text) => prose(text),
+                    Content::Step(step) => {
+                        let mut text = String::new();
+                        for item in &step.items {
+                            match item {
+                                Item::Text { value } => text += value,
+                                Item::InlineQuantity { index } => {
+                                    text += &self.inline_quantities[*index].to_string()
+                                }
+                                Item::Link { text: link, .. } => text += link,
+                                _ if !components => {}
+                                Item::Ingredient { index } => {
+                                    text += &self.ingredients[*index].display_name()
+                                }
+                                Item::Cookware { index } => {
+                                    text += self.cookware[*index].display_name()
+                                }
+                                Item::Timer { index } => {
+                                    let timer = &self.timers[*index];
+                                    match (&timer.quantity, &timer.name) {
+                                        (Some(quantity), _) => text += &quantity.to_string(),
+                                        (None, Some(name)) => text += name,
+                                        (None, None) => {}
+                                    }
+                                }
+                            }
+                        }
+                        prose(&text)
+                    }
+                };
+                if !line.is_empty() {
+                    lines.push(line);
+                }
+            }
+            if !lines.is_empty() {
+                sections.push(lines.join("\n"));
+            }
+        }
+        sections.join("\n\n")
+    }
+}
+
+/// Collapses the whitespace and removes the spaces left before punctuation,
+/// like when a component at the end of a sentence is left out
+fn prose(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for word in text.split_whitespace() {
+        if !out.is_empty() && !word.starts_with(['.', ',', ';', ':', '!', '?']) {
+            out.push(' ');
+        }
+        out += word;
+    }
+    out
+}
+
+/// An intermediate preparation reference without a valid target
+///
+/// From [`Recipe::dangling_intermediate_refs`]. The recipe doesn't keep the
+/// position in the source, so the reference is located by its place in the
+/// recipe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DanglingReference {
+    /// Index in [`Recipe::ingredients`]
+    pub ingredient: usize,
+    /// What the reference points to
+    pub target: IngredientReferenceTarget,
+    /// Index of the target, see [`IngredientRelation::references_to`]
+    pub target_index: usize,
+    /// Index in [`Recipe::sections`] and [`Section::content`] of the step with
+    /// the ingredient, if it is in any
+    pub location: Option<(usize, usize)>,
+}
+
+/// Mismatches between the ingredient list and the steps
+///
+/// From [`Recipe::ingredient_list_audit`]. Both lists have indices in
+/// [`Recipe::ingredients`], in the order of the recipe.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IngredientListAudit {
+    /// Ingredients in the list that are not used in any step
+    pub unused: Vec<usize>,
+    /// Ingredients in the steps that are not in the list
+    pub unlisted: Vec<usize>,
+}
+
 /// A section holding steps
 #[derive(Debug, Default, Serialize, Deserialize, PartialEq, Clone)]
 pub struct Section {
@@ -62,6 +399,12 @@ pub struct Section {
     pub name: Option<String>,
     /// Content inside
     pub content: Vec<Content>,
+    /// How much the section makes, like 12 meatballs
+    ///
+    /// Only with the [`SECTION_YIELDS`](crate::Extensions::SECTION_YIELDS)
+    /// extension. It always scales linearly with the recipe.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub yields: Option<ScaledQuantity>,
 }
 
 impl Section {
@@ -69,14 +412,15 @@ impl Section {
         Self {
             name,
             content: Vec::new(),
+            yields: None,
         }
     }
 
     /// Check if the section is empty
     ///
-    /// A section is empty when it has no name and no content.
+    /// A section is empty when it has no name, no yield and no content.
     pub fn is_empty(&self) -> bool {
-        self.name.is_none() && self.content.is_empty()
+        self.name.is_none() && self.yields.is_none() && self.content.is_empty()
     }
 }
 
@@ -136,6 +480,14 @@ pub struct Step {
     /// The step numbers start at 1 in each section and increase with non
     /// text step.
     pub number: u32,
+
+    /// Note of the step, like a tip, that is not an instruction
+    ///
+    /// It's the text after a `>` in the step, like in `Knead the dough. > Wet
+    /// your hands so it doesn't stick`. Only with the
+    /// [`STEP_NOTES`](crate::Extensions::STEP_NOTES) extension.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
 }
 
 /// A step item
@@ -161,6 +513,14 @@ pub enum Item {
     InlineQuantity {
         index: usize,
     },
+    /// A URL, only with [`ParseOptions::detect_links`](crate::ParseOptions::detect_links)
+    Link {
+        /// The URL to open, with `https://` added if it was written without
+        /// a scheme, like `www.cooklang.org`
+        url: String,
+        /// The URL as written
+        text: String,
+    },
 }
 
 /// A recipe ingredient
@@ -178,6 +538,11 @@ pub struct Ingredient<V: QuantityValue = Value> {
     pub note: Option<String>,
     /// How the cookware is related to others
     pub relation: IngredientRelation,
+    /// Prep order hint, like `[#2]` to prep it second
+    ///
+    /// This is independent of the order of the steps.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prep_order: Option<u32>,
     pub(crate) modifiers: Modifiers,
 }
 
@@ -196,6 +561,27 @@ impl<V: QuantityValue> Ingredient<V> {
         self.alias.as_ref().map(Cow::from).unwrap_or(name)
     }
 
+    /// Name of the recipe this ingredient references, if any
+    ///
+    /// An ingredient references a recipe with the recipe modifier,
+    /// `@@pesto{}`, or with a relative path, `@./sauces/pesto{}`. The name is
+    /// the file name without the extension, `pesto`.
+    ///
+    /// ```
+    /// let recipe = cooklang::parse("Add @./sauces/pesto.cook{} to the @pasta{}.")
+    ///     .into_output()
+    ///     .unwrap();
+    /// assert_eq!(recipe.ingredients[0].referenced_recipe(), Some("pesto"));
+    /// assert_eq!(recipe.ingredients[1].referenced_recipe(), None);
+    /// ```
+    pub fn referenced_recipe(&self) -> Option<&str> {
+        let is_path = self.name.starts_with("./") || self.name.starts_with("../");
+        if !(is_path || self.modifiers.contains(Modifiers::RECIPE)) {
+            return None;
+        }
+        std::path::Path::new(&self.name).file_stem()?.to_str()
+    }
+
     /// Access the ingredient modifiers
     pub fn modifiers(&self) -> Modifiers {
         self.modifiers
@@ -0,0 +1,142 @@
+use thiserror::Error;
+
+/// Characters that start a component
+///
+/// By default these are the standard Cooklang markers, `@` for ingredients,
+/// `#` for cookware and `~` for timers. The ones not used become regular
+/// text. The other syntax that uses these characters, like the recipe
+/// modifier `@@`, the prep order `[#2]` or the relative intermediate
+/// preparation reference `@&(~1)dough{}`, uses the configured markers too. So
+/// with `!` for timers, that reference is written `@&(!1)dough{}`, and a `~`
+/// there is an error.
+///
+/// A marker has to be a punctuation or symbol character that is not used by
+/// other Cooklang syntax, and all three must be different.
+///
+/// The unit separator `%` always works, but another character can also be
+/// used for it with [`Self::with_unit_separator`]. It has the same
+/// restrictions as the markers, so characters with another meaning, like the
+/// auto scale marker `*`, can't be used.
+///
+/// ```
+/// # use cooklang::parser::{ComponentMarkers, Event, PullParser};
+/// # use cooklang::Extensions;
+/// let markers = ComponentMarkers::new('$', '^', '!').unwrap();
+/// let events = PullParser::with_markers("Pay $rent{1} @ home", Extensions::empty(), markers)
+///     .filter(|ev| matches!(ev, Event::Ingredient(_)))
+///     .count();
+/// assert_eq!(events, 1);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ComponentMarkers {
+    ingredient: char,
+    cookware: char,
+    timer: char,
+    unit_separator: Option<char>,
+}
+
+/// Error creating [`ComponentMarkers`]
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum ComponentMarkersError {
+    #[error("Invalid component marker: '{0}'")]
+    Invalid(char),
+    #[error("Duplicate component marker: '{0}'")]
+    Duplicate(char),
+}
+
+/// Characters with a meaning in Cooklang besides the component markers
+const RESERVED: &[char] = &[
+    '\\', '>', ':', '?', '+', '-', '/', '*', '&', '|', '=', '%', '{', '}', '(', ')', '[', ']', '.',
+    ',',
+];
+
+impl ComponentMarkers {
+    /// Creates a new set of markers
+    pub fn new(
+        ingredient: char,
+        cookware: char,
+        timer: char,
+    ) -> Result<Self, ComponentMarkersError> {
+        for c in [ingredient, cookware, timer] {
+            check_char(c)?;
+        }
+        if ingredient == cookware || ingredient == timer {
+            return Err(ComponentMarkersError::Duplicate(ingredient));
+        }
+        if cookware == timer {
+            return Err(ComponentMarkersError::Duplicate(cookware));
+        }
+        Ok(Self {
+            ingredient,
+            cookware,
+            timer,
+            unit_separator: None,
+        })
+    }
+
+    /// Adds another character to separate the value and the unit, besides `%`
+    ///
+    /// ```
+    /// # use cooklang::parser::ComponentMarkers;
+    /// # use cooklang::CooklangParser;
+    /// let markers = ComponentMarkers::default().with_unit_separator('$').unwrap();
+    /// let parser = CooklangParser::extended().with_markers(markers);
+    /// let recipe = parser.parse("Add @flour{200$g} and @salt{1%tsp}.").into_output().unwrap();
+    /// assert_eq!(recipe.ingredients[0].quantity.as_ref().unwrap().unit_text(), Some("g"));
+    /// assert_eq!(recipe.ingredients[1].quantity.as_ref().unwrap().unit_text(), Some("tsp"));
+    /// ```
+    pub fn with_unit_separator(mut self, separator: char) -> Result<Self, ComponentMarkersError> {
+        check_char(separator)?;
+        if self.contains(separator) {
+            return Err(ComponentMarkersError::Duplicate(separator));
+        }
+        self.unit_separator = Some(separator);
+        Ok(self)
+    }
+
+    pub fn ingredient(&self) -> char {
+        self.ingredient
+    }
+
+    pub fn cookware(&self) -> char {
+        self.cookware
+    }
+
+    pub fn timer(&self) -> char {
+        self.timer
+    }
+
+    /// The extra unit separator, if any
+    pub fn unit_separator(&self) -> Option<char> {
+        self.unit_separator
+    }
+
+    pub(crate) fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub(crate) fn contains(&self, c: char) -> bool {
+        c == self.ingredient
+            || c == self.cookware
+            || c == self.timer
+            || Some(c) == self.unit_separator
+    }
+}
+
+fn check_char(c: char) -> Result<(), ComponentMarkersError> {
+    if c.is_alphanumeric() || c.is_whitespace() || c.is_control() || RESERVED.contains(&c) {
+        return Err(ComponentMarkersError::Invalid(c));
+    }
+    Ok(())
+}
+
+impl Default for ComponentMarkers {
+    fn default() -> Self {
+        Self {
+            ingredient: '@',
+            cookware: '#',
+            timer: '~',
+            unit_separator: None,
+        }
+    }
+}
@@ -11,10 +11,12 @@
 //! meta_key   = (!(Colon | Newline) ANY)*
 //! meta_value = (!Newline ANY)*
 //!
-//! section    = Eq+ (section_name Eq*)
+//! section    = Eq+ (section_name sect_yield? Eq*)
 //! sect_name  = (!Eq ANY)*
+//! sect_yield = OpenBrace Quantity CloseBrace
 //!
-//! step       = TextStep? (component | ANY)*
+//! step       = TextStep? (component | ANY)* step_note?
+//! step_note  = TextStep ANY*
 //!
 //! component  = c_kind modifiers? c_body note?
 //! c_kind     = At | Hash | Tilde
@@ -53,6 +55,7 @@
 //! not cover but the pareser does.
 
 mod block_parser;
+mod markers;
 mod metadata;
 mod model;
 mod quantity;
@@ -61,6 +64,7 @@ mod step;
 mod text_block;
 mod token_stream;
 
+pub use markers::{ComponentMarkers, ComponentMarkersError};
 pub use model::*;
 
 use std::collections::VecDeque;
@@ -87,6 +91,10 @@ pub enum Event<'i> {
     Metadata { key: Text<'i>, value: Text<'i> },
     /// Section (single line block)
     Section { name: Option<Text<'i>> },
+    /// Yield of a section, like `{12%meatballs}` in `== Meatballs {12%meatballs} ==`
+    ///
+    /// Emitted right after the [`Event::Section`] it belongs to.
+    SectionYield(Located<Quantity<'i>>),
     /// Start of an element that can contain others.
     ///
     /// If this is emitted, a later [`Event::End`] of the same kind is
@@ -105,6 +113,10 @@ pub enum Event<'i> {
     Cookware(Located<Cookware<'i>>),
     /// Timer item
     Timer(Located<Timer<'i>>),
+    /// Note of a step
+    ///
+    /// Emitted at most once, after the items of a [`BlockKind::Step`].
+    StepNote(Text<'i>),
 
     /// Parser error
     ///
@@ -151,6 +163,11 @@ impl<'i> PullParser<'i, TokenStream<'i>> {
     pub fn new(input: &'i str, extensions: Extensions) -> Self {
         Self::new_from_token_iter(input, extensions, TokenStream::new(input))
     }
+
+    /// Creates a new parser with custom [`ComponentMarkers`]
+    pub fn with_markers(input: &'i str, extensions: Extensions, markers: ComponentMarkers) -> Self {
+        Self::new_from_token_iter(input, extensions, TokenStream::with_markers(input, markers))
+    }
 }
 
 impl<'i, T> PullParser<'i, T>
@@ -334,14 +351,12 @@ where
 
 fn parse_block(block: &mut BlockParser) {
     let meta_or_section = match block.peek() {
-        T![meta] => block.with_recover(metadata_entry),
+        T![meta] => block.with_recover(metadata_entry).map(|ev| block.event(ev)),
         T![=] => block.with_recover(section),
         _ => None,
     };
 
-    if let Some(ev) = meta_or_section {
-        block.event(ev);
-    } else {
+    if meta_or_section.is_none() {
         parse_multiline_block(block);
     }
 }
@@ -463,7 +478,8 @@ mod tests {
                     t.append_fragment(crate::text::TextFragment::soft_break("\n", 37));
                     t.append_str(" and this line continues  ", 39);
                     t
-                })]
+                })],
+                note: None,
             }]
         );
     }
@@ -15,7 +15,11 @@ pub enum Block<'a> {
     /// Section divider
     ///
     /// In the ast, a section does not own steps, it just exists in between.
-    Section { name: Option<Text<'a>> },
+    Section {
+        name: Option<Text<'a>>,
+        /// How much the section makes, the `{quantity}` after the name
+        yields: Option<Located<Quantity<'a>>>,
+    },
     /// Recipe step
     Step {
         /// Items that compose the step.
@@ -23,6 +27,8 @@ pub enum Block<'a> {
         /// This is in order, so to form the representation of the step just
         /// iterate over the items and process them in that order.
         items: Vec<Item<'a>>,
+        /// Note of the step, the text after a `>`
+        note: Option<Text<'a>>,
     },
     /// A paragraph of instructions
     TextBlock(Vec<Text<'a>>),
@@ -66,6 +72,10 @@ pub struct Ingredient<'a> {
     pub alias: Option<Text<'a>>,
     pub quantity: Option<Located<Quantity<'a>>>,
     pub note: Option<Text<'a>>,
+    /// Prep order hint
+    ///
+    /// Only with the [`PREP_ORDER`](crate::Extensions::PREP_ORDER) extension.
+    pub prep_order: Option<Located<u32>>,
 }
 
 /// Cookware [`Item`]
@@ -104,6 +114,17 @@ pub struct Quantity<'a> {
     ///
     /// It's just the text, no checks
     pub unit: Option<Text<'a>>,
+    /// [`Some`] if the approximate marker (`~`) is present
+    ///
+    /// Only with the
+    /// [`APPROXIMATE_QUANTITIES`](crate::Extensions::APPROXIMATE_QUANTITIES)
+    /// extension.
+    pub approximate: Option<Span>,
+    /// [`Some`] if the unit lock marker (`!`) is present
+    ///
+    /// Only with the [`LOCKED_UNITS`](crate::Extensions::LOCKED_UNITS)
+    /// extension.
+    pub unit_locked: Option<Span>,
 }
 
 /// Quantity value(s)
@@ -156,6 +177,8 @@ impl Recover for Quantity<'_> {
         Self {
             value: Recover::recover(),
             unit: Recover::recover(),
+            approximate: None,
+            unit_locked: None,
         }
     }
 }
@@ -241,12 +264,16 @@ pub struct IntermediateData {
     /// |:-------------------------|:---------------------------------------|:--------------------------|
     /// | [`Number`]               | Step number **in the current section** | Section number            |
     /// | [`Relative`]             | Number of non text steps back          | Number of sections back   |
+    /// | [`Name`]                 | -                                      | Always 0                  |
     ///
     /// [`Step`]: IntermediateTargetKind::Step
     /// [`Section`]: IntermediateTargetKind::Section
     /// [`Number`]: IntermediateRefMode::Number
     /// [`Relative`]: IntermediateRefMode::Relative
+    /// [`Name`]: IntermediateRefMode::Name
     pub val: i16,
+    /// Span of the section name, only with [`IntermediateRefMode::Name`]
+    pub name: Option<Span>,
 }
 
 /// How to treat the value in [`IntermediateData`]
@@ -258,6 +285,11 @@ pub enum IntermediateRefMode {
     ///
     /// When it is steps, is number of non text steps back.
     Relative,
+    /// Section name
+    ///
+    /// Only for sections. The name is the text after the `=` in the
+    /// reference, see [`IntermediateData::name`].
+    Name,
 }
 
 /// What the target of [`IntermediateData`] is
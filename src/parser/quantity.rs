@@ -25,9 +25,60 @@ pub(crate) fn parse_quantity<'i>(
 ) -> ParsedQuantity<'i> {
     assert!(!tokens.is_empty(), "empty quantity tokens. this is a bug.");
 
+    let mut value_tokens = tokens;
+    let approximate = bp
+        .extension(Extensions::APPROXIMATE_QUANTITIES)
+        .then(|| approximate_marker(bp, value_tokens))
+        .flatten()
+        .map(|pos| {
+            let span = value_tokens[pos].span;
+            value_tokens = &value_tokens[pos + 1..];
+            span
+        });
+    let unit_locked = bp
+        .extension(Extensions::LOCKED_UNITS)
+        .then(|| unit_lock_marker(bp, value_tokens))
+        .flatten()
+        .map(|pos| {
+            let span = value_tokens[pos].span;
+            value_tokens = &value_tokens[..pos];
+            span
+        });
+    if approximate.is_none() && unit_locked.is_none() {
+        return parse_quantity_value(bp, tokens);
+    }
+
+    // the rest is parsed as usual, only the quantity location includes the markers
+    let mut q = parse_quantity_value(bp, value_tokens);
+    let mut quantity = q.quantity.into_inner();
+    quantity.approximate = approximate;
+    if let Some(marker) = unit_locked {
+        if quantity.unit.is_some() {
+            quantity.unit_locked = Some(marker);
+        } else {
+            bp.error(
+                error!(
+                    "Invalid unit lock marker: no unit",
+                    label!(marker, "remove this")
+                )
+                .hint("Only a quantity with a unit can lock it"),
+            );
+        }
+    }
+    q.quantity = Located::new(quantity, tokens_span(tokens));
+    q
+}
+
+fn parse_quantity_value<'i>(bp: &mut BlockParser<'_, 'i>, tokens: &[Token]) -> ParsedQuantity<'i> {
     // create an insolated sub-block for the quantity tokens
     let mut bp2 = BlockParser::new(tokens, bp.input, bp.events, bp.extensions);
 
+    if bp2.extension(Extensions::MULTIPLIERS) {
+        if let Some(q) = bp2.with_recover(parse_multiplier_quantity) {
+            return q;
+        }
+    }
+
     let advanced = bp2
         .extension(Extensions::ADVANCED_UNITS)
         .then(|| bp2.with_recover(parse_advanced_quantity))
@@ -36,6 +87,30 @@ pub(crate) fn parse_quantity<'i>(
     advanced.unwrap_or_else(|| parse_regular_quantity(&mut bp2))
 }
 
+/// Position of the approximate marker, a `~` before the value, like `~200`
+///
+/// Inside the braces there can't be a timer, so the `~` is never ambiguous.
+/// It is only a marker if something follows it. It is always `~`, even with
+/// custom component markers.
+fn approximate_marker(bp: &BlockParser, tokens: &[Token]) -> Option<usize> {
+    let pos = tokens.iter().position(not_ws_comment)?;
+    if bp.token_str(tokens[pos]) != "~" || !tokens[pos + 1..].iter().any(not_ws_comment) {
+        return None;
+    }
+    Some(pos)
+}
+
+/// Position of the unit lock marker, a `!` at the end, like `250%ml!`
+///
+/// It is only a marker if something comes before it.
+fn unit_lock_marker(bp: &BlockParser, tokens: &[Token]) -> Option<usize> {
+    let pos = tokens.iter().rposition(not_ws_comment)?;
+    if bp.token_str(tokens[pos]) != "!" || !tokens[..pos].iter().any(not_ws_comment) {
+        return None;
+    }
+    Some(pos)
+}
+
 fn parse_regular_quantity<'i>(bp: &mut BlockParser<'_, 'i>) -> ParsedQuantity<'i> {
     let mut value = many_values(bp);
     let unit = match bp.peek() {
@@ -81,7 +156,15 @@ fn parse_regular_quantity<'i>(bp: &mut BlockParser<'_, 'i>) -> ParsedQuantity<'i
     }
 
     ParsedQuantity {
-        quantity: Located::new(Quantity { value, unit }, tokens_span(bp.tokens())),
+        quantity: Located::new(
+            Quantity {
+                value,
+                unit,
+                approximate: None,
+                unit_locked: None,
+            },
+            tokens_span(bp.tokens()),
+        ),
         unit_separator,
     }
 }
@@ -98,7 +181,14 @@ fn parse_advanced_quantity<'i>(bp: &mut BlockParser<'_, 'i>) -> Option<ParsedQua
     bp.ws_comments();
     let value_tokens = bp.consume_while(|t| !matches!(t, T![word]));
 
-    if value_tokens.is_empty() || value_tokens.last().unwrap().kind != T![ws] {
+    // the unit can be separated with whitespace or directly follow a number,
+    // so `5 g` and `5g` are the same
+    if value_tokens.is_empty()
+        || !matches!(
+            value_tokens.last().unwrap().kind,
+            T![ws] | T![int] | T![zeroint]
+        )
+    {
         return None;
     }
     let value_tokens = {
@@ -140,6 +230,54 @@ fn parse_advanced_quantity<'i>(bp: &mut BlockParser<'_, 'i>) -> Option<ParsedQua
                     auto_scale: None,
                 },
                 unit: Some(unit),
+                approximate: None,
+                unit_locked: None,
+            },
+            tokens_span(bp.tokens()),
+        ),
+        unit_separator: None,
+    })
+}
+
+/// A number of items with an `x` before, like `x3`
+fn parse_multiplier_quantity<'i>(bp: &mut BlockParser<'_, 'i>) -> Option<ParsedQuantity<'i>> {
+    if bp
+        .tokens()
+        .iter()
+        .any(|t| matches!(t.kind, T![|] | T![*] | T![%]))
+    {
+        return None;
+    }
+
+    bp.ws_comments();
+    let marker = bp.consume(T![word])?;
+    if !matches!(bp.token_str(marker), "x" | "X") {
+        return None;
+    }
+
+    let value_tokens = trim_tokens(bp.consume_rest());
+    if value_tokens.is_empty() {
+        return None;
+    }
+    let value = match numeric_value(value_tokens, bp)? {
+        Ok(value) => value,
+        Err(err) => {
+            bp.error(err);
+            Value::recover()
+        }
+    };
+    let value = Located::new(value, tokens_span(value_tokens));
+
+    Some(ParsedQuantity {
+        quantity: Located::new(
+            Quantity {
+                value: QuantityValue::Single {
+                    value,
+                    auto_scale: None,
+                },
+                unit: None,
+                approximate: None,
+                unit_locked: None,
             },
             tokens_span(bp.tokens()),
         ),
@@ -269,6 +407,12 @@ fn numeric_value(tokens: &[Token], bp: &BlockParser) -> Option<Result<Value, Sou
         return None;
     }
 
+    if bp.extension(Extensions::GROUP_SEPARATORS) {
+        if let Some(r) = grouped_number(trimmed_tokens, bp) {
+            return Some(r.map(Value::from));
+        }
+    }
+
     // check simple numbers
 
     // int or float
@@ -305,6 +449,64 @@ fn numeric_value(tokens: &[Token], bp: &BlockParser) -> Option<Result<Value, Sou
     Some(r.map(Value::Number))
 }
 
+/// Numbers with group separators
+///
+/// - Whitespace or the group separator, followed by exactly 3 digits,
+///   separates groups: `1,000`, `1 000`.
+/// - The decimal separator is `.`, and the group separator `,`. With
+///   [`Extensions::DECIMAL_COMMA`] they are swapped: `1.000,5`.
+///
+/// Returns [`None`] if the tokens are not a number like this, so other
+/// kinds of values are tried.
+fn grouped_number(tokens: &[Token], bp: &BlockParser) -> Option<Result<f64, SourceDiag>> {
+    let only_number_tokens = tokens.iter().all(|t| match t.kind {
+        T![int] | T![zeroint] | T![.] | T![ws] => true,
+        T![punctuation] => bp.token_str(*t) == ",",
+        _ => false,
+    });
+    if !only_number_tokens {
+        return None;
+    }
+    let (decimal, group) = if bp.extension(Extensions::DECIMAL_COMMA) {
+        (',', '.')
+    } else {
+        ('.', ',')
+    };
+    let s = bp.slice_str(tokens);
+    if !s.contains([',', group, ' ', '\t']) {
+        return None;
+    }
+
+    let (int_part, dec_part) = match s.split_once(decimal) {
+        Some((int_part, dec_part)) => (int_part, Some(dec_part)),
+        None => (s, None),
+    };
+    if dec_part.is_some_and(|d| d.is_empty() || !d.bytes().all(|b| b.is_ascii_digit())) {
+        return None;
+    }
+
+    let mut groups = int_part.split(|c: char| c == group || c.is_whitespace());
+    let first = groups.next()?;
+    if first.is_empty() || first.len() > 3 {
+        return None;
+    }
+    let mut digits = first.to_string();
+    for group in groups {
+        if group.len() != 3 {
+            return None;
+        }
+        digits.push_str(group);
+    }
+    if let Some(dec) = dec_part {
+        digits.push('.');
+        digits.push_str(dec);
+    }
+
+    Some(digits.parse::<f64>().map_err(|e| {
+        error!("Error parsing decimal number", label!(tokens_span(tokens))).set_source(e)
+    }))
+}
+
 fn mixed_num(i: Token, a: Token, b: Token, bp: &BlockParser) -> Result<Number, SourceDiag> {
     let i = int(i, bp)?;
     let Number::Fraction { num, den, .. } = frac(a, b, bp)? else {
@@ -433,6 +635,43 @@ mod tests {
         assert!(ctx.is_empty());
     }
 
+    #[test]
+    fn no_separator_no_space() {
+        let (q, s, ctx) = t!("100ml");
+        assert_eq!(
+            q.value,
+            QuantityValue::Single {
+                value: Located::new(num!(100.0), 0..3),
+                auto_scale: None
+            }
+        );
+        assert_eq!(s, None);
+        assert_eq!(q.unit.unwrap().text(), "ml");
+        assert!(ctx.is_empty());
+
+        let (q, _, _) = t!("1.5  kg");
+        assert_eq!(q.unit.unwrap().text(), "kg");
+
+        let (q, _, ctx) = t!("1/2cup");
+        assert_eq!(
+            q.value,
+            QuantityValue::Single {
+                value: Located::new(
+                    Value::Number(Number::Fraction {
+                        whole: 0,
+                        num: 1,
+                        den: 2,
+                        err: 0.0
+                    }),
+                    0..3
+                ),
+                auto_scale: None
+            }
+        );
+        assert_eq!(q.unit.unwrap().text(), "cup");
+        assert!(ctx.is_empty());
+    }
+
     #[test]
     fn no_separator_range() {
         let (q, s, ctx) = t!("100-200 ml");
@@ -619,4 +858,162 @@ mod tests {
         assert!(r.is_empty(), "source error");
         n
     }
+
+    #[test_case("1,000", false => Some(1000.0) ; "thousands comma")]
+    #[test_case("1 000", false => Some(1000.0) ; "thousands space")]
+    #[test_case("12,345,678", false => Some(12345678.0) ; "many groups")]
+    #[test_case("1,5", false => None ; "comma is not decimal")]
+    #[test_case("1,000.5", false => Some(1000.5) ; "thousands and decimal point")]
+    #[test_case("1.000", false => Some(1.0) ; "decimal point")]
+    #[test_case("1,00,000", false => None ; "bad groups")]
+    #[test_case("1,000 g", false => Some(1000.0) ; "advanced unit")]
+    #[test_case("a,000", false => None ; "not a number")]
+    #[test_case("1,5", true => Some(1.5) ; "decimal comma")]
+    #[test_case("1,25", true => Some(1.25) ; "decimal comma 2 digits")]
+    #[test_case("1,000", true => Some(1.0) ; "decimal comma 3 digits")]
+    #[test_case("1.000", true => Some(1000.0) ; "thousands point")]
+    #[test_case("1 000", true => Some(1000.0) ; "thousands space decimal comma")]
+    #[test_case("1.000,5", true => Some(1000.5) ; "thousands and decimal comma")]
+    #[test_case("1,000.5", true => None ; "decimal before group")]
+    fn grouped_numbers(s: &str, decimal_comma: bool) -> Option<f64> {
+        let extensions = if decimal_comma {
+            Extensions::all()
+        } else {
+            Extensions::default() | Extensions::GROUP_SEPARATORS
+        };
+        let (q, _, r) = t!(s, extensions);
+        assert!(r.is_empty(), "source error");
+        let QuantityValue::Single { value, .. } = q.value else {
+            panic!("not single value")
+        };
+        match value.into_inner() {
+            Value::Number(n) => Some(n.value()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn grouped_numbers_extension() {
+        let (q, _, _) = t!("1,000%g", Extensions::default());
+        let QuantityValue::Single { value, .. } = q.value else {
+            panic!("not single value")
+        };
+        assert_eq!(value.into_inner(), Value::Text("1,000".into()));
+
+        let (q, _, _) = t!(
+            "1,000-1,500%g",
+            Extensions::default() | Extensions::GROUP_SEPARATORS
+        );
+        let QuantityValue::Single { value, .. } = q.value else {
+            panic!("not single value")
+        };
+        assert_eq!(value.into_inner(), range!(1000.0, 1500.0));
+    }
+
+    #[test]
+    fn multipliers() {
+        let (q, s, _) = t!("x3");
+        assert_eq!(
+            q.value,
+            QuantityValue::Single {
+                value: Located::new(num!(3.0), 1..2),
+                auto_scale: None,
+            }
+        );
+        assert_eq!(q.unit, None);
+        assert_eq!(s, None);
+
+        let (q, _, _) = t!("x 1/2");
+        let QuantityValue::Single { value, .. } = q.value else {
+            panic!("not single value")
+        };
+        assert_eq!(
+            value.into_inner(),
+            Value::Number(Number::Fraction {
+                whole: 0,
+                num: 1,
+                den: 2,
+                err: 0.0
+            })
+        );
+
+        // not a multiplier
+        let (q, _, _) = t!("xl");
+        let QuantityValue::Single { value, .. } = q.value else {
+            panic!("not single value")
+        };
+        assert_eq!(value.into_inner(), Value::Text("xl".into()));
+        let (q, _, _) = t!("x3%cups");
+        assert_eq!(q.unit.unwrap().text(), "cups");
+
+        let (q, _, _) = t!("x3", Extensions::all() ^ Extensions::MULTIPLIERS);
+        let QuantityValue::Single { value, .. } = q.value else {
+            panic!("not single value")
+        };
+        assert_eq!(value.into_inner(), Value::Text("x3".into()));
+    }
+
+    #[test]
+    fn approximate() {
+        let (q, s, r) = t!("~200%g");
+        assert_eq!(q.approximate, Some(Span::new(0, 1)));
+        assert_eq!(
+            q.value,
+            QuantityValue::Single {
+                value: Located::new(num!(200.0), 1..4),
+                auto_scale: None,
+            }
+        );
+        assert_eq!(q.unit.unwrap().text(), "g");
+        assert_eq!(s, Some(Span::new(4, 5)));
+        assert!(r.is_empty());
+
+        let (q, _, _) = t!(" ~ 1-2 cups");
+        assert_eq!(q.approximate, Some(Span::new(1, 2)));
+        let QuantityValue::Single { value, .. } = q.value else {
+            panic!("not single value")
+        };
+        assert_eq!(value.into_inner(), range!(1.0, 2.0));
+        assert_eq!(q.unit.unwrap().text(), "cups");
+
+        // only the marker is text
+        let (q, _, _) = t!("~");
+        assert_eq!(q.approximate, None);
+        let QuantityValue::Single { value, .. } = q.value else {
+            panic!("not single value")
+        };
+        assert_eq!(value.into_inner(), Value::Text("~".into()));
+
+        let (q, _, _) = t!(
+            "~200%g",
+            Extensions::all() ^ Extensions::APPROXIMATE_QUANTITIES
+        );
+        assert_eq!(q.approximate, None);
+        let QuantityValue::Single { value, .. } = q.value else {
+            panic!("not single value")
+        };
+        assert_eq!(value.into_inner(), Value::Text("~200".into()));
+    }
+
+    #[test]
+    fn unit_lock() {
+        let (q, s, r) = t!("250%ml!");
+        assert_eq!(q.unit_locked, Some(Span::new(6, 7)));
+        assert_eq!(q.unit.unwrap().text(), "ml");
+        assert_eq!(s, Some(Span::new(3, 4)));
+        assert!(r.is_empty());
+
+        let (q, _, _) = t!("~1 1/2 kg !");
+        assert!(q.approximate.is_some());
+        assert!(q.unit_locked.is_some());
+        assert_eq!(q.unit.unwrap().text_trimmed(), "kg");
+
+        let (q, _, r) = t!("2!");
+        assert_eq!(q.unit_locked, None);
+        assert_eq!(r.errors().count(), 1);
+
+        let (q, _, _) = t!("250%ml!", Extensions::all() ^ Extensions::LOCKED_UNITS);
+        assert_eq!(q.unit_locked, None);
+        assert_eq!(q.unit.unwrap().text(), "ml!");
+    }
 }
@@ -1,8 +1,11 @@
 use crate::{error::label, lexer::T, Extensions};
 
-use super::{tokens_span, warning, BlockParser, Event};
+use super::{
+    quantity::parse_quantity, token_stream::Token, tokens_span, warning, BlockParser, Event,
+};
 
-pub(crate) fn section<'i>(block: &mut BlockParser<'_, 'i>) -> Option<Event<'i>> {
+/// Parses a section and pushes its events
+pub(crate) fn section(block: &mut BlockParser<'_, '_>) -> Option<()> {
     if !block.extension(Extensions::SECTIONS) {
         return None;
     }
@@ -11,6 +14,11 @@ pub(crate) fn section<'i>(block: &mut BlockParser<'_, 'i>) -> Option<Event<'i>>
     block.consume_while(|t| t == T![=]);
     let name_pos = block.current_offset();
     let name_tokens = block.consume_while(|t| t != T![=]);
+    let (name_tokens, yield_tokens) = if block.extension(Extensions::SECTION_YIELDS) {
+        split_yield(name_tokens)
+    } else {
+        (name_tokens, None)
+    };
     let name = block.text(name_pos, name_tokens);
     block.consume_while(|t| t == T![=]);
     block.ws_comments();
@@ -31,7 +39,36 @@ pub(crate) fn section<'i>(block: &mut BlockParser<'_, 'i>) -> Option<Event<'i>>
     } else {
         Some(name)
     };
-    Some(Event::Section { name })
+    block.event(Event::Section { name });
+    if let Some(tokens) = yield_tokens {
+        let quantity = parse_quantity(block, tokens).quantity;
+        block.event(Event::SectionYield(quantity));
+    }
+    Some(())
+}
+
+/// Splits the `{quantity}` at the end of the name of a section
+///
+/// The quantity is [`None`] when the braces are empty.
+fn split_yield(tokens: &[Token]) -> (&[Token], Option<&[Token]>) {
+    let close = tokens
+        .iter()
+        .rposition(|t| !matches!(t.kind, T![ws] | T![line comment] | T![block comment]))
+        .filter(|&i| tokens[i].kind == T!['}']);
+    let Some(close) = close else {
+        return (tokens, None);
+    };
+    let Some(open) = tokens[..close].iter().rposition(|t| t.kind == T!['{']) else {
+        return (tokens, None);
+    };
+    let quantity = &tokens[open + 1..close];
+    if quantity.iter().any(|t| t.kind == T!['}']) {
+        return (tokens, None);
+    }
+    let not_empty = quantity
+        .iter()
+        .any(|t| !matches!(t.kind, T![ws] | T![block comment]));
+    (&tokens[..open], not_empty.then_some(quantity))
 }
 
 #[cfg(test)]
@@ -70,12 +107,35 @@ mod tests {
         let tokens = TokenStream::new(input).collect::<Vec<_>>();
         let mut events = VecDeque::new();
         let mut bp = BlockParser::new(&tokens, input, &mut events, Extensions::all());
-        let event = section(&mut bp).expect("failed to parse section");
+        section(&mut bp).expect("failed to parse section");
         bp.finish();
-        assert!(events.is_empty());
-        let Event::Section { name } = event else {
+        assert_eq!(events.len(), 1);
+        let Some(Event::Section { name }) = events.pop_front() else {
             panic!()
         };
         name.map(|text| (text.text().into_owned(), text.span()))
     }
+
+    #[test_case("== Meatballs {12%meatballs} ==" => (Some(" Meatballs ".into()), Some("12%meatballs".into())) ; "named")]
+    #[test_case("= Sauce {1.5%l}" => (Some(" Sauce ".into()), Some("1.5%l".into())) ; "single char")]
+    #[test_case("== {4} ==" => (None, Some("4".into())) ; "no name")]
+    #[test_case("== Dough {} ==" => (Some(" Dough ".into()), None) ; "empty")]
+    #[test_case("== Dough {4} -- comment" => (Some(" Dough ".into()), Some("4".into())) ; "trailing comment")]
+    #[test_case("== {4} Dough ==" => (Some(" {4} Dough ".into()), None) ; "not at the end")]
+    fn test_section_yield(input: &'static str) -> (Option<String>, Option<String>) {
+        let tokens = TokenStream::new(input).collect::<Vec<_>>();
+        let mut events = VecDeque::new();
+        let mut bp = BlockParser::new(&tokens, input, &mut events, Extensions::all());
+        section(&mut bp).expect("failed to parse section");
+        bp.finish();
+        let Some(Event::Section { name }) = events.pop_front() else {
+            panic!()
+        };
+        let quantity = events.pop_front().map(|ev| {
+            let Event::SectionYield(q) = ev else { panic!() };
+            input[q.span().start()..q.span().end()].to_string()
+        });
+        assert!(events.is_empty());
+        (name.map(|text| text.text().into_owned()), quantity)
+    }
 }
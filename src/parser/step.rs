@@ -13,7 +13,13 @@ use super::{
 pub(crate) fn parse_step(bp: &mut BlockParser<'_, '_>) {
     bp.event(Event::Start(BlockKind::Step));
 
+    let notes = bp.extension(Extensions::STEP_NOTES);
     while !bp.rest().is_empty() {
+        // a step can't start with a note
+        if notes && bp.current > 0 && bp.at(T![>]) {
+            step_note(bp);
+            break;
+        }
         let component = match bp.peek() {
             T![@] => bp.with_recover(ingredient),
             T![#] => bp.with_recover(cookware),
@@ -26,7 +32,10 @@ pub(crate) fn parse_step(bp: &mut BlockParser<'_, '_>) {
             let start = bp.current_offset();
             let tokens = bp.capture_slice(|bp| {
                 bp.bump_any(); // consume the first token, this avoids entering an infinite loop
-                bp.consume_while(|t| !matches!(t, T![@] | T![#] | T![~]));
+                bp.consume_while(|t| {
+                    let note = notes && t == T![>];
+                    !(note || matches!(t, T![@] | T![#] | T![~]))
+                });
             });
             let text = bp.text(start, tokens);
             if !text.fragments().is_empty() {
@@ -38,6 +47,17 @@ pub(crate) fn parse_step(bp: &mut BlockParser<'_, '_>) {
     bp.event(Event::End(BlockKind::Step));
 }
 
+/// The rest of the step after the `>` is a note, components are not parsed
+fn step_note(bp: &mut BlockParser<'_, '_>) {
+    bp.bump(T![>]);
+    let offset = bp.current_offset();
+    let tokens = bp.consume_rest();
+    let note = bp.text(offset, tokens);
+    if !note.is_text_empty() {
+        bp.event(Event::StepNote(note));
+    }
+}
+
 struct Body<'t> {
     name: &'t [Token],
     close: Option<Span>,
@@ -129,6 +149,41 @@ fn note<'i>(bp: &mut BlockParser<'_, 'i>) -> Option<Text<'i>> {
         .flatten()
 }
 
+fn prep_order(bp: &mut BlockParser) -> Option<Located<u32>> {
+    if !bp.extension(Extensions::PREP_ORDER) {
+        return None;
+    }
+    bp.with_recover(|bp| {
+        let open = bp.consume(T![punctuation])?;
+        if bp.token_str(open) != "[" {
+            return None;
+        }
+        bp.consume(T![#])?;
+        let num = bp.consume(T![int])?;
+        let close = bp.consume(T![punctuation])?;
+        if bp.token_str(close) != "]" {
+            return None;
+        }
+        let span = Span::new(open.span.start(), close.span.end());
+        let order = match bp.token_str(num).parse::<u32>() {
+            Ok(0) => {
+                bp.error(
+                    error!("Invalid prep order", label!(num.span, "this is 0"))
+                        .hint("Prep order starts at 1"),
+                );
+                return Some(None);
+            }
+            Ok(order) => order,
+            Err(e) => {
+                bp.error(error!("Invalid prep order", label!(num.span)).set_source(e));
+                return Some(None);
+            }
+        };
+        Some(Some(Located::new(order, span)))
+    })
+    .flatten()
+}
+
 struct ParsedModifiers {
     flags: Located<Modifiers>,
     intermediate_data: Option<Located<IntermediateData>>,
@@ -193,7 +248,8 @@ fn parse_intermediate_ref_data(
 ) -> Option<Located<IntermediateData>> {
     use IntermediateRefMode::*;
     use IntermediateTargetKind::*;
-    const INTER_PREP_HELP: &str = "The target is something like: `1`, `~1`, `=1` or `=~1`";
+    const INTER_PREP_HELP: &str =
+        "The target is something like: `1`, `~1`, `=1`, `=~1` or `=section name`";
     const INVALID: &str = "Invalid intermediate preparation reference";
 
     // if '(' has been taken as a modifier token, it has taken until
@@ -223,6 +279,21 @@ fn parse_intermediate_ref_data(
         [mt![~], i @ mt![int]] => (i, Relative, Step),
         [mt![=], i @ mt![int]] => (i, Number, Section),
         [mt![=], mt![~], i @ mt![int]] => (i, Relative, Section),
+        [mt![=], first, ..] if first.kind == T![word] => {
+            let is_name = |t: &Token| !matches!(t.kind, T![ws] | T![block comment]);
+            let start = inner_slice
+                .iter()
+                .position(|t| t.span == first.span)
+                .unwrap();
+            let end = inner_slice.iter().rposition(is_name).unwrap();
+            let data = IntermediateData {
+                ref_mode: Name,
+                target_kind: Section,
+                val: 0,
+                name: Some(tokens_span(&inner_slice[start..=end])),
+            };
+            return Some(Located::new(data, tokens_span(slice)));
+        }
 
         // common errors
         [] => {
@@ -274,6 +345,7 @@ fn parse_intermediate_ref_data(
         ref_mode,
         target_kind,
         val,
+        name: None,
     };
 
     Some(Located::new(data, tokens_span(slice)))
@@ -337,6 +409,7 @@ fn ingredient<'i>(bp: &mut BlockParser<'_, 'i>) -> Option<Event<'i>> {
     let name_offset = bp.current_offset();
     let body = comp_body(bp)?;
     let note = note(bp);
+    let prep_order = prep_order(bp);
     let end = bp.current_offset();
 
     // Build text(s) and checks
@@ -360,6 +433,7 @@ fn ingredient<'i>(bp: &mut BlockParser<'_, 'i>) -> Option<Event<'i>> {
             alias,
             quantity,
             note,
+            prep_order,
         },
         start..end,
     )))
@@ -409,6 +483,15 @@ fn cookware<'i>(bp: &mut BlockParser<'_, 'i>) -> Option<Event<'i>> {
                 .hint("Cookware items amount can't be auto scaled"),
             );
         }
+        if let Some(marker) = q.quantity.approximate {
+            bp.error(
+                error!(
+                    "Invalid cookware quantity: approximate marker",
+                    label!(marker, "remove this"),
+                )
+                .hint("Cookware items amount can't be approximate"),
+            );
+        }
         q.quantity.map(|q| q.value)
     });
     let modifiers = parse_modifiers(bp, modifiers_tokens, modifiers_pos);
@@ -658,7 +741,8 @@ mod tests {
         Located::new(IntermediateData {
             ref_mode: IntermediateRefMode::Relative,
             target_kind: IntermediateTargetKind::Step,
-            val: 1
+            val: 1,
+            name: None,
             }, 2..6)
     ); "step relative")]
     #[test_case("@&(1)step index 1{}" => (
@@ -666,7 +750,8 @@ mod tests {
         Located::new(IntermediateData {
             ref_mode: IntermediateRefMode::Number,
             target_kind: IntermediateTargetKind::Step,
-            val: 1
+            val: 1,
+            name: None,
         }, 2..5)
     ); "step index")]
     #[test_case("@&(=~1)one section back{}" => (
@@ -674,7 +759,8 @@ mod tests {
         Located::new(IntermediateData {
             ref_mode: IntermediateRefMode::Relative,
             target_kind: IntermediateTargetKind::Section,
-            val: 1
+            val: 1,
+            name: None,
         }, 2..7)
     ); "section relative")]
     #[test_case("@&(=1)section index 1{}" => (
@@ -682,9 +768,19 @@ mod tests {
         Located::new(IntermediateData {
             ref_mode: IntermediateRefMode::Number,
             target_kind: IntermediateTargetKind::Section,
-            val: 1
+            val: 1,
+            name: None,
         }, 2..6)
     ); "section index")]
+    #[test_case("@&(= Sauce  base )sauce{}" => (
+        Located::new(Modifiers::REF, 1..18),
+        Located::new(IntermediateData {
+            ref_mode: IntermediateRefMode::Name,
+            target_kind: IntermediateTargetKind::Section,
+            val: 0,
+            name: Some(Span::new(5, 16)),
+        }, 2..18)
+    ); "section name")]
     fn intermediate_ref(input: &str) -> (Located<Modifiers>, Located<IntermediateData>) {
         let (s, ctx) = t(input);
         let igr = igr!(&s[0]);
@@ -718,4 +814,23 @@ mod tests {
         let body = comp_body(&mut bp).expect("not parsed");
         bp.text(0, body.name).text_trimmed().into_owned()
     }
+
+    #[test_case("@onion{1}[#2]" => Some(2); "after quantity")]
+    #[test_case("@onion{1}(diced)[#1]" => Some(1); "after note")]
+    #[test_case("@onion[#3]" => Some(3); "single word")]
+    #[test_case("@onion{1} [#2]" => None; "space")]
+    #[test_case("@onion{1}[2]" => None; "no hash")]
+    fn prep_order(input: &str) -> Option<u32> {
+        let (s, ctx) = t(input);
+        assert!(ctx.is_empty());
+        igr!(&s[0]).prep_order.as_ref().map(|o| *o.value())
+    }
+
+    #[test]
+    fn prep_order_zero() {
+        let (s, ctx) = t("@onion{1}[#0]");
+        assert_eq!(ctx.errors().count(), 1);
+        assert_eq!(igr!(&s[0]).prep_order, None);
+        assert_eq!(s.len(), 1);
+    }
 }
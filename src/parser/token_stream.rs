@@ -4,6 +4,8 @@
 pub use crate::lexer::TokenKind;
 use crate::{lexer::Cursor, span::Span};
 
+use super::ComponentMarkers;
+
 pub struct TokenStream<'i> {
     cursor: Cursor<'i>,
     consumed: usize,
@@ -16,6 +18,13 @@ impl<'i> TokenStream<'i> {
             consumed: 0,
         }
     }
+
+    pub fn with_markers(input: &'i str, markers: ComponentMarkers) -> Self {
+        Self {
+            cursor: Cursor::with_markers(input, markers),
+            consumed: 0,
+        }
+    }
 }
 
 impl<'i> Iterator for TokenStream<'i> {
@@ -18,6 +18,18 @@ pub struct Quantity<V: QuantityValue = Value> {
     /// Value
     pub value: V,
     pub(crate) unit: Option<QuantityUnit>,
+    /// The quantity is approximate, written with a `~` before the value
+    ///
+    /// It is kept when scaling, converting or adding, and the [`Display`]
+    /// impl writes the `~` back.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub approximate: bool,
+    /// The unit is never converted, written with a `!` after it
+    ///
+    /// Fitting and converting a whole recipe leave the quantity as is.
+    /// Converting the quantity to a specific unit still works.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub unit_locked: bool,
 }
 
 pub type ScalableQuantity = Quantity<ScalableValue>;
@@ -219,6 +231,8 @@ impl<V: QuantityValue> Quantity<V> {
                 text,
                 info: OnceCell::new(),
             }),
+            approximate: false,
+            unit_locked: false,
         }
     }
 
@@ -230,6 +244,8 @@ impl<V: QuantityValue> Quantity<V> {
                 info: OnceCell::from(UnitInfo::new(&text, converter)),
                 text,
             }),
+            approximate: false,
+            unit_locked: false,
         }
     }
 
@@ -241,6 +257,8 @@ impl<V: QuantityValue> Quantity<V> {
                 text: unit.to_string(),
                 info: OnceCell::from(UnitInfo::Known(unit)),
             }),
+            approximate: false,
+            unit_locked: false,
         }
     }
 
@@ -289,6 +307,9 @@ impl ScalableValue {
 
 impl<V: QuantityValue + Display> Display for Quantity<V> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.approximate {
+            f.write_str("~")?;
+        }
         self.value.fmt(f)?;
         if let Some(unit) = &self.unit {
             f.write_str(" ")?;
@@ -357,6 +378,286 @@ impl Display for Number {
     }
 }
 
+/// Conventions to write numbers
+///
+/// Used in [`FormatOptions`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NumberLocale {
+    /// Separator between the integer and the decimal part
+    pub decimal_separator: char,
+    /// Separator between groups of 3 digits of the integer part, if any
+    pub group_separator: Option<char>,
+}
+
+impl NumberLocale {
+    /// `1234.5`, the same as the [`Display`] impl
+    pub const PLAIN: Self = Self {
+        decimal_separator: '.',
+        group_separator: None,
+    };
+    /// `1,234.5`
+    pub const EN: Self = Self {
+        decimal_separator: '.',
+        group_separator: Some(','),
+    };
+    /// `1 234,5`
+    pub const FR: Self = Self {
+        decimal_separator: ',',
+        group_separator: Some(' '),
+    };
+    /// `1.234,5`
+    pub const DE: Self = Self {
+        decimal_separator: ',',
+        group_separator: Some('.'),
+    };
+
+    /// Get the conventions for a language tag like `en`, `fr-FR` or `pt_BR`
+    ///
+    /// Only a few common languages are known, returns [`None`] for the rest.
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let tag = tag.trim().to_lowercase().replace('_', "-");
+        let lang = tag.split('-').next().unwrap_or_default();
+        let locale = match lang {
+            "en" | "ja" | "zh" | "ko" | "he" | "th" | "hi" => Self::EN,
+            "pt" if tag == "pt-br" => Self::DE,
+            "fr" | "ru" | "uk" | "pl" | "cs" | "sk" | "sv" | "fi" | "nb" | "no" | "hu" | "pt" => {
+                Self::FR
+            }
+            "de" | "es" | "it" | "nl" | "da" | "tr" | "id" | "el" | "ro" | "ca" => Self::DE,
+            _ => return None,
+        };
+        Some(locale)
+    }
+
+    fn write_integer(&self, digits: &str, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Some(sep) = self.group_separator else {
+            return f.write_str(digits);
+        };
+        let (sign, digits) = match digits.strip_prefix('-') {
+            Some(rest) => ("-", rest),
+            None => ("", digits),
+        };
+        f.write_str(sign)?;
+        for (i, c) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i) % 3 == 0 {
+                write!(f, "{sep}")?;
+            }
+            write!(f, "{c}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for NumberLocale {
+    fn default() -> Self {
+        Self::PLAIN
+    }
+}
+
+/// Options to display quantities and values
+///
+/// The default is the same as the [`Display`] impls.
+///
+/// ```
+/// # use cooklang::quantity::{FormatOptions, NumberLocale, Value};
+/// let options = FormatOptions {
+///     locale: NumberLocale::FR,
+///     ..Default::default()
+/// };
+/// let value = Value::from(1234.5);
+/// assert_eq!(value.display_with(&options).to_string(), "1 234,5");
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FormatOptions {
+    /// How to write numbers
+    pub locale: NumberLocale,
+    /// What goes between the value and the unit
+    pub unit_spacing: UnitSpacing,
+    /// Only write ASCII characters
+    ///
+    /// Units and text values are written with [`to_ascii`], so `°C` becomes
+    /// `degC` and `½` becomes `1/2`. Numbers are always ASCII.
+    pub ascii: bool,
+}
+
+/// Replaces common non ASCII characters in recipes with ASCII equivalents
+///
+/// For example `°` is `deg` and `½` is `1/2`. Other non ASCII characters are
+/// kept as they are.
+///
+/// ```
+/// # use cooklang::quantity::to_ascii;
+/// assert_eq!(to_ascii("180 °C"), "180 degC");
+/// assert_eq!(to_ascii("1½ cups"), "1 1/2 cups");
+/// assert_eq!(to_ascii("salt"), "salt");
+/// ```
+pub fn to_ascii(s: &str) -> std::borrow::Cow<'_, str> {
+    if s.is_ascii() {
+        return s.into();
+    }
+    let mut out = String::with_capacity(s.len());
+    let mut prev = None;
+    for c in s.chars() {
+        let replacement = match c {
+            '°' | 'º' => "deg",
+            '½' => "1/2",
+            '⅓' => "1/3",
+            '⅔' => "2/3",
+            '¼' => "1/4",
+            '¾' => "3/4",
+            '⅕' => "1/5",
+            '⅖' => "2/5",
+            '⅗' => "3/5",
+            '⅘' => "4/5",
+            '⅙' => "1/6",
+            '⅚' => "5/6",
+            '⅛' => "1/8",
+            '⅜' => "3/8",
+            '⅝' => "5/8",
+            '⅞' => "7/8",
+            'µ' | 'μ' => "u",
+            '×' => "x",
+            '–' | '—' | '−' => "-",
+            '\u{a0}' | '\u{202f}' => " ",
+            c => {
+                out.push(c);
+                prev = Some(c);
+                continue;
+            }
+        };
+        // 1½ -> 1 1/2
+        if replacement.contains('/') && prev.is_some_and(|p: char| p.is_ascii_digit()) {
+            out.push(' ');
+        }
+        out.push_str(replacement);
+        prev = replacement.chars().last();
+    }
+    out.into()
+}
+
+/// Separation between the value and the unit of a quantity
+///
+/// Used in [`FormatOptions`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UnitSpacing {
+    /// A single space: `5 g`
+    #[default]
+    Space,
+    /// No space at all: `5g`
+    None,
+}
+
+impl UnitSpacing {
+    fn as_str(self) -> &'static str {
+        match self {
+            UnitSpacing::Space => " ",
+            UnitSpacing::None => "",
+        }
+    }
+}
+
+/// Helper to [`Display`] something with [`FormatOptions`]
+///
+/// Created with the `display_with` methods.
+#[derive(Debug, Clone, Copy)]
+pub struct WithOptions<'a, T> {
+    inner: &'a T,
+    options: &'a FormatOptions,
+}
+
+impl Number {
+    /// Display the number with the given options
+    pub fn display_with<'a>(&'a self, options: &'a FormatOptions) -> WithOptions<'a, Self> {
+        WithOptions {
+            inner: self,
+            options,
+        }
+    }
+}
+
+impl Value {
+    /// Display the value with the given options
+    pub fn display_with<'a>(&'a self, options: &'a FormatOptions) -> WithOptions<'a, Self> {
+        WithOptions {
+            inner: self,
+            options,
+        }
+    }
+}
+
+impl ScaledQuantity {
+    /// Display the quantity with the given options
+    pub fn display_with<'a>(&'a self, options: &'a FormatOptions) -> WithOptions<'a, Self> {
+        WithOptions {
+            inner: self,
+            options,
+        }
+    }
+}
+
+impl Display for WithOptions<'_, Number> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let locale = &self.options.locale;
+        match *self.inner {
+            Number::Regular(n) => {
+                let s = round_float(n).to_string();
+                let (int, dec) = s.split_once('.').unwrap_or((&s, ""));
+                locale.write_integer(int, f)?;
+                if !dec.is_empty() {
+                    write!(f, "{}{dec}", locale.decimal_separator)?;
+                }
+                Ok(())
+            }
+            Number::Fraction { whole, num, .. } if whole >= 1000 => {
+                locale.write_integer(&whole.to_string(), f)?;
+                let mut rest = *self.inner;
+                if let Number::Fraction { whole, .. } = &mut rest {
+                    *whole = 0;
+                }
+                if num != 0 {
+                    write!(f, " {rest}")?;
+                }
+                Ok(())
+            }
+            n => n.fmt(f),
+        }
+    }
+}
+
+impl Display for WithOptions<'_, Value> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.inner {
+            Value::Number(n) => n.display_with(self.options).fmt(f),
+            Value::Range { start, end } => write!(
+                f,
+                "{}-{}",
+                start.display_with(self.options),
+                end.display_with(self.options)
+            ),
+            Value::Text(t) if self.options.ascii => to_ascii(t).fmt(f),
+            Value::Text(t) => t.fmt(f),
+        }
+    }
+}
+
+impl Display for WithOptions<'_, ScaledQuantity> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.inner.approximate {
+            f.write_str("~")?;
+        }
+        self.inner.value.display_with(self.options).fmt(f)?;
+        if let Some(unit) = &self.inner.unit {
+            f.write_str(self.options.unit_spacing.as_str())?;
+            if self.options.ascii {
+                to_ascii(unit.text()).fmt(f)?;
+            } else {
+                unit.fmt(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
 impl Display for QuantityUnit {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.text)
@@ -375,6 +676,163 @@ impl From<String> for Value {
     }
 }
 
+/// Common phrases used as text values and their canonical form
+const DEFAULT_PHRASES: &[(&str, &[&str])] = &[
+    (
+        "to_taste",
+        &[
+            "to taste",
+            "taste",
+            "season to taste",
+            "according to taste",
+            "as desired",
+        ],
+    ),
+    ("pinch", &["pinch", "a pinch", "a small pinch"]),
+    ("dash", &["dash", "a dash"]),
+    ("splash", &["splash", "a splash"]),
+    ("handful", &["handful", "a handful"]),
+    ("as_needed", &["as needed", "as required", "if needed"]),
+    ("some", &["some", "a little", "a bit"]),
+];
+
+/// Table of text value phrases and their canonical form
+///
+/// Text values like `to taste` are free text, so the same thing can be
+/// written in many ways. This maps them to a canonical key, like `to_taste`,
+/// that can be used to group them or to show a localized text. The original
+/// [`Value::Text`] is not changed.
+///
+/// Phrases are matched ignoring case, extra whitespace and a trailing period.
+///
+/// ```
+/// # use cooklang::quantity::{TextPhrases, Value};
+/// let phrases = TextPhrases::default().with_phrase("al gusto", "to_taste");
+/// assert_eq!(phrases.canonical("To taste."), Some("to_taste"));
+/// assert_eq!(phrases.canonical("al gusto"), Some("to_taste"));
+/// assert_eq!(phrases.canonical("lots"), None);
+///
+/// let value = Value::from("a  pinch".to_string());
+/// assert_eq!(value.canonical_text(&phrases), Some("pinch"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct TextPhrases {
+    phrases: HashMap<String, String>,
+}
+
+impl TextPhrases {
+    /// Empty table
+    pub fn empty() -> Self {
+        Self {
+            phrases: HashMap::new(),
+        }
+    }
+
+    /// Add a phrase to the table
+    ///
+    /// If the phrase was already in the table, the canonical form is
+    /// replaced.
+    pub fn with_phrase(mut self, phrase: &str, canonical: impl Into<String>) -> Self {
+        self.add_phrase(phrase, canonical);
+        self
+    }
+
+    /// Same as [`Self::with_phrase`] but by reference
+    pub fn add_phrase(&mut self, phrase: &str, canonical: impl Into<String>) -> &mut Self {
+        self.phrases
+            .insert(normalize_phrase(phrase), canonical.into());
+        self
+    }
+
+    /// Get the canonical form of a phrase
+    pub fn canonical(&self, phrase: &str) -> Option<&str> {
+        self.phrases
+            .get(&normalize_phrase(phrase))
+            .map(String::as_str)
+    }
+}
+
+impl Default for TextPhrases {
+    fn default() -> Self {
+        let mut table = Self::empty();
+        for (canonical, phrases) in DEFAULT_PHRASES {
+            for phrase in *phrases {
+                table.add_phrase(phrase, *canonical);
+            }
+        }
+        table
+    }
+}
+
+fn normalize_phrase(phrase: &str) -> String {
+    phrase
+        .trim()
+        .trim_end_matches('.')
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// English number words for [`Value::from_number_word`]
+const NUMBER_WORDS: &[(&str, f64)] = &[
+    ("a", 1.0),
+    ("an", 1.0),
+    ("one", 1.0),
+    ("two", 2.0),
+    ("three", 3.0),
+    ("four", 4.0),
+    ("five", 5.0),
+    ("six", 6.0),
+    ("seven", 7.0),
+    ("eight", 8.0),
+    ("nine", 9.0),
+    ("ten", 10.0),
+    ("eleven", 11.0),
+    ("twelve", 12.0),
+    ("half", 0.5),
+    ("a half", 0.5),
+    ("one half", 0.5),
+    ("couple", 2.0),
+    ("a couple", 2.0),
+    ("a couple of", 2.0),
+    ("dozen", 12.0),
+    ("a dozen", 12.0),
+];
+
+impl Value {
+    /// Number for an English number word, like `two` or `a couple`
+    ///
+    /// The words are `one` to `twelve`, `a`/`an`, `half`, `a couple` and `a
+    /// dozen`. They are matched like the [`TextPhrases`], ignoring case and
+    /// extra whitespace. Any other text, like `some`, is [`None`].
+    ///
+    /// ```
+    /// # use cooklang::quantity::Value;
+    /// assert_eq!(Value::from_number_word("Two"), Some(Value::from(2.0)));
+    /// assert_eq!(Value::from_number_word("a couple"), Some(Value::from(2.0)));
+    /// assert_eq!(Value::from_number_word("half"), Some(Value::from(0.5)));
+    /// assert_eq!(Value::from_number_word("some"), None);
+    /// ```
+    pub fn from_number_word(text: &str) -> Option<Value> {
+        let text = normalize_phrase(text);
+        NUMBER_WORDS
+            .iter()
+            .find(|(word, _)| *word == text)
+            .map(|(_, n)| Value::from(*n))
+    }
+
+    /// Canonical form of a text value, if it's a known phrase
+    ///
+    /// See [`TextPhrases`]. Always [`None`] for numbers and ranges.
+    pub fn canonical_text<'a>(&self, phrases: &'a TextPhrases) -> Option<&'a str> {
+        match self {
+            Value::Text(text) => phrases.canonical(text),
+            _ => None,
+        }
+    }
+}
+
 /// Error during adding of quantities
 #[derive(Debug, Error)]
 pub enum QuantityAddError {
@@ -480,10 +938,37 @@ impl ScaledQuantity {
         let qty = Quantity {
             value,
             unit: self.unit.clone(), // unit is mantained
+            approximate: self.approximate || rhs.approximate,
+            unit_locked: self.unit_locked && rhs.unit_locked,
         };
 
         Ok(qty)
     }
+
+    /// Try to get the largest of two quantities
+    ///
+    /// The units are checked like in [`Self::try_add`], and the quantities
+    /// are compared in the unit of the first one. A range is compared by its
+    /// end. The largest quantity is returned as it is, with its own unit.
+    pub fn try_max(&self, rhs: &Self, converter: &Converter) -> Result<Self, QuantityAddError> {
+        let convert_to = self.compatible_unit(rhs, converter)?;
+
+        let mut converted = rhs.clone();
+        if let Some(to) = convert_to {
+            converted.convert(&to, converter)?;
+        };
+
+        let largest = |v: &Value| match v {
+            Value::Number(n) => Ok(n.value()),
+            Value::Range { end, .. } => Ok(end.value()),
+            Value::Text(_) => Err(TextValueError(v.clone())),
+        };
+        if largest(&converted.value)? > largest(&self.value)? {
+            Ok(rhs.clone())
+        } else {
+            Ok(self.clone())
+        }
+    }
 }
 
 pub trait TryAdd: Sized {
@@ -523,6 +1008,20 @@ impl TryAdd for Value {
     }
 }
 
+/// How [`GroupedQuantity`] combines the quantities with compatible units
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub enum AggregateMode {
+    /// Add them, the total that is used
+    #[default]
+    Sum,
+    /// Keep the largest one, like when the same pack is used again and only
+    /// the biggest has to be bought
+    ///
+    /// Quantities of different units of the same physical quantity are
+    /// converted to be compared.
+    Max,
+}
+
 /// Group of quantities
 ///
 /// This support efficient adding of new quantities, merging other groups..
@@ -555,9 +1054,35 @@ impl GroupedQuantity {
 
     /// Add a new quantity to the group
     pub fn add(&mut self, q: &ScaledQuantity, converter: &Converter) {
+        self.add_with_mode(q, converter, AggregateMode::Sum)
+    }
+
+    /// Same as [`Self::add`] but choosing how the quantities are combined
+    ///
+    /// ```
+    /// # use cooklang::{Converter, Quantity, Value};
+    /// # use cooklang::quantity::{AggregateMode, GroupedQuantity};
+    /// let converter = Converter::bundled();
+    /// let mut group = GroupedQuantity::empty();
+    /// for (value, unit) in [(500.0, "g"), (1.0, "kg"), (200.0, "g")] {
+    ///     let q = Quantity::new(Value::from(value), Some(unit.to_string()));
+    ///     group.add_with_mode(&q, &converter, AggregateMode::Max);
+    /// }
+    /// assert_eq!(group.to_string(), "1 kg");
+    /// ```
+    pub fn add_with_mode(
+        &mut self,
+        q: &ScaledQuantity,
+        converter: &Converter,
+        mode: AggregateMode,
+    ) {
         macro_rules! add {
             ($stored:expr, $quantity:ident, $converter:expr, $other:expr) => {
-                match $stored.try_add($quantity, $converter) {
+                let result = match mode {
+                    AggregateMode::Sum => $stored.try_add($quantity, $converter),
+                    AggregateMode::Max => $stored.try_max($quantity, $converter),
+                };
+                match result {
                     Ok(q) => *$stored = q,
                     Err(_) => {
                         $other.push($quantity.clone());
@@ -602,8 +1127,13 @@ impl GroupedQuantity {
 
     /// Merge the group with another one
     pub fn merge(&mut self, other: &Self, converter: &Converter) {
+        self.merge_with_mode(other, converter, AggregateMode::Sum)
+    }
+
+    /// Same as [`Self::merge`] but choosing how the quantities are combined
+    pub fn merge_with_mode(&mut self, other: &Self, converter: &Converter, mode: AggregateMode) {
         for q in other.iter() {
-            self.add(q, converter)
+            self.add_with_mode(q, converter, mode)
         }
     }
 
@@ -910,6 +1440,7 @@ impl Number {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::convert::System;
     use test_case::test_case;
 
     macro_rules! frac {
@@ -945,4 +1476,151 @@ mod tests {
         }
         num
     }
+
+    #[test_case(Value::from(1.5), Some("kg") => Some(1500.0) ; "mass")]
+    #[test_case(Value::from(250.0), Some("ml") => Some(0.25) ; "volume")]
+    #[test_case(Value::Range { start: 2.0.into(), end: 3.0.into() }, Some("min") => Some(120.0) ; "range")]
+    #[test_case(Value::from(1.0), None => None ; "no unit")]
+    #[test_case(Value::from(1.0), Some("handful") => None ; "unknown unit")]
+    #[test_case(Value::from("a bit".to_string()), Some("g") => None ; "text")]
+    fn to_base(value: Value, unit: Option<&str>) -> Option<f64> {
+        let converter = Converter::bundled();
+        let q = Quantity::new(value, unit.map(String::from));
+        q.to_base(&converter)
+    }
+
+    #[test_case(NumberLocale::PLAIN => "1234567.5" ; "plain")]
+    #[test_case(NumberLocale::EN => "1,234,567.5" ; "en")]
+    #[test_case(NumberLocale::FR => "1 234 567,5" ; "fr")]
+    #[test_case(NumberLocale::DE => "1.234.567,5" ; "de")]
+    fn localized_number(locale: NumberLocale) -> String {
+        let options = FormatOptions {
+            locale,
+            ..Default::default()
+        };
+        Number::Regular(1234567.5)
+            .display_with(&options)
+            .to_string()
+    }
+
+    #[test]
+    fn localized_small_numbers() {
+        let options = FormatOptions {
+            locale: NumberLocale::EN,
+            ..Default::default()
+        };
+        let fmt = |n: Number| n.display_with(&options).to_string();
+        assert_eq!(fmt(Number::Regular(123.0)), "123");
+        assert_eq!(fmt(Number::Regular(-1234.0)), "-1,234");
+        assert_eq!(
+            fmt(Number::Fraction {
+                whole: 1,
+                num: 1,
+                den: 2,
+                err: 0.0
+            }),
+            "1 1/2"
+        );
+        assert_eq!(NumberLocale::from_tag("fr-FR"), Some(NumberLocale::FR));
+        assert_eq!(NumberLocale::from_tag("pt_BR"), Some(NumberLocale::DE));
+        assert_eq!(NumberLocale::from_tag("xx"), None);
+    }
+
+    #[test]
+    fn unit_spacing() {
+        let q = Quantity::new(Value::from(5.0), Some("g".to_string()));
+        let fmt = |unit_spacing| {
+            let options = FormatOptions {
+                unit_spacing,
+                ..Default::default()
+            };
+            q.display_with(&options).to_string()
+        };
+        assert_eq!(fmt(UnitSpacing::Space), "5 g");
+        assert_eq!(fmt(UnitSpacing::None), "5g");
+        assert_eq!(q.to_string(), "5 g");
+    }
+
+    #[test]
+    fn ascii() {
+        let options = FormatOptions {
+            ascii: true,
+            ..Default::default()
+        };
+        let q = Quantity::new(Value::from(180.0), Some("°C".to_string()));
+        assert_eq!(q.display_with(&options).to_string(), "180 degC");
+        assert_eq!(q.to_string(), "180 °C");
+
+        let q = Quantity::new(Value::from("½".to_string()), Some("cup".to_string()));
+        assert_eq!(q.display_with(&options).to_string(), "1/2 cup");
+
+        let n = Number::Fraction {
+            whole: 1,
+            num: 1,
+            den: 2,
+            err: 0.0,
+        };
+        assert_eq!(n.display_with(&options).to_string(), "1 1/2");
+        assert_eq!(to_ascii("2–3 × 5 µg"), "2-3 x 5 ug");
+    }
+
+    #[test_case("1.5", "l", Some("ml") => "1500 ml" ; "preferred")]
+    #[test_case("1500", "ml", None => "1.5 l" ; "no preference")]
+    #[test_case("200", "ml", Some("l") => "0.2 l" ; "preferred bigger")]
+    #[test_case("2", "cup", Some("ml") => "473.176 ml" ; "other system")]
+    fn fit_with_preferences(value: &str, unit: &str, preferred: Option<&str>) -> String {
+        use crate::convert::{DisplayPreferences, PhysicalQuantity};
+
+        let converter = Converter::bundled();
+        let mut preferences = DisplayPreferences::default();
+        if let Some(preferred) = preferred {
+            preferences.set_unit(PhysicalQuantity::Volume, preferred);
+        }
+        let mut q = Quantity::new(
+            Value::from(value.parse::<f64>().unwrap()),
+            Some(unit.to_string()),
+        );
+        q.fit_with_preferences(&preferences, &converter).unwrap();
+        q.to_string()
+    }
+
+    #[test_case("to taste" => Some("to_taste") ; "plain")]
+    #[test_case("To Taste" => Some("to_taste") ; "case")]
+    #[test_case("  to   taste. " => Some("to_taste") ; "whitespace and period")]
+    #[test_case("season to taste" => Some("to_taste") ; "season")]
+    #[test_case("as desired" => Some("to_taste") ; "as desired")]
+    #[test_case("a pinch" => Some("pinch") ; "pinch")]
+    #[test_case("tasty" => None ; "unknown")]
+    fn canonical_text(text: &str) -> Option<&'static str> {
+        static PHRASES: Lazy<TextPhrases> = Lazy::new(TextPhrases::default);
+        Value::from(text.to_string()).canonical_text(&PHRASES)
+    }
+
+    #[test]
+    fn custom_text_phrases() {
+        let mut phrases = TextPhrases::empty();
+        phrases
+            .add_phrase("al gusto", "to_taste")
+            .add_phrase("to taste", "at_will");
+        assert_eq!(phrases.canonical("Al gusto"), Some("to_taste"));
+        assert_eq!(phrases.canonical("to taste"), Some("at_will"));
+        assert_eq!(phrases.canonical("a pinch"), None);
+        assert_eq!(Value::from(1.0).canonical_text(&phrases), None);
+    }
+
+    #[test_case("2", "cup", None => "2 c" ; "no preference")]
+    #[test_case("2", "cup", Some(System::Metric) => "473.176 ml" ; "to metric")]
+    #[test_case("1000", "ml", Some(System::Metric) => "1 l" ; "same system")]
+    #[test_case("1", "kg", Some(System::Imperial) => "35 oz" ; "to imperial")]
+    #[test_case("90", "min", Some(System::Imperial) => "1.5 h" ; "no system")]
+    fn fit_preferred_system(value: &str, unit: &str, system: Option<System>) -> String {
+        let mut converter = Converter::bundled();
+        converter.set_preferred_system(system);
+        let mut q = Quantity::new(
+            Value::from(value.parse::<f64>().unwrap()),
+            Some(unit.to_string()),
+        );
+        q.fit(&converter).unwrap();
+        q.to_string()
+    }
 }
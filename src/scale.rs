@@ -5,6 +5,8 @@ use thiserror::Error;
 
 use crate::{
     convert::Converter,
+    error::{PassResult, Severity, SourceDiag, SourceReport, Stage},
+    metadata::ServingsPoint,
     quantity::{ScalableQuantity, ScalableValue, ScaledQuantity, TextValueError, Value},
     Cookware, Ingredient, Quantity, ScalableRecipe, ScaledRecipe, Timer,
 };
@@ -15,6 +17,8 @@ pub struct ScaleTarget {
     base: u32,
     target: u32,
     index: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    factor: Option<f64>,
 }
 
 impl ScaleTarget {
@@ -33,12 +37,28 @@ impl ScaleTarget {
             base,
             target,
             index: declared_servings.iter().position(|&s| s == target),
+            factor: None,
+        }
+    }
+
+    /// Creates a [`ScaleTarget`] that scales by a factor
+    ///
+    /// `base` is the number of servings the recipe was initially written
+    /// for. The quantities are multiplied by `factor` as is, and the target
+    /// servings are `base * factor` rounded, with a minimum of 1. Values for
+    /// each of the servings can't be scaled by a factor.
+    pub fn with_factor(base: u32, factor: f64) -> Self {
+        ScaleTarget {
+            base,
+            target: ((base as f64 * factor).round() as u32).max(1),
+            index: None,
+            factor: Some(factor),
         }
     }
 
     /// Get the calculated scaling factor
     pub fn factor(&self) -> f64 {
-        self.target as f64 / self.base as f64
+        self.factor.unwrap_or(self.target as f64 / self.base as f64)
     }
 
     /// Get the index into a [`ScalableValue::ByServings`]
@@ -88,10 +108,93 @@ pub enum ScaleOutcome {
     Fixed,
     /// It has no quantity, so it can't be scaled
     NoQuantity,
+    /// Scaled and rounded to a whole number, see [`ScaleOptions::round_counts`]
+    Rounded {
+        /// The scaled value before rounding
+        exact: Value,
+    },
     /// Error scaling
     Error(#[serde(skip)] ScaleError),
 }
 
+/// Options for [`ScalableRecipe::scale_with_options`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ScaleOptions {
+    /// Base point when the servings are a range, see
+    /// [`ScalableRecipe::scale_to_servings`]
+    pub point: ServingsPoint,
+    /// Round the ingredients counted without a unit, like `@eggs{3}`, to whole
+    /// numbers
+    ///
+    /// Quantities with a unit are scaled as usual. A positive count never
+    /// rounds to 0. The outcome of a rounded ingredient is
+    /// [`ScaleOutcome::Rounded`] and there is a warning in the report of
+    /// [`ScalableRecipe::scale_with_options`].
+    pub round_counts: Option<CountRounding>,
+}
+
+/// How to round counts, see [`ScaleOptions::round_counts`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CountRounding {
+    /// To the closest whole number
+    #[default]
+    Nearest,
+    /// To the next whole number, to never have less than needed
+    Up,
+    /// To the previous whole number
+    Down,
+}
+
+impl CountRounding {
+    fn round(self, n: f64) -> f64 {
+        let rounded = match self {
+            CountRounding::Nearest => n.round(),
+            CountRounding::Up => n.ceil(),
+            CountRounding::Down => n.floor(),
+        };
+        if n > 0.0 {
+            rounded.max(1.0)
+        } else {
+            rounded
+        }
+    }
+
+    /// Rounds a value, returning the previous one if it changed
+    fn round_value(self, value: &mut Value) -> Option<Value> {
+        const EPSILON: f64 = 1e-9;
+        let (start, end) = match value {
+            Value::Number(n) => (n.value(), None),
+            Value::Range { start, end } => (start.value(), Some(end.value())),
+            Value::Text(_) => return None,
+        };
+        let round = |n: f64| {
+            let rounded = self.round(n);
+            // float error from scaling is not a fraction
+            if (rounded - n).abs() < EPSILON {
+                n.round()
+            } else {
+                rounded
+            }
+        };
+        let rounded = match end {
+            None => Value::Number(round(start).into()),
+            Some(end) => Value::Range {
+                start: round(start).into(),
+                end: round(end).into(),
+            },
+        };
+        let changed = match (&rounded, end) {
+            (Value::Number(n), None) => (n.value() - start).abs() >= EPSILON,
+            (Value::Range { start: s, end: e }, Some(end)) => {
+                (s.value() - start).abs() >= EPSILON || (e.value() - end).abs() >= EPSILON
+            }
+            _ => unreachable!(),
+        };
+        let exact = std::mem::replace(value, rounded);
+        changed.then_some(exact)
+    }
+}
+
 /// Possible errors during scaling process
 #[derive(Debug, Error, Clone, Default)]
 pub enum ScaleError {
@@ -124,24 +227,115 @@ impl ScalableRecipe {
     ///
     /// Note that this returns a [`ScaledRecipe`] wich doesn't implement this
     /// method. A recipe can only be scaled once.
+    ///
+    /// If the servings are a range, the start of the range is used as the
+    /// base. See [`scale_to_servings`](Self::scale_to_servings) to choose it.
     pub fn scale(self, target: u32, converter: &Converter) -> ScaledRecipe {
+        self.scale_to_servings(target, ServingsPoint::Min, converter)
+    }
+
+    /// Scale a recipe choosing the base point when the servings are a range
+    ///
+    /// With a range like `servings: 4-6`, `point` chooses which number of
+    /// servings the quantities are written for. Scaling to that same number
+    /// leaves the quantities unchanged. Without a range, this is the same as
+    /// [`scale`](Self::scale).
+    ///
+    /// ```
+    /// # use cooklang::{Converter, metadata::ServingsPoint};
+    /// let recipe = cooklang::parse(">> servings: 2-4\nAdd @water{300*%ml}.")
+    ///     .into_output()
+    ///     .unwrap();
+    /// let scaled = recipe.scale_to_servings(6, ServingsPoint::Mid, &Converter::empty());
+    /// assert_eq!(scaled.ingredients[0].quantity.as_ref().unwrap().to_string(), "600 ml");
+    /// ```
+    pub fn scale_to_servings(
+        self,
+        target: u32,
+        point: ServingsPoint,
+        converter: &Converter,
+    ) -> ScaledRecipe {
+        let options = ScaleOptions {
+            point,
+            ..Default::default()
+        };
+        self.scale_with_options(target, options, converter)
+            .unwrap_output()
+    }
+
+    /// Scale a recipe with [`ScaleOptions`]
+    ///
+    /// The output is always there. The report has a warning for every
+    /// ingredient rounded with [`ScaleOptions::round_counts`].
+    ///
+    /// ```
+    /// # use cooklang::{Converter, scale::{CountRounding, ScaleOptions, ScaleOutcome}};
+    /// let recipe = cooklang::parse(">> servings: 2\nBeat @eggs{3*} with @milk{100*%ml}.")
+    ///     .into_output()
+    ///     .unwrap();
+    /// let options = ScaleOptions {
+    ///     round_counts: Some(CountRounding::Up),
+    ///     ..Default::default()
+    /// };
+    /// let (scaled, report) = recipe
+    ///     .scale_with_options(3, options, &Converter::empty())
+    ///     .into_tuple();
+    /// let scaled = scaled.unwrap();
+    /// assert_eq!(scaled.ingredients[0].quantity.as_ref().unwrap().to_string(), "5");
+    /// assert_eq!(report.warnings().count(), 1);
+    /// assert_eq!(scaled.ingredients[1].quantity.as_ref().unwrap().to_string(), "150 ml");
+    /// let outcome = &scaled.scaled_data().unwrap().ingredients[0];
+    /// assert!(matches!(outcome, ScaleOutcome::Rounded { .. }));
+    /// ```
+    pub fn scale_with_options(
+        self,
+        target: u32,
+        options: ScaleOptions,
+        converter: &Converter,
+    ) -> PassResult<ScaledRecipe> {
+        let point = options.point;
         let target = if let Some(servings) = self.metadata.servings() {
             let base = servings.first().copied().unwrap_or(1);
             ScaleTarget::new(base, target, servings)
+        } else if let Some(range) = self.metadata.servings_range() {
+            let base = range.point(point);
+            ScaleTarget::new(base, target, &[base])
         } else {
             ScaleTarget::new(1, target, &[])
         };
 
         if target.index() == Some(0) {
-            return self.default_scale();
+            return PassResult::new(Some(self.default_scale()), SourceReport::empty());
         }
+        let (scaled, report) = self.scale_to_target(target, options, converter);
+        PassResult::new(Some(scaled), report)
+    }
 
+    fn scale_to_target(
+        self,
+        target: ScaleTarget,
+        options: ScaleOptions,
+        converter: &Converter,
+    ) -> (ScaledRecipe, SourceReport) {
+        let mut report = SourceReport::empty();
         let (ingredients, ingredient_outcomes): (Vec<_>, Vec<_>) = self
             .ingredients
             .into_iter()
             .map(|i| i.scale(target))
-            .map(|(mut i, o)| {
+            .map(|(mut i, mut o)| {
                 if let Some(q) = &mut i.quantity {
+                    if let (Some(rounding), ScaleOutcome::Scaled, None) =
+                        (options.round_counts, &o, &q.unit)
+                    {
+                        if let Some(exact) = rounding.round_value(&mut q.value) {
+                            report.push(SourceDiag::unlabeled(
+                                format!("Rounded {} from {exact} to {}", i.name, q.value),
+                                Severity::Warning,
+                                Stage::Analysis,
+                            ));
+                            o = ScaleOutcome::Rounded { exact };
+                        }
+                    }
                     let _ = q.fit(converter);
                 }
                 (i, o)
@@ -163,6 +357,14 @@ impl ScalableRecipe {
             })
             .unzip();
 
+        let mut sections = self.sections;
+        for yields in sections.iter_mut().filter_map(|s| s.yields.as_mut()) {
+            if let Ok(value) = linear_scale(yields.value.clone(), target.factor()) {
+                yields.value = value;
+                let _ = yields.fit(converter);
+            }
+        }
+
         let data = ScaledData {
             target,
             ingredients: ingredient_outcomes,
@@ -170,15 +372,98 @@ impl ScalableRecipe {
             timers: timer_outcomes,
         };
 
-        ScaledRecipe {
+        let scaled = ScaledRecipe {
             metadata: self.metadata,
-            sections: self.sections,
+            sections,
             ingredients,
             cookware,
             timers,
             inline_quantities: self.inline_quantities,
             data: Scaled::Scaled(data),
+        };
+        (scaled, report)
+    }
+
+    /// Scale a recipe by a factor, like `1.5` for one and a half times
+    ///
+    /// The quantities are multiplied by the factor as is, it is not rounded
+    /// to a whole number of servings. Values for each of the servings, like
+    /// `@flour{200|400%g}`, can't be scaled by a factor, so they keep the
+    /// first value and their outcome is an error. A factor of 1 is the same
+    /// as [`default_scale`](Self::default_scale).
+    ///
+    /// ```
+    /// # use cooklang::Converter;
+    /// let recipe = cooklang::parse("Add @water{300*%ml}.").into_output().unwrap();
+    /// let scaled = recipe.scale_by_factor(0.5, &Converter::empty());
+    /// assert_eq!(scaled.ingredients[0].quantity.as_ref().unwrap().to_string(), "150 ml");
+    /// ```
+    pub fn scale_by_factor(self, factor: f64, converter: &Converter) -> ScaledRecipe {
+        if factor == 1.0 {
+            return self.default_scale();
         }
+        let base = match (self.metadata.servings(), self.metadata.servings_range()) {
+            (Some(servings), _) => servings.first().copied().unwrap_or(1),
+            (None, Some(range)) => range.point(ServingsPoint::Min),
+            (None, None) => 1,
+        };
+        let target = ScaleTarget::with_factor(base, factor);
+        self.scale_to_target(target, ScaleOptions::default(), converter)
+            .0
+    }
+
+    /// Scale a recipe by the factor in its `default scale` metadata
+    ///
+    /// The recipe is scaled like with [`scale_by_factor`](Self::scale_by_factor),
+    /// so the factor doesn't have to give a whole number of servings. Without
+    /// the key, this is the same as [`default_scale`](Self::default_scale).
+    /// The factor used is in the [`ScaledData::target`] of the result.
+    ///
+    /// ```
+    /// # use cooklang::Converter;
+    /// let recipe = cooklang::parse(">> servings: 2\n>> default scale: 2\nAdd @water{300*%ml}.")
+    ///     .into_output()
+    ///     .unwrap();
+    /// assert_eq!(recipe.metadata.default_scale(), Some(2.0));
+    /// let scaled = recipe.apply_default_scale(&Converter::empty());
+    /// assert_eq!(scaled.ingredients[0].quantity.as_ref().unwrap().to_string(), "600 ml");
+    /// assert_eq!(scaled.scaled_data().unwrap().target.factor(), 2.0);
+    /// ```
+    pub fn apply_default_scale(self, converter: &Converter) -> ScaledRecipe {
+        match self.metadata.default_scale() {
+            Some(factor) => self.scale_by_factor(factor, converter),
+            None => self.default_scale(),
+        }
+    }
+
+    /// Scales the quantity of a single ingredient by a factor
+    ///
+    /// The recipe is not consumed or copied, so this is cheap to call every
+    /// time the factor changes, like with a slider. `index` is the position in
+    /// [`Recipe::ingredients`](crate::Recipe::ingredients).
+    ///
+    /// Fixed values and text are returned unchanged, and so are values with
+    /// one value for each of the servings, because they can't be scaled by any
+    /// factor. Returns [`None`] if there is no ingredient at `index` or it
+    /// has no quantity.
+    ///
+    /// ```
+    /// let recipe = cooklang::parse("Add @water{300*%ml} and @salt{1%tsp}.")
+    ///     .into_output()
+    ///     .unwrap();
+    /// let water = recipe.scaled_ingredient_quantity(0, 1.5).unwrap();
+    /// assert_eq!(water.to_string(), "450 ml");
+    /// let salt = recipe.scaled_ingredient_quantity(1, 1.5).unwrap();
+    /// assert_eq!(salt.to_string(), "1 tsp");
+    /// ```
+    pub fn scaled_ingredient_quantity(&self, index: usize, factor: f64) -> Option<ScaledQuantity> {
+        let quantity = self.ingredients.get(index)?.quantity.as_ref()?;
+        Some(ScaledQuantity {
+            value: quantity.value.scale_by(factor),
+            unit: quantity.unit.clone(),
+            approximate: quantity.approximate,
+            unit_locked: quantity.unit_locked,
+        })
     }
 
     /// Scale the recipe to the default values
@@ -267,6 +552,21 @@ impl Scale for ScalableValue {
     }
 }
 
+impl ScalableValue {
+    /// Scales the value by a factor
+    ///
+    /// Only [`Linear`](Self::Linear) numbers and ranges change. Anything else
+    /// is the default value, like in [`ScalableRecipe::default_scale`].
+    pub fn scale_by(&self, factor: f64) -> Value {
+        match self {
+            Self::Linear(value) => {
+                linear_scale(value.clone(), factor).unwrap_or_else(|_| value.clone())
+            }
+            _ => self.clone().default_scale(),
+        }
+    }
+}
+
 fn linear_scale(value: Value, factor: f64) -> Result<Value, ScaleError> {
     match value {
         Value::Number(n) => Ok(Value::Number((n.value() * factor).into())),
@@ -283,17 +583,34 @@ impl Scale for ScalableQuantity {
     type Output = ScaledQuantity;
 
     fn scale(self, target: ScaleTarget) -> (Self::Output, ScaleOutcome) {
-        let Self { value, unit } = self;
+        let Self {
+            value,
+            unit,
+            approximate,
+            unit_locked,
+        } = self;
         let (value, outcome) = value.scale(target);
-        let scaled = ScaledQuantity { value, unit };
+        let scaled = ScaledQuantity {
+            value,
+            unit,
+            approximate,
+            unit_locked,
+        };
         (scaled, outcome)
     }
 
     fn default_scale(self) -> Self::Output {
-        let Self { value, unit } = self;
+        let Self {
+            value,
+            unit,
+            approximate,
+            unit_locked,
+        } = self;
         Self::Output {
             value: value.default_scale(),
             unit,
+            approximate,
+            unit_locked,
         }
     }
 }
@@ -310,6 +627,7 @@ impl Scale for Ingredient<ScalableValue> {
             quantity,
             note: self.note,
             relation: self.relation,
+            prep_order: self.prep_order,
             modifiers: self.modifiers,
         };
         (scaled, outcome)
@@ -322,6 +640,7 @@ impl Scale for Ingredient<ScalableValue> {
             quantity: self.quantity.map(Quantity::default_scale),
             note: self.note,
             relation: self.relation,
+            prep_order: self.prep_order,
             modifiers: self.modifiers,
         }
     }
@@ -394,3 +713,169 @@ impl ScaledRecipe {
         matches!(self.data, Scaled::DefaultScaling)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Values of a quantity in the base unit, or the raw values if it can't be
+    /// converted. Text values are not checked.
+    fn base_values(q: &ScaledQuantity, converter: &Converter) -> Vec<f64> {
+        let unit = q.unit_text().map(String::from);
+        let to_base = |n: f64| {
+            Quantity::new(Value::from(n), unit.clone())
+                .to_base(converter)
+                .unwrap_or(n)
+        };
+        match &q.value {
+            Value::Number(n) => vec![to_base(n.value())],
+            Value::Range { start, end } => vec![to_base(start.value()), to_base(end.value())],
+            Value::Text(_) => vec![],
+        }
+    }
+
+    /// Scales a recipe to `target` servings and back, and checks that every
+    /// scaled ingredient and timer ends where it started
+    #[track_caller]
+    fn assert_round_trip(input: &str, target: u32) {
+        const TOLERANCE: f64 = 0.01;
+
+        let converter = Converter::bundled();
+        let recipe = crate::parse(input).into_output().unwrap();
+        let original = recipe.clone().default_scale();
+        let scaled = recipe.scale(target, &converter);
+        let data = scaled.scaled_data().expect("not scaled");
+        let inverse = 1.0 / data.target.factor();
+
+        let originals = original
+            .ingredients
+            .iter()
+            .map(|i| &i.quantity)
+            .chain(original.timers.iter().map(|t| &t.quantity));
+        let scaled_quantities = scaled
+            .ingredients
+            .iter()
+            .map(|i| &i.quantity)
+            .chain(scaled.timers.iter().map(|t| &t.quantity));
+        let outcomes = data.ingredients.iter().chain(data.timers.iter());
+
+        let mut lossy = Vec::new();
+        for ((original, scaled), outcome) in originals.zip(scaled_quantities).zip(outcomes) {
+            let (Some(original), Some(scaled), ScaleOutcome::Scaled) = (original, scaled, outcome)
+            else {
+                continue;
+            };
+            let back = ScaledQuantity::new(
+                linear_scale(scaled.value.clone(), inverse).unwrap(),
+                scaled.unit_text().map(String::from),
+            );
+            let expected = base_values(original, &converter);
+            let got = base_values(&back, &converter);
+            let matches = expected.len() == got.len()
+                && expected
+                    .iter()
+                    .zip(&got)
+                    .all(|(e, g)| (e - g).abs() <= e.abs() * TOLERANCE);
+            if !matches {
+                lossy.push(format!("{original} -> {scaled} -> {back}"));
+            }
+        }
+        assert!(lossy.is_empty(), "lossy round trip: {lossy:#?}");
+    }
+
+    #[test]
+    fn round_trip() {
+        let input = "\
+>> servings: 2
+>> [auto scale]: true
+Mix @flour{500%g}, @water{330%ml} and @salt{1.5%tsp}.
+Add @eggs{3} and @sugar{1-2%tbsp}. Rest for ~{45%min}.
+Finish with @butter{1/3%cup} and a @pinch of pepper{}.
+";
+        for target in [1, 3, 4, 7, 10] {
+            assert_round_trip(input, target);
+        }
+    }
+
+    #[test]
+    fn round_trip_unit_change() {
+        // fitting to a better unit must not lose the amount
+        assert_round_trip(">> servings: 1\n@flour{800*%g} and @salt{2*%tsp}", 5);
+        assert_round_trip(">> servings: 4\n@milk{1.5*%l}", 1);
+    }
+
+    #[test]
+    fn round_counts() {
+        let converter = Converter::bundled();
+        let input =
+            ">> servings: 2\nBeat @eggs{3*}, @yolks{1-3*} and @milk{250*%ml}.\nAdd @lemon{0.25*}.";
+        let scale = |rounding| {
+            let options = ScaleOptions {
+                round_counts: rounding,
+                ..Default::default()
+            };
+            let recipe = crate::parse(input).into_output().unwrap();
+            let (scaled, report) = recipe
+                .scale_with_options(3, options, &converter)
+                .into_tuple();
+            let scaled = scaled.unwrap();
+            assert_eq!(report.warnings().count(), report.iter().count());
+            let quantities = scaled
+                .ingredients
+                .iter()
+                .map(|i| i.quantity.as_ref().unwrap().to_string())
+                .collect::<Vec<_>>();
+            let rounded = scaled
+                .scaled_data()
+                .unwrap()
+                .ingredients
+                .iter()
+                .map(|o| matches!(o, ScaleOutcome::Rounded { .. }))
+                .collect::<Vec<_>>();
+            let warnings = report
+                .iter()
+                .map(|w| w.message.to_string())
+                .collect::<Vec<_>>();
+            (quantities, rounded, warnings)
+        };
+
+        let (quantities, rounded, warnings) = scale(None);
+        assert_eq!(quantities, ["4.5", "1.5-4.5", "375 ml", "0.375"]);
+        assert_eq!(rounded, [false; 4]);
+        assert!(warnings.is_empty());
+
+        let (quantities, rounded, warnings) = scale(Some(CountRounding::Nearest));
+        assert_eq!(quantities, ["5", "2-5", "375 ml", "1"]);
+        assert_eq!(rounded, [true, true, false, true]);
+        assert_eq!(
+            warnings,
+            [
+                "Rounded eggs from 4.5 to 5",
+                "Rounded yolks from 1.5-4.5 to 2-5",
+                "Rounded lemon from 0.375 to 1"
+            ]
+        );
+
+        let (quantities, _, _) = scale(Some(CountRounding::Down));
+        assert_eq!(quantities, ["4", "1-4", "375 ml", "1"]);
+    }
+
+    #[test]
+    fn range_scaling() {
+        let converter = Converter::bundled();
+        let recipe = crate::parse(">> servings: 1\nAdd @stock{2-3*%cup}.")
+            .into_output()
+            .unwrap();
+        let scaled = recipe.scale(2, &converter);
+        let q = scaled.ingredients[0].quantity.as_ref().unwrap();
+        assert_eq!(
+            q.value,
+            Value::Range {
+                start: 4.0.into(),
+                end: 6.0.into()
+            }
+        );
+        // fitted to the unit symbol, but not to another unit
+        assert_eq!(q.to_string(), "4-6 c");
+    }
+}
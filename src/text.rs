@@ -109,6 +109,17 @@ impl<'a> Text<'a> {
         s
     }
 
+    /// Get the text of all the fragments concatenated, keeping the line breaks
+    ///
+    /// Like [`Self::text`], but a soft break is rendered as written.
+    pub(crate) fn text_with_breaks(&self) -> Cow<'a, str> {
+        let mut s = Cow::default();
+        for f in self.fragments() {
+            s += f.text;
+        }
+        s
+    }
+
     /// Get the text trimmed (start and end)
     pub fn text_outer_trimmed(&self) -> Cow<'a, str> {
         match self.text() {
@@ -256,9 +267,28 @@ impl PartialEq for TextFragment<'_> {
     }
 }
 
+/// Replaces every run of whitespace with a single space, or with a single
+/// line break if the run has one
+pub fn collapse_whitespace(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut run = None;
+    for c in text.chars() {
+        if c.is_whitespace() {
+            if run != Some('\n') {
+                run = Some(if c == '\n' { '\n' } else { ' ' });
+            }
+        } else {
+            out.extend(run.take());
+            out.push(c);
+        }
+    }
+    out.extend(run);
+    out
+}
+
 #[cfg(test)]
 mod tests {
-    use super::Text;
+    use super::{collapse_whitespace, Text};
     use test_case::test_case;
 
     #[test_case("a b c" => "a b c"; "no trim")]
@@ -268,4 +298,11 @@ mod tests {
         let t = Text::from_str(t, 0);
         t.text_trimmed().into_owned()
     }
+
+    #[test_case("a b c" => "a b c"; "no runs")]
+    #[test_case("  a \t b  " => " a b "; "runs")]
+    #[test_case("a  \n  b \r\n\n c" => "a\nb\nc"; "line breaks")]
+    fn collapse(t: &str) -> String {
+        collapse_whitespace(t)
+    }
 }
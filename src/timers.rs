@@ -0,0 +1,389 @@
+//! Utilities to work with the timers of a recipe
+
+use std::{borrow::Cow, fmt::Display};
+
+use crate::{
+    convert::PhysicalQuantity,
+    model::{Content, Item, Timer},
+    quantity::{QuantityValue, ScaledQuantity, UnitInfo, Value},
+    Converter, Recipe, ScaledRecipe,
+};
+
+/// Characters that end the sentence around a timer
+const SENTENCE_END: [char; 5] = ['.', '!', '?', ';', '\n'];
+
+/// A timer with the sentence of the step it's in
+///
+/// Created from [`Recipe::timers_with_context`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimerContext<'a, V: QuantityValue> {
+    /// Index of the timer in [`Recipe::timers`]
+    pub index: usize,
+    /// The timer
+    pub timer: &'a Timer<V>,
+    /// Index of the section in [`Recipe::sections`]
+    pub section: usize,
+    /// Number of the step in the section, see [`Step::number`](crate::model::Step::number)
+    pub step: u32,
+    /// Text of the sentence around the timer, including the timer, like
+    /// `Simmer for 20 min`
+    pub text: String,
+}
+
+impl<D, V: QuantityValue + Display> Recipe<D, V> {
+    /// All the timers with the sentence of the step around them
+    ///
+    /// The sentence is built from the step items next to the timer, until the
+    /// end of a sentence. Components are written with their display name.
+    ///
+    /// ```
+    /// let recipe = cooklang::parse("Chop the @onion. Fry it in a #pan{} for ~{5%min}, then serve.")
+    ///     .into_output()
+    ///     .unwrap();
+    /// let timers = recipe.timers_with_context();
+    /// assert_eq!(timers.len(), 1);
+    /// assert_eq!(timers[0].step, 1);
+    /// assert_eq!(timers[0].text, "Fry it in a pan for 5 min, then serve");
+    /// ```
+    pub fn timers_with_context(&self) -> Vec<TimerContext<'_, V>> {
+        let mut timers = Vec::new();
+        for (section_index, section) in self.sections.iter().enumerate() {
+            for content in &section.content {
+                let Content::Step(step) = content else {
+                    continue;
+                };
+                for (pos, item) in step.items.iter().enumerate() {
+                    let &Item::Timer { index } = item else {
+                        continue;
+                    };
+                    timers.push(TimerContext {
+                        index,
+                        timer: &self.timers[index],
+                        section: section_index,
+                        step: step.number,
+                        text: self.sentence_around(&step.items, pos),
+                    });
+                }
+            }
+        }
+        timers
+    }
+
+    fn sentence_around(&self, items: &[Item], pos: usize) -> String {
+        let texts = items
+            .iter()
+            .map(|item| self.item_text(item))
+            .collect::<Vec<_>>();
+        let items = items
+            .iter()
+            .zip(&texts)
+            .map(|(item, text)| match item {
+                Item::Text { .. } => ItemText::Text(text),
+                _ => ItemText::Component(text),
+            })
+            .collect::<Vec<_>>();
+        sentence_around(&items, pos)
+    }
+
+    fn item_text<'a>(&'a self, item: &'a Item) -> Cow<'a, str> {
+        match item {
+            Item::Text { value } => value.into(),
+            Item::Ingredient { index } => self.ingredients[*index].display_name(),
+            Item::Cookware { index } => self.cookware[*index].display_name().into(),
+            Item::Timer { index } => {
+                let timer = &self.timers[*index];
+                match (&timer.quantity, &timer.name) {
+                    (Some(quantity), _) => quantity.to_string().into(),
+                    (None, Some(name)) => name.into(),
+                    (None, None) => "".into(),
+                }
+            }
+            Item::InlineQuantity { index } => self.inline_quantities[*index].to_string().into(),
+            Item::Link { text, .. } => text.into(),
+        }
+    }
+}
+
+/// Text of a step item for [`sentence_around`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemText<'a> {
+    /// Text of the step, where a sentence can end
+    Text(&'a str),
+    /// A component written as text, like a name or a quantity, that never
+    /// ends a sentence, even with a `.` like in `1.5 min`
+    Component(&'a str),
+}
+
+/// The sentence around the item at `pos` of a step, including it
+///
+/// This is what [`Recipe::timers_with_context`] uses, for steps that are not
+/// in a [`Recipe`]. The sentence ends at a `.`, `!`, `?`, `;` or a line
+/// break in the text items, and whitespace is collapsed.
+///
+/// ```
+/// # use cooklang::timers::{sentence_around, ItemText};
+/// let items = [
+///     ItemText::Text("Chop it. Fry for "),
+///     ItemText::Component("1.5 min"),
+///     ItemText::Text(", then serve. Enjoy."),
+/// ];
+/// assert_eq!(sentence_around(&items, 1), "Fry for 1.5 min, then serve");
+/// ```
+pub fn sentence_around(items: &[ItemText], pos: usize) -> String {
+    let mut before = String::new();
+    for item in items[..pos].iter().rev() {
+        match item {
+            ItemText::Text(text) => {
+                if let Some(end) = text.rfind(SENTENCE_END) {
+                    before.insert_str(0, &text[end + 1..]);
+                    break;
+                }
+                before.insert_str(0, text);
+            }
+            ItemText::Component(text) => before.insert_str(0, text),
+        }
+    }
+
+    let mut after = String::new();
+    for item in &items[pos + 1..] {
+        match item {
+            ItemText::Text(text) => {
+                if let Some(end) = text.find(SENTENCE_END) {
+                    after.push_str(&text[..end]);
+                    break;
+                }
+                after.push_str(text);
+            }
+            ItemText::Component(text) => after.push_str(text),
+        }
+    }
+
+    let (ItemText::Text(current) | ItemText::Component(current)) = items[pos];
+    let sentence = format!("{before}{current}{after}");
+    sentence.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+impl ScaledRecipe {
+    /// The timer with the longest duration
+    ///
+    /// Returns the duration in the base unit of time, seconds with the bundled
+    /// units, and the timer with its context. Timers without a time unit or
+    /// with a text value are skipped. For ranges, the end of the range is
+    /// used, the longest it can take. If many timers are the longest, the
+    /// first one is returned.
+    ///
+    /// ```
+    /// # use cooklang::Converter;
+    /// let recipe = cooklang::parse("Knead for ~{10%min}. Let it proof for ~{2%h}. Bake ~{45%min}.")
+    ///     .into_output()
+    ///     .unwrap()
+    ///     .default_scale();
+    /// let (seconds, timer) = recipe.longest_timer(&Converter::bundled()).unwrap();
+    /// assert_eq!(seconds, 7200.0);
+    /// assert_eq!(timer.text, "Let it proof for 2 h");
+    /// ```
+    pub fn longest_timer(&self, converter: &Converter) -> Option<(f64, TimerContext<'_, Value>)> {
+        let mut longest: Option<(f64, TimerContext<_>)> = None;
+        for timer in self.timers_with_context() {
+            let Some(duration) = timer
+                .timer
+                .quantity
+                .as_ref()
+                .and_then(|q| longest_duration(q, converter))
+            else {
+                continue;
+            };
+            if longest.as_ref().is_none_or(|(d, _)| duration > *d) {
+                longest = Some((duration, timer));
+            }
+        }
+        longest
+    }
+
+    /// Split of the timers time in active and passive time
+    ///
+    /// The heuristic only looks at the timers, the `time` metadata is not
+    /// used:
+    /// - A timer in a step without ingredients is a wait, like `Let it rest
+    ///   for ~{2%h}`, so it is passive time.
+    /// - Any other timer is active time, like `Fry the @onion for ~{5%min}`,
+    ///   where the cook has to pay attention.
+    ///
+    /// Durations are in the base unit of time, seconds with the bundled
+    /// units, and are skipped like in [`Self::longest_timer`]. A recipe
+    /// without timers has both times at 0.
+    ///
+    /// ```
+    /// # use cooklang::Converter;
+    /// let recipe = cooklang::parse("Fry the @onion for ~{5%min}.\n\nLet it rest for ~{1%h}.")
+    ///     .into_output()
+    ///     .unwrap()
+    ///     .default_scale();
+    /// let time = recipe.time_breakdown(&Converter::bundled());
+    /// assert_eq!(time.active, 300.0);
+    /// assert_eq!(time.passive, 3600.0);
+    /// ```
+    pub fn time_breakdown(&self, converter: &Converter) -> TimeBreakdown {
+        let mut breakdown = TimeBreakdown::default();
+        for section in &self.sections {
+            for content in &section.content {
+                let Content::Step(step) = content else {
+                    continue;
+                };
+                let has_ingredients = step
+                    .items
+                    .iter()
+                    .any(|item| matches!(item, Item::Ingredient { .. }));
+                let timers = step.items.iter().filter_map(|item| match *item {
+                    Item::Timer { index } => self.timers[index].quantity.as_ref(),
+                    _ => None,
+                });
+                breakdown.add_step(has_ingredients, timers, converter);
+            }
+        }
+        breakdown
+    }
+}
+
+/// Active and passive time of a recipe
+///
+/// Created from [`ScaledRecipe::time_breakdown`].
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TimeBreakdown {
+    /// Time of the steps that need attention
+    pub active: f64,
+    /// Time waiting
+    pub passive: f64,
+}
+
+impl TimeBreakdown {
+    /// Active plus passive time
+    pub fn total(&self) -> f64 {
+        self.active + self.passive
+    }
+
+    /// Adds the timers of a step, with the same heuristic as
+    /// [`ScaledRecipe::time_breakdown`]
+    ///
+    /// This is to use the heuristic with another representation of the
+    /// recipe. `timers` are the quantities of the timers in the step.
+    pub fn add_step<'a>(
+        &mut self,
+        has_ingredients: bool,
+        timers: impl IntoIterator<Item = &'a ScaledQuantity>,
+        converter: &Converter,
+    ) {
+        for quantity in timers {
+            let Some(duration) = quantity_duration(quantity, converter) else {
+                continue;
+            };
+            if has_ingredients {
+                self.active += duration;
+            } else {
+                self.passive += duration;
+            }
+        }
+    }
+}
+
+/// Duration of a timer in the base unit, if it has a number and a time unit
+/// Like [`quantity_duration`], but with the end of a range
+fn longest_duration(quantity: &ScaledQuantity, converter: &Converter) -> Option<f64> {
+    match quantity.value {
+        Value::Range { end, .. } => {
+            let mut end_quantity = quantity.clone();
+            end_quantity.value = Value::Number(end);
+            quantity_duration(&end_quantity, converter)
+        }
+        _ => quantity_duration(quantity, converter),
+    }
+}
+
+fn quantity_duration(quantity: &ScaledQuantity, converter: &Converter) -> Option<f64> {
+    let is_time = quantity.unit().is_some_and(|unit| {
+        matches!(
+            unit.unit_info_or_parse(converter),
+            UnitInfo::Known(unit) if unit.physical_quantity == PhysicalQuantity::Time
+        )
+    });
+    if !is_time {
+        return None;
+    }
+    quantity.to_base(converter)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Converter;
+
+    #[test]
+    fn timers_with_context() {
+        let recipe = crate::parse(
+            "Boil @water{1%l} and cook the @pasta ~{10%min}. Drain.\n\nLet it rest for ~rest{2%min}! Serve.",
+        )
+        .into_output()
+        .unwrap();
+        let timers = recipe
+            .timers_with_context()
+            .into_iter()
+            .map(|t| (t.step, t.text))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            timers,
+            [
+                (1, "Boil water and cook the pasta 10 min".to_string()),
+                (2, "Let it rest for 2 min".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn longest_timer() {
+        let converter = Converter::bundled();
+        let recipe = crate::parse(
+            "Rest ~{90%s}.\n\nWait ~{1-2%min}, then ~{a few%min}.\n\nDone ~{1.5%min}.",
+        )
+        .into_output()
+        .unwrap()
+        .default_scale();
+        let (duration, timer) = recipe.longest_timer(&converter).unwrap();
+        assert_eq!(duration, 120.0);
+        assert_eq!(timer.step, 2);
+
+        // a range is as long as its end
+        let recipe = crate::parse("Wait ~{10%min}.\n\nRest ~{5-30%min}.")
+            .into_output()
+            .unwrap()
+            .default_scale();
+        let (duration, timer) = recipe.longest_timer(&converter).unwrap();
+        assert_eq!(duration, 1800.0);
+        assert_eq!(timer.step, 2);
+
+        let recipe = crate::parse("Wait ~{a few%min} and ~{5%kg}.")
+            .into_output()
+            .unwrap()
+            .default_scale();
+        assert!(recipe.longest_timer(&converter).is_none());
+    }
+
+    #[test]
+    fn time_breakdown() {
+        let converter = Converter::bundled();
+        let recipe = crate::parse(
+            "Knead the @dough{} for ~{10%min}.\n\nProof ~{2%h} in a #bowl{}.\n\nWait ~{a few%min}.\n\nBake @bread{} ~{45%min}.",
+        )
+        .into_output()
+        .unwrap()
+        .default_scale();
+        let time = recipe.time_breakdown(&converter);
+        assert_eq!(time.active, 3300.0);
+        assert_eq!(time.passive, 7200.0);
+        assert_eq!(time.total(), 10500.0);
+
+        let recipe = crate::parse("Mix @flour.")
+            .into_output()
+            .unwrap()
+            .default_scale();
+        assert_eq!(recipe.time_breakdown(&converter), Default::default());
+    }
+}
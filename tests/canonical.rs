@@ -162,6 +162,7 @@ impl TestStepItem {
                 }
             }
             Item::InlineQuantity { index: _ } => panic!("Unexpected inline quantity"),
+            Item::Link { .. } => panic!("Unexpected link"),
         }
     }
 }
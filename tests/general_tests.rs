@@ -1,4 +1,10 @@
-use cooklang::{Content, CooklangParser, Extensions, Item};
+use cooklang::analysis::{CheckResult, MetadataHandler};
+use cooklang::parser::{ComponentMarkers, ComponentMarkersError};
+use cooklang::quantity::Value;
+use cooklang::{
+    Content, CooklangParser, DanglingReference, Extensions, IngredientReferenceTarget, Item,
+    ParseOptions,
+};
 use indoc::indoc;
 use test_case::test_case;
 
@@ -211,6 +217,43 @@ fn multiple_temperatures() {
     );
 }
 
+#[test_case(Extensions::all(), "Bake at 180-200ºC." => (Value::Range { start: 180.0.into(), end: 200.0.into() }, 1); "range")]
+#[test_case(Extensions::all(), "Bake at 180 – 200 ºC." => (Value::Range { start: 180.0.into(), end: 200.0.into() }, 1); "en dash")]
+#[test_case(Extensions::all(), "Bake at 180ºC." => (Value::Number(180.0.into()), 1); "single")]
+#[test_case(Extensions::all() - Extensions::RANGE_VALUES, "Bake at 180–200ºC." => (Value::Number(200.0.into()), 1); "no ranges")]
+fn temperature_ranges(extensions: Extensions, input: &str) -> (Value, usize) {
+    let parser = CooklangParser::new(extensions, Default::default());
+    let r = parser.parse(input).unwrap_output();
+    (
+        r.inline_quantities[0].value.clone(),
+        r.inline_quantities.len(),
+    )
+}
+
+#[test]
+fn adjacent_text_merged() {
+    // lone markers and temperatures split the text
+    let input = "Mix in a # bowl, then bake at 180ºC ~ covered with @foil{}.";
+    let r = CooklangParser::extended().parse(input).unwrap_output();
+    let Content::Step(first_step) = &r.sections[0].content[0] else {
+        panic!()
+    };
+    assert_eq!(
+        first_step.items,
+        vec![
+            Item::Text {
+                value: "Mix in a # bowl, then bake at ".into()
+            },
+            Item::InlineQuantity { index: 0 },
+            Item::Text {
+                value: " ~ covered with ".into()
+            },
+            Item::Ingredient { index: 0 },
+            Item::Text { value: ".".into() }
+        ]
+    );
+}
+
 #[test]
 fn no_steps_component_mode() {
     let input = indoc! {r#"
@@ -245,3 +288,833 @@ fn text_steps_extension() {
         [Content::Step(_)]
     ));
 }
+
+#[test]
+fn metadata_handlers() {
+    let input = indoc! {r#"
+        >> source: EXAMPLE.COM
+        >> difficulty: impossible
+        >> internal: secret
+        >> author: Someone
+    "#};
+    let options = ParseOptions {
+        metadata_handlers: [
+            (
+                "source".to_string(),
+                Box::new(|v: &str| (CheckResult::Ok, Some(v.to_lowercase()))) as MetadataHandler,
+            ),
+            (
+                "difficulty".to_string(),
+                Box::new(|v: &str| match v {
+                    "easy" | "medium" | "hard" => (CheckResult::Ok, Some(v.to_string())),
+                    _ => (
+                        CheckResult::Warning(vec!["Use easy, medium or hard".into()]),
+                        None,
+                    ),
+                }),
+            ),
+            (
+                "internal".to_string(),
+                Box::new(|_: &str| (CheckResult::Ok, None)),
+            ),
+        ]
+        .into(),
+        ..Default::default()
+    };
+    let (r, report) = CooklangParser::extended()
+        .parse_with_options(input, options)
+        .into_result()
+        .unwrap();
+    let warnings = report
+        .warnings()
+        .map(|w| w.message.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(warnings, ["Invalid value for metadata key 'difficulty'"]);
+    let source = r.metadata.map.get("source").map(String::as_str);
+    assert_eq!(source, Some("example.com"));
+    assert!(!r.metadata.map.contains_key("difficulty"));
+    assert!(!r.metadata.map.contains_key("internal"));
+    assert_eq!(
+        r.metadata.map.get("author").map(String::as_str),
+        Some("Someone")
+    );
+}
+
+#[test]
+fn strict_config_keys() {
+    let input = ">> [mdoe]: steps\nMix @flour.";
+    let parser = CooklangParser::extended();
+
+    let r = parser.parse(input);
+    let warning = r.report().warnings().next().unwrap();
+    assert_eq!(warning.message, "Unknown config metadata key: [mdoe]");
+    assert_eq!(warning.hints[0], "Did you mean '[mode]'?");
+    assert!(r.output().unwrap().metadata.map.contains_key("[mdoe]"));
+
+    let options = ParseOptions {
+        strict_config_keys: true,
+        ..Default::default()
+    };
+    let r = parser.parse_with_options(input, options);
+    let error = r.report().errors().next().unwrap();
+    assert_eq!(error.message, "Unknown config metadata key: [mdoe]");
+    assert_eq!(error.hints[0], "Did you mean '[mode]'?");
+
+    let options = ParseOptions {
+        strict_config_keys: true,
+        ..Default::default()
+    };
+    let r = parser.parse_with_options(">> [colour]: red\nMix @flour.", options);
+    let error = r.report().errors().next().unwrap();
+    assert_eq!(
+        error.hints,
+        ["Possible config keys are '[mode]', '[duplicate]' and '[auto scale]'"]
+    );
+}
+
+#[test_case("= A\nMix @flour.\n= B\nUse @&(=1)dough{}." => Ok(0); "previous section")]
+#[test_case("Mix @flour.\n= B\nUse @&(=1)dough{}." => Ok(0); "unnamed first section")]
+#[test_case("= A\n= B\nMix @flour.\n= C\nUse @&(=2)dough{}." => Ok(1); "empty named section counts")]
+#[test_case("= A\nMix @flour.\n= B\nUse @&(=~1)dough{}." => Ok(0); "relative section")]
+#[test_case("= A\nMix @flour.\n= B\nUse @&(=2)dough{}." => Err("Section 2 is the current section, only previous sections can be referenced".to_string()); "current section")]
+#[test_case("= A\nMix @flour.\n= B\nUse @&(=3)dough{}." => Err("The value has to be a previous section number: 1".to_string()); "future section")]
+#[test_case("Mix @flour.\n= B\nUse @&(=~2)dough{}." => Err("The recipe only has 1 sections before this one".to_string()); "relative out of bounds")]
+#[test_case("= Dough\nMix @flour.\n= Bake\nUse @&(=dough)dough{}." => Ok(0); "section name")]
+#[test_case("= Pre dough\nMix @flour.\n= Bake\nUse @&( = Pre dough )dough{}." => Ok(0); "section name with spaces")]
+#[test_case("= A\nMix @flour.\n= B\nUse @&(=C)dough{}." => Err("Previous sections are: `A`".to_string()); "missing section name")]
+#[test_case("= Dough\nMix @flour.\n= Dough\nKnead @salt.\n= Bake\nUse @&(=Dough)dough{}." => Err("There are many sections with this name, use the number instead: 1, 2".to_string()); "ambiguous section name")]
+#[test_case("= A\nMix @flour.\n= B\nUse @&(=B)dough{}." => Err("This is the current section, only previous sections can be referenced".to_string()); "current section name")]
+#[test_case("Mix @flour.\n\nUse @&(2)dough{}." => Err("Step 2 is the current step, only previous steps can be referenced".to_string()); "current step")]
+fn intermediate_section_refs(input: &str) -> Result<usize, String> {
+    let r = CooklangParser::extended().parse(input);
+    if let Some(err) = r.report().errors().next() {
+        return Err(err.hints[0].to_string());
+    }
+    let recipe = r.into_output().unwrap();
+    let dough = recipe
+        .ingredients
+        .iter()
+        .find(|i| i.name == "dough")
+        .unwrap();
+    match dough.relation.references_to() {
+        Some((index, IngredientReferenceTarget::Section)) => Ok(index),
+        other => panic!("unexpected relation: {other:?}"),
+    }
+}
+
+#[test]
+fn step_notes() {
+    let input = indoc! {"
+        Knead the @dough{} for ~{10%min}. > Wet your @hands so it doesn't stick.
+
+        > A text step, not a note.
+
+        Serve, 5 \\> 3.
+    "};
+    let recipe = CooklangParser::new(Extensions::all(), Default::default())
+        .parse(input)
+        .into_output()
+        .unwrap();
+    let notes = recipe.sections[0]
+        .content
+        .iter()
+        .map(|c| match c {
+            Content::Step(step) => step.note.as_deref(),
+            Content::Text(_) => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        notes,
+        [Some("Wet your @hands so it doesn't stick."), None, None]
+    );
+    assert_eq!(recipe.ingredients.len(), 1);
+
+    let recipe = CooklangParser::extended()
+        .parse("Mix. > Tip.")
+        .into_output()
+        .unwrap();
+    let Content::Step(step) = &recipe.sections[0].content[0] else {
+        panic!()
+    };
+    assert_eq!(step.note, None);
+}
+
+#[test_case("Mix @flour{500*%g} with @water{=flour}." => Ok("1500 g".to_string()); "scales along")]
+#[test_case("Mix @Flour{500%g} with @water{ = flour }." => Ok("500 g".to_string()); "case insensitive")]
+#[test_case("Mix @water{=flour} and @flour{500%g}." => Err("Relative quantity target not found: flour".to_string()); "not defined before")]
+#[test_case("Mix @flour{some} with @water{=flour}." => Err("Relative quantity target has no numeric quantity: flour".to_string()); "text target")]
+#[test_case("Mix @flour with @water{=flour}." => Err("Relative quantity target has no numeric quantity: flour".to_string()); "no quantity")]
+#[test_case("Mix @flour{500%g} with @water{=flour%ml}." => Err("Relative quantity with a unit".to_string()); "unit")]
+fn relative_quantities(input: &str) -> Result<String, String> {
+    let r = CooklangParser::new(Extensions::all(), Default::default()).parse(input);
+    if let Some(err) = r.report().errors().next() {
+        return Err(err.message.to_string());
+    }
+    let recipe = r
+        .into_output()
+        .unwrap()
+        .scale(3, &cooklang::Converter::empty());
+    let water = recipe
+        .ingredients
+        .iter()
+        .find(|i| i.name == "water")
+        .unwrap();
+    Ok(water.quantity.as_ref().unwrap().to_string())
+}
+
+#[test]
+fn prep_order_not_default() {
+    let recipe = CooklangParser::extended()
+        .parse("Fry the @onion{1}[#2].")
+        .into_output()
+        .unwrap();
+    assert_eq!(recipe.ingredients[0].prep_order, None);
+    assert_eq!(recipe.cookware[0].name, "2");
+}
+
+#[test]
+fn relative_quantities_not_default() {
+    let recipe = CooklangParser::extended()
+        .parse("Mix @water{=flour}.")
+        .into_output()
+        .unwrap();
+    assert_eq!(
+        recipe.ingredients[0].quantity.as_ref().unwrap().to_string(),
+        "=flour"
+    );
+}
+
+#[test]
+fn section_yields() {
+    let input = indoc! {"
+        >> servings: 2
+        == Meatballs {12%meatballs} ==
+        Mix @beef{500%g} and @breadcrumbs{50%g}.
+
+        == Sauce ==
+        Simmer @tomatoes{400%g}.
+    "};
+    let parser = CooklangParser::new(Extensions::all(), Default::default());
+    let recipe = parser.parse(input).into_output().unwrap();
+    let yields = recipe.sections[0].yields.as_ref().unwrap();
+    assert_eq!(yields.to_string(), "12 meatballs");
+    assert_eq!(recipe.sections[0].name.as_deref(), Some("Meatballs"));
+    assert_eq!(recipe.sections[1].yields, None);
+
+    let scaled = recipe.scale(3, &cooklang::Converter::empty());
+    let yields = scaled.sections[0].yields.as_ref().unwrap();
+    assert_eq!(yields.to_string(), "18 meatballs");
+
+    let r = parser.parse(">> servings: 2|4\n= Dough {1|2}\nMix.");
+    assert_eq!(
+        r.report().errors().next().unwrap().message,
+        "A section yield can't have a value for each serving"
+    );
+
+    let recipe = CooklangParser::extended()
+        .parse("= Dough {1%kg}\nMix.")
+        .into_output()
+        .unwrap();
+    assert_eq!(recipe.sections[0].name.as_deref(), Some("Dough {1%kg}"));
+    assert_eq!(recipe.sections[0].yields, None);
+}
+
+#[test]
+fn dangling_intermediate_refs() {
+    let input = indoc! {"
+        = Dough
+        Mix @flour{500%g} and @water{300%ml}.
+        Knead the @&(~1)dough{}.
+
+        = Bake
+        Shape the @&(=1)dough{} and bake.
+    "};
+    let mut recipe = CooklangParser::extended()
+        .parse(input)
+        .into_output()
+        .unwrap();
+    assert!(recipe.dangling_intermediate_refs().is_empty());
+
+    // the bake section is now the first one, so its reference is to itself
+    recipe.sections.remove(0);
+    assert_eq!(
+        recipe.dangling_intermediate_refs(),
+        [DanglingReference {
+            ingredient: 3,
+            target: IngredientReferenceTarget::Section,
+            target_index: 0,
+            location: Some((0, 0)),
+        }]
+    );
+}
+
+#[test]
+fn undefined_cookware_refs() {
+    let input = indoc! {"
+        Heat oil in a #pan.
+        Use the #&pan again and the #&wok.
+        Then the #&pot{}.
+    "};
+    let result = CooklangParser::extended().parse(input);
+    assert_eq!(result.report().errors().count(), 2);
+    let recipe = result.into_output().unwrap();
+    let names = recipe
+        .undefined_cookware_refs()
+        .into_iter()
+        .map(|index| recipe.cookware[index].name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["wok", "pot"]);
+}
+
+#[test]
+fn ingredient_density() {
+    let input = indoc! {"
+        >> density.flour: 0.53 g/ml
+        >> density.Milk: 1.03
+        Mix @flour{1%cup}, @milk{200%ml} and @sugar{100%ml}.
+    "};
+    let parser = CooklangParser::extended();
+    let recipe = parser.parse(input).into_output().unwrap().default_scale();
+    let grams = recipe
+        .ingredient_grams(parser.converter())
+        .into_iter()
+        .map(|(name, grams)| (name, grams.map(f64::round)))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        grams,
+        [
+            ("flour", Some(125.0)),
+            ("milk", Some(206.0)),
+            ("sugar", None)
+        ]
+    );
+
+    let r = parser.parse(">> density.flour: heavy\n>> density.salt: 2 g/kg\nMix @flour.");
+    let warnings = r
+        .report()
+        .warnings()
+        .map(|w| w.message.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        warnings,
+        ["Invalid density for 'flour'", "Invalid density for 'salt'"]
+    );
+}
+
+#[test]
+fn custom_component_markers() {
+    let markers = ComponentMarkers::new('$', '^', '!').unwrap();
+    let parser = CooklangParser::extended().with_markers(markers);
+    let recipe = parser
+        .parse("Email $alice{2} about ^laptop{} in !{5%min}. Costs \\$3 @ home.")
+        .into_output()
+        .unwrap();
+    assert_eq!(recipe.ingredients.len(), 1);
+    assert_eq!(recipe.ingredients[0].name, "alice");
+    assert_eq!(recipe.cookware[0].name, "laptop");
+    assert_eq!(recipe.timers.len(), 1);
+    let Content::Step(step) = &recipe.sections[0].content[0] else {
+        panic!()
+    };
+    let Item::Text { value } = step.items.last().unwrap() else {
+        panic!()
+    };
+    assert_eq!(value, ". Costs $3 @ home.");
+
+    // relative references use the timer marker too
+    let recipe = parser
+        .parse("Mix $flour{}.\n\nKnead $&(!1)dough{}.")
+        .into_output()
+        .unwrap();
+    assert_eq!(
+        recipe.ingredients[1].relation.references_to(),
+        Some((0, IngredientReferenceTarget::Step))
+    );
+    let r = parser.parse("Mix $flour{}.\n\nKnead $&(~1)dough{}.");
+    assert_eq!(
+        r.report().errors().next().unwrap().message,
+        "Invalid intermediate preparation reference"
+    );
+
+    assert_eq!(
+        ComponentMarkers::new('$', '%', '!'),
+        Err(ComponentMarkersError::Invalid('%'))
+    );
+    assert_eq!(
+        ComponentMarkers::new('$', '$', '!'),
+        Err(ComponentMarkersError::Duplicate('$'))
+    );
+}
+
+#[test]
+fn custom_unit_separator() {
+    let markers = ComponentMarkers::default()
+        .with_unit_separator('$')
+        .unwrap();
+    let parser = CooklangParser::extended().with_markers(markers);
+    let recipe = parser
+        .parse("Add @flour{200$g}, @milk{1/2%cup} and ~{10$min}. Costs 3$ each.")
+        .into_output()
+        .unwrap();
+    let unit =
+        |q: &Option<cooklang::Quantity<_>>| q.as_ref().unwrap().unit_text().map(String::from);
+    assert_eq!(unit(&recipe.ingredients[0].quantity), Some("g".into()));
+    assert_eq!(unit(&recipe.ingredients[1].quantity), Some("cup".into()));
+    assert_eq!(unit(&recipe.timers[0].quantity), Some("min".into()));
+
+    assert_eq!(
+        ComponentMarkers::default().with_unit_separator('*'),
+        Err(ComponentMarkersError::Invalid('*'))
+    );
+    assert_eq!(
+        ComponentMarkers::default().with_unit_separator('@'),
+        Err(ComponentMarkersError::Duplicate('@'))
+    );
+}
+
+#[test]
+fn approximate_quantities() {
+    let parser = CooklangParser::new(Extensions::all(), cooklang::Converter::bundled());
+    let input = ">> servings: 2\nAdd @flour{~200*%g} and @salt{1%tsp}, rest ~{~10%min}.";
+    let recipe = parser.parse(input).into_output().unwrap();
+    assert_eq!(recipe.timers.len(), 1);
+    assert!(recipe.timers[0].quantity.as_ref().unwrap().approximate);
+
+    let scaled = recipe.scale(4, parser.converter());
+    let quantity = |i: usize| scaled.ingredients[i].quantity.as_ref().unwrap();
+    assert!(quantity(0).approximate);
+    assert_eq!(quantity(0).to_string(), "~400 g");
+    assert!(!quantity(1).approximate);
+    assert_eq!(quantity(1).to_string(), "1 tsp");
+
+    let r = parser.parse("Use a #pan{~2}.");
+    assert_eq!(
+        r.report().errors().next().unwrap().message,
+        "Invalid cookware quantity: approximate marker"
+    );
+
+    let recipe = CooklangParser::extended()
+        .parse("Add @flour{~200%g}.")
+        .into_output()
+        .unwrap();
+    let quantity = recipe.ingredients[0].quantity.as_ref().unwrap();
+    assert!(!quantity.approximate);
+    assert_eq!(quantity.to_string(), "~200 g");
+}
+
+#[test]
+fn locked_units() {
+    let parser = CooklangParser::new(Extensions::all(), cooklang::Converter::bundled());
+    let input = ">> servings: 1\nMix @milk{250*%ml!} and @water{250*%ml}.";
+    let recipe = parser.parse(input).into_output().unwrap();
+
+    let mut converted = recipe.clone().default_scale();
+    let errors = converted.convert(cooklang::convert::System::Imperial, parser.converter());
+    assert!(errors.is_empty());
+    let unit = |r: &cooklang::ScaledRecipe, i: usize| {
+        r.ingredients[i]
+            .quantity
+            .as_ref()
+            .unwrap()
+            .unit_text()
+            .unwrap()
+            .to_string()
+    };
+    assert_eq!(unit(&converted, 0), "ml");
+    assert_ne!(unit(&converted, 1), "ml");
+
+    let scaled = recipe.scale(6, parser.converter());
+    assert_eq!(
+        scaled.ingredients[0].quantity.as_ref().unwrap().to_string(),
+        "1500 ml"
+    );
+    assert_eq!(
+        scaled.ingredients[1].quantity.as_ref().unwrap().to_string(),
+        "1.5 l"
+    );
+
+    let recipe = CooklangParser::extended()
+        .parse("Mix @milk{250%ml!}.")
+        .into_output()
+        .unwrap();
+    let quantity = recipe.ingredients[0].quantity.as_ref().unwrap();
+    assert_eq!(quantity.unit_text(), Some("ml!"));
+}
+
+#[test]
+fn default_scale_metadata() {
+    let parser = CooklangParser::extended();
+    let recipe = parser
+        .parse(">> servings: 4\n>> default scale: 0.5\nAdd @water{300*%ml} and @salt{1%tsp}.")
+        .into_output()
+        .unwrap();
+    assert_eq!(recipe.metadata.default_scale(), Some(0.5));
+    let scaled = recipe.apply_default_scale(parser.converter());
+    assert_eq!(scaled.scaled_data().unwrap().target.target_servings(), 2);
+    assert_eq!(
+        scaled.ingredients[0].quantity.as_ref().unwrap().to_string(),
+        "150 ml"
+    );
+    assert_eq!(
+        scaled.ingredients[1].quantity.as_ref().unwrap().to_string(),
+        "1 tsp"
+    );
+
+    let recipe = parser.parse("Add @water{300*%ml}.").into_output().unwrap();
+    assert!(recipe
+        .apply_default_scale(parser.converter())
+        .scaled_data()
+        .is_none());
+
+    // the factor is not rounded to whole servings
+    for (input, expected) in [
+        (">> default scale: 1.5\nAdd @water{300*%ml}.", "450 ml"),
+        (">> default scale: 0.5\nAdd @water{300*%ml}.", "150 ml"),
+        (
+            ">> servings: 1\n>> default scale: 0.5\nAdd @water{300*%ml}.",
+            "150 ml",
+        ),
+    ] {
+        let recipe = parser.parse(input).into_output().unwrap();
+        let scaled = recipe.apply_default_scale(parser.converter());
+        assert_eq!(
+            scaled.ingredients[0].quantity.as_ref().unwrap().to_string(),
+            expected,
+            "{input}"
+        );
+    }
+
+    for value in ["double", "0", "-2"] {
+        let r = parser.parse(&format!(
+            ">> default scale: {value}\nAdd @water{{300*%ml}}."
+        ));
+        assert!(r.report().has_errors(), "{value}");
+    }
+
+    // without special metadata it's only a warning
+    let r = CooklangParser::canonical().parse(
+        ">> default scale: large
+Add @water{300%ml}.",
+    );
+    assert!(!r.report().has_errors());
+    assert_eq!(
+        r.report().warnings().next().unwrap().message,
+        "Unsupported value for special key: 'default scale'"
+    );
+    assert_eq!(r.output().unwrap().metadata.default_scale(), None);
+}
+
+#[test]
+fn lenient_references() {
+    let input = "Add the @&flour{}.\n\nMix @flour{200%g} with @&flour{}.";
+    let parser = CooklangParser::extended();
+
+    let r = parser.parse(input);
+    assert_eq!(
+        r.report().errors().next().unwrap().message,
+        "Reference not found: flour"
+    );
+    assert!(r.valid_output().is_none());
+
+    let options = ParseOptions {
+        lenient_references: true,
+        ..Default::default()
+    };
+    let r = parser.parse_with_options(input, options);
+    assert!(!r.report().has_errors());
+    assert_eq!(
+        r.report().warnings().next().unwrap().message,
+        "Reference not found: flour"
+    );
+    let recipe = r.valid_output().unwrap();
+    assert!(recipe.ingredients[0].relation.is_definition());
+    assert!(recipe.ingredients[1].relation.is_definition());
+    assert_eq!(
+        recipe.ingredients[2].relation.references_to(),
+        Some((1, IngredientReferenceTarget::Ingredient))
+    );
+}
+
+#[test]
+fn infer_units_when_grouping() {
+    use cooklang::ingredient_list::{GroupOptions, IngredientList};
+
+    let parser = CooklangParser::extended();
+    let recipe = parser
+        .parse("Mix @flour{200%g} and @Flour{100}.\n\nAdd @salt{2} and @salt{a pinch}.")
+        .into_output()
+        .unwrap()
+        .default_scale();
+    let converter = parser.converter();
+    let totals = |list: IngredientList| {
+        list.into_iter()
+            .map(|(name, q)| (name, q.to_string()))
+            .collect::<Vec<_>>()
+    };
+
+    let mut list = IngredientList::new();
+    list.add_recipe(&recipe, converter);
+    assert_eq!(
+        totals(list),
+        [
+            ("Flour".to_string(), "100".to_string()),
+            ("flour".to_string(), "200 g".to_string()),
+            ("salt".to_string(), "a pinch, 2".to_string()),
+        ]
+    );
+
+    let options = GroupOptions {
+        infer_units: true,
+        ..Default::default()
+    };
+    let mut list = IngredientList::new();
+    list.add_recipe_with_options(&recipe, options, converter);
+    assert_eq!(
+        totals(list),
+        [
+            ("Flour".to_string(), "100 g".to_string()),
+            ("flour".to_string(), "200 g".to_string()),
+            ("salt".to_string(), "a pinch, 2".to_string()),
+        ]
+    );
+
+    let grouped = recipe.group_ingredients_with_options(converter, options);
+    let flour = grouped
+        .iter()
+        .find(|g| g.ingredient.name == "Flour")
+        .unwrap();
+    assert_eq!(flour.inferred_units, [flour.index]);
+    assert!(grouped
+        .iter()
+        .filter(|g| g.ingredient.name != "Flour")
+        .all(|g| g.inferred_units.is_empty()));
+}
+
+#[test]
+fn recipe_refs_as_ingredients() {
+    use cooklang::ingredient_list::{GroupOptions, IngredientList};
+
+    let parser = CooklangParser::extended();
+    let recipe = parser
+        .parse("Pour @./sauces/tomato sauce{200%ml} over the @pasta{}.")
+        .into_output()
+        .unwrap()
+        .default_scale();
+    let converter = parser.converter();
+    let names = |list: IngredientList| list.into_iter().map(|(name, _)| name).collect::<Vec<_>>();
+
+    let mut list = IngredientList::new();
+    list.add_recipe(&recipe, converter);
+    assert_eq!(names(list), ["./sauces/tomato sauce", "pasta"]);
+
+    let options = GroupOptions {
+        recipe_refs_as_ingredients: true,
+        ..Default::default()
+    };
+    let mut list = IngredientList::new();
+    list.add_recipe_with_options(&recipe, options, converter);
+    assert_eq!(names(list), ["pasta", "tomato sauce"]);
+}
+
+#[test]
+fn collapse_whitespace() {
+    let input =
+        "Mix   the  @flour{200%g}   with\t @water{}  .\nThen   rest.\n\n>   Some   text\n> in   two  lines";
+    let parser = CooklangParser::extended();
+    let text = |content: &Content| match content {
+        Content::Step(step) => step
+            .items
+            .iter()
+            .map(|item| match item {
+                Item::Text { value } => value.clone(),
+                _ => "_".to_string(),
+            })
+            .collect::<String>(),
+        Content::Text(text) => text.clone(),
+    };
+
+    let recipe = parser.parse(input).into_output().unwrap();
+    let content = &recipe.sections[0].content;
+    assert_eq!(text(&content[0]), "Mix   the  _   with\t _  . Then   rest.");
+
+    let options = ParseOptions {
+        collapse_whitespace: true,
+        ..Default::default()
+    };
+    let recipe = parser
+        .parse_with_options(input, options)
+        .into_output()
+        .unwrap();
+    let content = &recipe.sections[0].content;
+    assert_eq!(text(&content[0]), "Mix the _ with _ .\nThen rest.");
+    assert_eq!(text(&content[1]), "Some text\nin two lines");
+}
+
+#[test]
+fn ingredient_rows() {
+    use cooklang::convert::PhysicalQuantity;
+
+    let parser = CooklangParser::extended();
+    let input = indoc! {"
+        Boil @water{2%l} with @-salt{1%tsp}.
+
+        = Dressing
+        Mix @oil{3%glugs} and @lemon{half}.
+    "};
+    let recipe = parser.parse(input).into_output().unwrap().default_scale();
+    let rows = recipe.ingredient_rows(parser.converter());
+
+    let water = &rows[0];
+    assert_eq!(water.base_value, Some(2.0));
+    assert_eq!(water.physical_quantity, Some(PhysicalQuantity::Volume));
+    assert_eq!(water.step, Some(1));
+
+    let salt = &rows[1];
+    assert!(salt.hidden);
+    assert!(!salt.optional);
+
+    let oil = &rows[2];
+    assert_eq!(oil.value, Some(3.0));
+    assert_eq!(oil.unit, Some("glugs"));
+    assert_eq!(oil.base_value, None);
+    assert_eq!(oil.physical_quantity, None);
+    assert_eq!(oil.step, Some(2));
+
+    let lemon = &rows[3];
+    assert_eq!(lemon.value, None);
+    assert_eq!(lemon.unit, None);
+}
+
+#[test]
+fn detect_links() {
+    let input =
+        "See https://cooklang.org/docs. For more, (www.example.com/a_(b)) or http://x.y?q=1!";
+    let parser = CooklangParser::extended();
+
+    let recipe = parser.parse(input).into_output().unwrap();
+    let Content::Step(step) = &recipe.sections[0].content[0] else {
+        panic!()
+    };
+    assert_eq!(step.items.len(), 1);
+
+    let options = ParseOptions {
+        detect_links: true,
+        ..Default::default()
+    };
+    let recipe = parser
+        .parse_with_options(input, options)
+        .into_output()
+        .unwrap();
+    let Content::Step(step) = &recipe.sections[0].content[0] else {
+        panic!()
+    };
+    let link = |url: &str, text: &str| Item::Link {
+        url: url.to_string(),
+        text: text.to_string(),
+    };
+    let text = |value: &str| Item::Text {
+        value: value.to_string(),
+    };
+    assert_eq!(
+        step.items,
+        vec![
+            text("See "),
+            link("https://cooklang.org/docs", "https://cooklang.org/docs"),
+            text(". For more, ("),
+            link("https://www.example.com/a_(b)", "www.example.com/a_(b)"),
+            text(") or "),
+            link("http://x.y?q=1", "http://x.y?q=1"),
+            text("!"),
+        ]
+    );
+}
+
+#[test]
+fn combine_ingredients_max() {
+    use cooklang::ingredient_list::{combine_ingredients, IngredientList};
+    use cooklang::quantity::AggregateMode;
+
+    let parser = CooklangParser::extended();
+    let converter = parser.converter();
+    let list = |input: &str| {
+        let recipe = parser.parse(input).into_output().unwrap().default_scale();
+        IngredientList::from_recipe(&recipe, converter)
+    };
+    let lists = [
+        list("Use @milk{1%cup}, @eggs{2} and @salt{a pinch}."),
+        list("Use @milk{300%ml}, @eggs{3-4} and @stock{2%cubes}."),
+        list("Use @milk{100%ml}, @eggs{1} and @stock{1%cubes}."),
+    ];
+    let quantities = |list: &IngredientList| {
+        list.iter()
+            .map(|(name, q)| (name.clone(), q.to_string()))
+            .collect::<Vec<_>>()
+    };
+
+    let sum = combine_ingredients(lists.clone(), AggregateMode::Sum, converter);
+    let max = combine_ingredients(lists, AggregateMode::Max, converter);
+    let (sum, max) = (quantities(&sum), quantities(&max));
+    assert_eq!(sum[0], ("eggs".to_string(), "6-7".to_string()));
+    assert_eq!(max[0], ("eggs".to_string(), "3-4".to_string()));
+    // 1 cup is less than 300 ml
+    assert_eq!(max[1].1, "300 ml");
+    assert_eq!(sum[3], ("stock".to_string(), "3 cubes".to_string()));
+    assert_eq!(max[3], ("stock".to_string(), "2 cubes".to_string()));
+    assert_eq!(max[2], sum[2]);
+}
+
+#[test]
+fn number_words() {
+    let input = "Crack @eggs{two}, add @milk{a couple%cups}, @butter{half%stick} and @salt{some}.";
+    let parser = CooklangParser::extended();
+    let values = |options| {
+        let recipe = parser
+            .parse_with_options(input, options)
+            .into_output()
+            .unwrap();
+        recipe
+            .ingredients
+            .iter()
+            .map(|i| i.quantity.as_ref().unwrap().value.to_string())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        values(ParseOptions::default()),
+        ["two", "a couple", "half", "some"]
+    );
+    let options = ParseOptions {
+        number_words: true,
+        ..Default::default()
+    };
+    assert_eq!(values(options), ["2", "2", "0.5", "some"]);
+}
+
+#[test]
+fn ingredient_list_audit() {
+    let input = indoc! {r#"
+        >> [mode]: ingredients
+        @flour{500%g}
+        @salt{1%tsp}
+        @butter{50%g}
+
+        >> [mode]: steps
+        Mix the @flour with the @salt.
+
+        == Finish ==
+        Brush with @+egg{1} and add @&flour{} again.
+    "#};
+    let recipe = CooklangParser::extended()
+        .parse(input)
+        .into_output()
+        .unwrap();
+    let audit = recipe.ingredient_list_audit().unwrap();
+    let names = |indices: &[usize]| {
+        indices
+            .iter()
+            .map(|i| recipe.ingredients[*i].name.as_str())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(names(&audit.unused), ["butter"]);
+    assert_eq!(names(&audit.unlisted), ["egg"]);
+}
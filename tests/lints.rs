@@ -0,0 +1,242 @@
+use cooklang::analysis::Lints;
+use cooklang::{CooklangParser, ParseOptions};
+use indoc::indoc;
+
+fn lint_warnings(input: &str, lints: Lints) -> Vec<String> {
+    lint_warnings_with(
+        input,
+        ParseOptions {
+            lints,
+            ..Default::default()
+        },
+    )
+}
+
+fn lint_warnings_with(input: &str, options: ParseOptions) -> Vec<String> {
+    let parser = CooklangParser::extended();
+    let (_, report) = parser
+        .parse_with_options(input, options)
+        .into_result()
+        .unwrap();
+    report.warnings().map(|w| w.message.to_string()).collect()
+}
+
+#[test]
+fn duplicate_definitions() {
+    let input = indoc! {r#"
+        Add @salt to the water.
+        Season with @salt{1%tsp}.
+    "#};
+    assert!(lint_warnings(input, Lints::empty()).is_empty());
+    assert_eq!(
+        lint_warnings(input, Lints::DUPLICATE_DEFINITIONS),
+        vec!["Ingredient defined more than once: salt"]
+    );
+
+    let references = indoc! {r#"
+        Add @salt to the water.
+        Season with @&salt{1%tsp} and @+salt{2%g}.
+    "#};
+    assert!(!lint_warnings(references, Lints::DUPLICATE_DEFINITIONS)
+        .iter()
+        .any(|w| w.starts_with("Ingredient defined more than once")));
+}
+
+#[test]
+fn used_before_defined() {
+    let input = indoc! {r#"
+        Heat the @sauce in a pan.
+        Mix @tomato{2} and @garlic{1%clove} to make the @sauce{200%ml}.
+    "#};
+    assert!(lint_warnings(input, Lints::empty()).is_empty());
+    assert_eq!(
+        lint_warnings(input, Lints::USED_BEFORE_DEFINED),
+        vec!["Ingredient used before its quantity is defined: sauce"]
+    );
+
+    let defined_first = indoc! {r#"
+        Heat the @sauce{200%ml} in a pan.
+        Add more @sauce.
+    "#};
+    assert!(lint_warnings(defined_first, Lints::USED_BEFORE_DEFINED).is_empty());
+
+    let no_quantities = indoc! {r#"
+        Add @salt.
+        Add more @salt.
+    "#};
+    assert!(lint_warnings(no_quantities, Lints::USED_BEFORE_DEFINED).is_empty());
+}
+
+#[test]
+fn structured_metadata() {
+    let structured = |input: &str| {
+        lint_warnings(input, Lints::STRUCTURED_METADATA)
+            .into_iter()
+            .filter(|w| w.starts_with("Metadata value should be structured"))
+            .count()
+    };
+
+    let input = indoc! {r#"
+        >> servings: four
+        >> time: 1h30
+        >> tags: #vegan #quick
+        Mix @flour.
+    "#};
+    assert!(lint_warnings(input, Lints::empty())
+        .iter()
+        .all(|w| !w.starts_with("Metadata value should be structured")));
+    assert_eq!(structured(input), 3);
+
+    let valid = indoc! {r#"
+        >> servings: 4|6
+        >> time: 1 h 30 min
+        >> tags: vegan, quick
+        >> source: four
+        Mix @flour.
+    "#};
+    assert_eq!(structured(valid), 0);
+}
+
+#[test]
+fn structured_metadata_hints() {
+    let parser = CooklangParser::extended();
+    let options = ParseOptions {
+        lints: Lints::STRUCTURED_METADATA,
+        ..Default::default()
+    };
+    let input = indoc! {r#"
+        >> servings: serves 4 to 6 people
+        >> prep time: 0:45
+        Mix @flour.
+    "#};
+    let (_, report) = parser
+        .parse_with_options(input, options)
+        .into_result()
+        .unwrap();
+    let hints = report
+        .warnings()
+        .filter(|w| w.message.starts_with("Metadata value should be structured"))
+        .flat_map(|w| w.hints.iter().map(|h| h.to_string()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        hints,
+        vec![
+            "Write it as 'servings: 4|6'",
+            "Write it as 'prep time: 45 min'"
+        ]
+    );
+
+    for (servings, hint) in [
+        ("a dozen", Some("Write it as 'servings: 12'")),
+        ("a family of four", Some("Write it as 'servings: 4'")),
+        ("half", None),
+    ] {
+        let input = format!(">> servings: {servings}\nMix @flour.");
+        let (_, report) = parser
+            .parse_with_options(
+                &input,
+                ParseOptions {
+                    lints: Lints::STRUCTURED_METADATA,
+                    ..Default::default()
+                },
+            )
+            .into_result()
+            .unwrap();
+        let hints = report
+            .warnings()
+            .filter(|w| w.message.starts_with("Metadata value should be structured"))
+            .flat_map(|w| w.hints.iter().map(|h| h.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(hints.first().map(String::as_str), hint, "{servings}");
+    }
+}
+
+#[test]
+fn auto_scale_markers() {
+    const WARNING: &str = "Every ingredient quantity has the auto scale marker";
+
+    let input = indoc! {r#"
+        Mix @flour{500*%g}, @water{300*%ml} and @salt.
+        Add @yeast{7*%g}.
+    "#};
+    assert!(lint_warnings(input, Lints::empty()).is_empty());
+    assert_eq!(
+        lint_warnings(input, Lints::AUTO_SCALE_MARKERS),
+        vec![WARNING]
+    );
+
+    let unmarked = indoc! {r#"
+        Mix @flour{500*%g}, @water{300*%ml} and @salt{1%pinch}.
+    "#};
+    assert!(lint_warnings(unmarked, Lints::AUTO_SCALE_MARKERS).is_empty());
+
+    let enabled = indoc! {r#"
+        >> [auto scale]: true
+        Mix @flour{500%g} and @water{300%ml}.
+    "#};
+    assert!(!lint_warnings(enabled, Lints::AUTO_SCALE_MARKERS).contains(&WARNING.to_string()));
+}
+
+#[test]
+fn long_steps() {
+    let long = "Stir the @sauce{} slowly. ".repeat(30);
+    let input = format!("Boil the @water{{2%l}}.\n\n{long}");
+    assert!(lint_warnings(&input, Lints::empty()).is_empty());
+    assert_eq!(
+        lint_warnings(&input, Lints::LONG_STEPS),
+        vec!["Step is too long: 690 characters"]
+    );
+
+    let options = |max| ParseOptions {
+        lints: Lints::LONG_STEPS,
+        max_step_length: Some(max),
+        ..Default::default()
+    };
+    assert!(lint_warnings_with(&input, options(1000)).is_empty());
+    assert_eq!(lint_warnings_with(&input, options(10)).len(), 2);
+}
+
+#[test]
+fn reference_quantities() {
+    const WARNING: &str = "References use more than the definition: flour";
+
+    let input = indoc! {r#"
+        Weigh @flour{500%g}.
+        Mix @&flour{300%g} with the water.
+        Dust with @&flour{0.25%kg}.
+    "#};
+    assert!(lint_warnings(input, Lints::empty()).is_empty());
+    assert_eq!(
+        lint_warnings(input, Lints::REFERENCE_QUANTITIES),
+        vec![WARNING]
+    );
+
+    let enough = indoc! {r#"
+        Weigh @flour{500%g}.
+        Mix @&flour{300%g} and dust with @&flour{200%g}.
+    "#};
+    assert!(lint_warnings(enough, Lints::REFERENCE_QUANTITIES).is_empty());
+
+    let mixed = indoc! {r#"
+        Weigh @flour{500%g}.
+        Mix @&flour{3%cups} and dust with @&flour{200%g}.
+    "#};
+    assert!(!lint_warnings(mixed, Lints::REFERENCE_QUANTITIES).contains(&WARNING.to_string()));
+}
+
+#[test]
+fn mixed_unit_systems() {
+    let input = indoc! {r#"
+        Mix @flour{500%g} with @milk{1%cup}, @butter{50%grams} and @salt{1%tsp}.
+    "#};
+    assert!(lint_warnings(input, Lints::empty()).is_empty());
+    assert_eq!(
+        lint_warnings(input, Lints::MIXED_UNIT_SYSTEMS),
+        vec!["Ingredient units mix metric (g) and imperial (c, tsp)"]
+    );
+
+    let metric = indoc! {r#"
+        Mix @flour{500%g} with @milk{250%ml} and @salt{1%pinch}.
+    "#};
+    assert!(lint_warnings(metric, Lints::MIXED_UNIT_SYSTEMS).is_empty());
+}
@@ -0,0 +1,60 @@
+#![cfg(feature = "metadata_schema")]
+
+use cooklang::analysis::MetadataSchema;
+use cooklang::{CooklangParser, ParseOptions};
+use indoc::indoc;
+use serde_json::json;
+
+fn schema_errors(input: &str, schema: &MetadataSchema) -> Vec<String> {
+    let options = ParseOptions {
+        metadata_schema: Some(schema),
+        ..Default::default()
+    };
+    let result = CooklangParser::extended().parse_with_options(input, options);
+    result
+        .report()
+        .errors()
+        .map(|e| e.message.to_string())
+        .collect()
+}
+
+#[test]
+fn metadata_schema() {
+    let schema = MetadataSchema::new(&json!({
+        "type": "object",
+        "required": ["title", "servings"],
+        "properties": {
+            "servings": { "type": "string", "pattern": "^[0-9]+$" },
+            "course": { "enum": ["starter", "main", "dessert"] }
+        }
+    }))
+    .unwrap();
+
+    let valid = indoc! {r#"
+        >> title: Soup
+        >> servings: 4
+        >> course: starter
+        Boil @water.
+    "#};
+    assert!(schema_errors(valid, &schema).is_empty());
+
+    let invalid = indoc! {r#"
+        >> servings: 4
+        >> course: breakfast
+        Boil @water.
+    "#};
+    let mut errors = schema_errors(invalid, &schema);
+    errors.sort();
+    assert_eq!(
+        errors,
+        [
+            "Metadata does not match the schema: 'course'",
+            "Metadata does not match the schema: 'title'",
+        ]
+    );
+}
+
+#[test]
+fn invalid_schema() {
+    assert!(MetadataSchema::new(&json!({ "type": "not a type" })).is_err());
+}